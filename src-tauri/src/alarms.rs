@@ -0,0 +1,959 @@
+//! Alarms and Timers
+//!
+//! Alarms (a time of day, once or on chosen weekdays) and countdown timers are
+//! kept in ~/.config/honeybee/alarms.json and run on a scheduler thread, so
+//! they keep going with the UI closed and survive a reboot. Anything that came
+//! due while the app was down rings on start if it's at most
+//! `MISSED_GRACE_SECS` late; older ones are skipped rather than going off
+//! hours late.
+//!
+//! When one goes off it's emitted as `alarm-fired` and rings on the default
+//! output: the alarm's sound file (or a built-in beep) loops with music paused
+//! and the system volume set to the alarm volume, until it's dismissed,
+//! snoozed or has rung for `ring_minutes`. Both the volume and the music are
+//! restored afterwards. The volume goes through ducking.rs, so an alarm during
+//! a voice session and the session's ducking don't undo each other.
+//! `alarms-changed` is emitted whenever the list changes.
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeZone};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SampleFormat, SizedSample,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    f32::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::decode::{AudioDecoder, Resampler};
+use crate::ducking;
+use crate::fsutil::write_atomic;
+use crate::player::{self, PlaybackStatus};
+
+/// How often the scheduler checks for due alarms
+const TICK: Duration = Duration::from_millis(500);
+
+/// Alarms missed while the app was down still ring if at most this late
+const MISSED_GRACE_SECS: i64 = 10 * 60;
+
+/// Longest part of a sound file that is loaded and looped
+const MAX_SOUND_SECS: u64 = 60;
+
+/// Ringing starts quieter and reaches full level after this long
+const RAMP_SECS: f32 = 10.0;
+
+const MAX_LABEL_LEN: usize = 100;
+
+const MAX_TIMER_SECS: u64 = 24 * 60 * 60;
+
+lazy_static::lazy_static! {
+    static ref ALARM_STORE: Mutex<AlarmStore> = Mutex::new(load_store());
+    static ref SCHEDULER: Mutex<Option<mpsc::Sender<SchedulerCommand>>> = Mutex::new(None);
+}
+
+/// For alarm and timer ids
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Self {
+        match day {
+            chrono::Weekday::Mon => Weekday::Monday,
+            chrono::Weekday::Tue => Weekday::Tuesday,
+            chrono::Weekday::Wed => Weekday::Wednesday,
+            chrono::Weekday::Thu => Weekday::Thursday,
+            chrono::Weekday::Fri => Weekday::Friday,
+            chrono::Weekday::Sat => Weekday::Saturday,
+            chrono::Weekday::Sun => Weekday::Sunday,
+        }
+    }
+}
+
+impl From<Weekday> for chrono::Weekday {
+    fn from(day: Weekday) -> Self {
+        match day {
+            Weekday::Monday => chrono::Weekday::Mon,
+            Weekday::Tuesday => chrono::Weekday::Tue,
+            Weekday::Wednesday => chrono::Weekday::Wed,
+            Weekday::Thursday => chrono::Weekday::Thu,
+            Weekday::Friday => chrono::Weekday::Fri,
+            Weekday::Saturday => chrono::Weekday::Sat,
+            Weekday::Sunday => chrono::Weekday::Sun,
+        }
+    }
+}
+
+/// Alarm fields set by the user
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlarmSettings {
+    #[serde(default)]
+    pub label: String,
+    pub hour: u8,
+    pub minute: u8,
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    #[serde(default)]
+    pub volume: Option<u8>,
+    #[serde(default)]
+    pub sound: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alarm {
+    pub id: String,
+    pub label: String,
+    pub hour: u8,
+    pub minute: u8,
+    /// Weekdays it repeats on, empty rings once and then disables itself
+    pub days: Vec<Weekday>,
+    pub enabled: bool,
+    /// System volume while ringing (None = the default)
+    pub volume: Option<u8>,
+    /// Sound file (None = the default)
+    pub sound: Option<String>,
+    /// Rings again at this time (unix seconds)
+    pub snoozed_until: Option<i64>,
+    /// Next time it goes off (unix seconds), filled in when listed
+    #[serde(skip_deserializing)]
+    pub next_fire: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timer {
+    pub id: String,
+    pub label: String,
+    pub duration_ms: u64,
+    /// When it goes off (unix ms), None while paused
+    pub ends_at: Option<i64>,
+    /// Time left when paused
+    pub remaining_ms: u64,
+}
+
+/// Used when an alarm doesn't set its own, and for timers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlarmDefaults {
+    /// System volume while ringing (0-100)
+    pub volume: u8,
+    /// Sound file (None = built-in beep)
+    pub sound: Option<String>,
+    pub snooze_minutes: u32,
+    /// Stop ringing after this long if nobody reacts
+    pub ring_minutes: u32,
+}
+
+impl Default for AlarmDefaults {
+    fn default() -> Self {
+        Self {
+            volume: 70,
+            sound: None,
+            snooze_minutes: 9,
+            ring_minutes: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AlarmStore {
+    defaults: AlarmDefaults,
+    alarms: Vec<Alarm>,
+    timers: Vec<Timer>,
+    /// Ringing right now, not persisted
+    #[serde(skip)]
+    ringing: Vec<FiredAlarm>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlarmKind {
+    Alarm,
+    Timer,
+}
+
+/// Sent with `alarm-fired`
+#[derive(Debug, Clone, Serialize)]
+pub struct FiredAlarm {
+    pub id: String,
+    pub kind: AlarmKind,
+    pub label: String,
+    /// Unix seconds
+    pub fired_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Dismissed,
+    Snoozed,
+    /// Rang for `ring_minutes` without a reaction
+    TimedOut,
+}
+
+/// Sent with `alarm-stopped`
+#[derive(Debug, Clone, Serialize)]
+pub struct AlarmStopped {
+    pub id: String,
+    pub reason: StopReason,
+}
+
+/// Sent with `alarms-changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct AlarmStatus {
+    pub alarms: Vec<Alarm>,
+    pub timers: Vec<Timer>,
+    pub ringing: Vec<FiredAlarm>,
+}
+
+enum SchedulerCommand {
+    /// Alarms or timers were edited
+    Changed,
+    Snooze(String),
+    Dismiss(String),
+}
+
+fn store_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/alarms.json"))
+        .unwrap_or_else(|| PathBuf::from("alarms.json"))
+}
+
+fn load_store() -> AlarmStore {
+    fs::read_to_string(store_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(store: &AlarmStore) -> Result<(), String> {
+    let path = store_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(store).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to save alarms: {}", e))
+}
+
+fn new_id() -> String {
+    format!(
+        "{:x}{:x}",
+        Local::now().timestamp_millis(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// First time strictly after `after` that the alarm's schedule goes off,
+/// ignoring snooze. Times skipped by a DST change don't ring that day.
+fn next_occurrence(alarm: &Alarm, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    let time = NaiveTime::from_hms_opt(alarm.hour as u32, alarm.minute as u32, 0)?;
+    (0..=7).find_map(|offset| {
+        let date = after.date_naive().checked_add_days(Days::new(offset))?;
+        if !alarm.days.is_empty() && !alarm.days.contains(&date.weekday().into()) {
+            return None;
+        }
+        Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .filter(|at| *at > after)
+    })
+}
+
+fn next_fire(alarm: &Alarm, now: DateTime<Local>) -> Option<i64> {
+    if !alarm.enabled {
+        return None;
+    }
+    let scheduled = next_occurrence(alarm, now).map(|at| at.timestamp());
+    match (scheduled, alarm.snoozed_until) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn status(store: &AlarmStore) -> AlarmStatus {
+    let now = Local::now();
+    AlarmStatus {
+        alarms: store
+            .alarms
+            .iter()
+            .map(|alarm| Alarm {
+                next_fire: next_fire(alarm, now),
+                ..alarm.clone()
+            })
+            .collect(),
+        timers: store.timers.clone(),
+        ringing: store.ringing.clone(),
+    }
+}
+
+// ============================================================================
+// RINGING
+// ============================================================================
+
+/// Something that came due, with how to ring it
+struct Due {
+    fired: FiredAlarm,
+    volume: u8,
+    sound: Option<String>,
+}
+
+/// Two short beeps a second, the default alarm sound
+fn beep(sample_rate: u32, channels: usize) -> Vec<f32> {
+    const BEEP_HZ: f32 = 880.0;
+    const PATTERN: [(f32, bool); 4] = [(0.1, true), (0.1, false), (0.1, true), (0.7, false)];
+    let rate = sample_rate as f32;
+    let edge = rate * 0.005;
+
+    let mut samples = Vec::new();
+    for (secs, on) in PATTERN {
+        let frames = (secs * rate) as usize;
+        for i in 0..frames {
+            let value = if on {
+                // Short fade in and out, a hard edge clicks
+                let envelope = (i as f32 / edge).min((frames - i) as f32 / edge).min(1.0);
+                (2.0 * PI * BEEP_HZ * i as f32 / rate).sin() * 0.5 * envelope
+            } else {
+                0.0
+            };
+            samples.extend(std::iter::repeat_n(value, channels));
+        }
+    }
+    samples
+}
+
+/// Decode up to `MAX_SOUND_SECS` of a file in the output format
+fn load_sound(path: &Path, sample_rate: u32, channels: usize) -> Result<Vec<f32>, String> {
+    let mut decoder = AudioDecoder::open(path)?;
+    let in_channels = decoder.channels().max(1) as usize;
+    let max_samples = (decoder.sample_rate() as u64 * MAX_SOUND_SECS) as usize * in_channels;
+    let mut resampler = Resampler::new(decoder.sample_rate(), sample_rate, channels);
+
+    let mut block = Vec::new();
+    let mut mapped = Vec::new();
+    let mut decoded = 0;
+    let mut samples = Vec::new();
+    while decoded < max_samples && decoder.next_block(&mut block)? {
+        decoded += block.len();
+        player::map_channels(&block, in_channels, channels, &mut mapped);
+        resampler.process(&mapped, &mut samples);
+    }
+    resampler.flush(&mut samples);
+
+    if samples.is_empty() {
+        return Err(format!("No audio in {}", path.display()));
+    }
+    Ok(samples)
+}
+
+/// Loops a sound on the default output until dropped.
+///
+/// This is a stream of its own rather than a source in the player engine: the
+/// engine only has an output open while a track plays, its gain is what
+/// ducking turns down during a voice session (an alarm must stay loud), and
+/// the paused track's decoder and position have to stay as they are so it
+/// resumes where it was once the alarm stops.
+struct Ringer {
+    _stream: cpal::Stream,
+}
+
+fn build_ring_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Vec<f32>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels.max(1) as usize;
+    let ramp_frames = config.sample_rate.0 as f32 * RAMP_SECS;
+    let mut position = 0;
+    let mut played_frames = 0u64;
+
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_mut(channels) {
+                    let gain = (0.2 + 0.8 * played_frames as f32 / ramp_frames).min(1.0);
+                    for out in frame {
+                        *out = T::from_sample(samples[position] * gain);
+                        position = (position + 1) % samples.len();
+                    }
+                    played_frames += 1;
+                }
+            },
+            |err| eprintln!("Alarm stream error: {}", err),
+            None,
+        )
+        .map_err(|e| format!("Failed to build stream: {}", e))
+}
+
+impl Ringer {
+    fn start(sound: Option<&str>) -> Result<Self, String> {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or("No audio output device found")?;
+        let config = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to get output config: {}", e))?;
+        let sample_rate = config.sample_rate().0;
+        let channels = config.channels().max(1) as usize;
+
+        // A missing or broken sound file must not make the alarm silent
+        let samples = sound
+            .and_then(|path| {
+                load_sound(Path::new(path), sample_rate, channels)
+                    .map_err(|e| eprintln!("Alarm sound unavailable, beeping: {}", e))
+                    .ok()
+            })
+            .unwrap_or_else(|| beep(sample_rate, channels));
+
+        let sample_format = config.sample_format();
+        let stream_config: cpal::StreamConfig = config.into();
+        let stream = match sample_format {
+            SampleFormat::I8 => build_ring_stream::<i8>(&device, &stream_config, samples),
+            SampleFormat::I16 => build_ring_stream::<i16>(&device, &stream_config, samples),
+            SampleFormat::I32 => build_ring_stream::<i32>(&device, &stream_config, samples),
+            SampleFormat::I64 => build_ring_stream::<i64>(&device, &stream_config, samples),
+            SampleFormat::U8 => build_ring_stream::<u8>(&device, &stream_config, samples),
+            SampleFormat::U16 => build_ring_stream::<u16>(&device, &stream_config, samples),
+            SampleFormat::U32 => build_ring_stream::<u32>(&device, &stream_config, samples),
+            SampleFormat::U64 => build_ring_stream::<u64>(&device, &stream_config, samples),
+            SampleFormat::F32 => build_ring_stream::<f32>(&device, &stream_config, samples),
+            SampleFormat::F64 => build_ring_stream::<f64>(&device, &stream_config, samples),
+            other => Err(format!("Unsupported sample format: {:?}", other)),
+        }?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start stream: {}", e))?;
+        Ok(Self { _stream: stream })
+    }
+}
+
+// ============================================================================
+// SCHEDULER
+// ============================================================================
+
+struct Scheduler {
+    app: AppHandle,
+    ringer: Option<Ringer>,
+    ring_started: Instant,
+    /// Music was playing and got paused for the alarm
+    paused_player: bool,
+}
+
+impl Scheduler {
+    fn emit_changed(&self) {
+        let status = status(&ALARM_STORE.lock());
+        let _ = self.app.emit("alarms-changed", status);
+    }
+
+    /// Collect what came due in (`from`, `now`] and update the store: one-shot
+    /// alarms are disabled, snoozes cleared and finished timers removed
+    fn take_due(&self, from: DateTime<Local>, now: DateTime<Local>) -> Vec<Due> {
+        let mut store = ALARM_STORE.lock();
+        let defaults = store.defaults.clone();
+        let (from_secs, now_secs) = (from.timestamp(), now.timestamp());
+        let (from_ms, now_ms) = (from.timestamp_millis(), now.timestamp_millis());
+        let mut due = Vec::new();
+        let mut changed = false;
+
+        for alarm in store.alarms.iter_mut().filter(|a| a.enabled) {
+            let snooze_due = alarm.snoozed_until.filter(|&t| t <= now_secs);
+            let scheduled_due = next_occurrence(alarm, from).filter(|at| *at <= now);
+            // A snooze that ended while the app was down is dropped silently
+            let rings = scheduled_due.is_some() || snooze_due.is_some_and(|t| t > from_secs);
+            if snooze_due.is_some() {
+                alarm.snoozed_until = None;
+                changed = true;
+            }
+            if (scheduled_due.is_some() || snooze_due.is_some()) && alarm.days.is_empty() {
+                alarm.enabled = false;
+                changed = true;
+            }
+            if rings {
+                due.push(Due {
+                    fired: FiredAlarm {
+                        id: alarm.id.clone(),
+                        kind: AlarmKind::Alarm,
+                        label: alarm.label.clone(),
+                        fired_at: now_secs,
+                    },
+                    volume: alarm.volume.unwrap_or(defaults.volume),
+                    sound: alarm.sound.clone().or(defaults.sound.clone()),
+                });
+            }
+        }
+
+        store.timers.retain(|timer| {
+            let Some(ends_at) = timer.ends_at.filter(|&t| t <= now_ms) else {
+                return true;
+            };
+            changed = true;
+            if ends_at > from_ms {
+                due.push(Due {
+                    fired: FiredAlarm {
+                        id: timer.id.clone(),
+                        kind: AlarmKind::Timer,
+                        label: timer.label.clone(),
+                        fired_at: now_secs,
+                    },
+                    volume: defaults.volume,
+                    sound: defaults.sound.clone(),
+                });
+            } else {
+                println!(
+                    "⏰ Skipping timer \"{}\" that ended while the app was down",
+                    timer.label
+                );
+            }
+            false
+        });
+
+        if changed {
+            if let Err(e) = save_store(&store) {
+                eprintln!("❌ {}", e);
+            }
+            drop(store);
+            self.emit_changed();
+        }
+        due
+    }
+
+    fn ring(&mut self, due: Due) {
+        println!("⏰ {:?} \"{}\" went off", due.fired.kind, due.fired.label);
+
+        if self.ringer.is_none() {
+            if player::get_player_state().status == PlaybackStatus::Playing {
+                self.paused_player = player::player_pause().is_ok();
+            }
+            if let Err(e) = ducking::raise_for_alarm(due.volume.min(100)) {
+                eprintln!("Failed to set alarm volume: {}", e);
+            }
+            match Ringer::start(due.sound.as_deref()) {
+                Ok(ringer) => self.ringer = Some(ringer),
+                Err(e) => eprintln!("❌ Alarm can't ring: {}", e),
+            }
+        }
+        // A newer alarm restarts the timeout
+        self.ring_started = Instant::now();
+
+        {
+            let mut store = ALARM_STORE.lock();
+            store.ringing.retain(|r| r.id != due.fired.id);
+            store.ringing.push(due.fired.clone());
+        }
+        let _ = self.app.emit("alarm-fired", due.fired);
+        self.emit_changed();
+    }
+
+    /// Stop ringing `id`, and the sound once nothing is left ringing
+    fn stop(&mut self, id: &str, reason: StopReason) {
+        let remaining = {
+            let mut store = ALARM_STORE.lock();
+            let before = store.ringing.len();
+            store.ringing.retain(|r| r.id != id);
+            if store.ringing.len() == before {
+                return;
+            }
+            store.ringing.len()
+        };
+        let _ = self.app.emit(
+            "alarm-stopped",
+            AlarmStopped {
+                id: id.to_string(),
+                reason,
+            },
+        );
+        if remaining > 0 {
+            return;
+        }
+
+        self.ringer = None;
+        ducking::restore_after_alarm();
+        if std::mem::take(&mut self.paused_player)
+            && player::get_player_state().status == PlaybackStatus::Paused
+        {
+            let _ = player::player_resume();
+        }
+    }
+
+    fn snooze(&mut self, id: &str) {
+        let kind = ALARM_STORE
+            .lock()
+            .ringing
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| (r.kind, r.label.clone()));
+        let Some((kind, label)) = kind else {
+            return;
+        };
+
+        {
+            let mut store = ALARM_STORE.lock();
+            let snooze_secs = store.defaults.snooze_minutes.max(1) as i64 * 60;
+            let until = Local::now().timestamp() + snooze_secs;
+            match kind {
+                AlarmKind::Alarm => {
+                    if let Some(alarm) = store.alarms.iter_mut().find(|a| a.id == id) {
+                        alarm.snoozed_until = Some(until);
+                        // One-shot alarms disabled themselves when they rang
+                        alarm.enabled = true;
+                    }
+                }
+                // A snoozed timer rings again like a fresh one
+                AlarmKind::Timer => store.timers.push(Timer {
+                    id: id.to_string(),
+                    label,
+                    duration_ms: snooze_secs as u64 * 1000,
+                    ends_at: Some(until * 1000),
+                    remaining_ms: 0,
+                }),
+            }
+            if let Err(e) = save_store(&store) {
+                eprintln!("❌ {}", e);
+            }
+        }
+        self.stop(id, StopReason::Snoozed);
+        self.emit_changed();
+    }
+
+    fn handle(&mut self, command: SchedulerCommand) {
+        match command {
+            SchedulerCommand::Changed => self.emit_changed(),
+            SchedulerCommand::Snooze(id) => self.snooze(&id),
+            SchedulerCommand::Dismiss(id) => {
+                self.stop(&id, StopReason::Dismissed);
+                self.emit_changed();
+            }
+        }
+    }
+
+    /// Give up ringing after `ring_minutes`
+    fn check_timeout(&mut self) {
+        let ring_minutes = ALARM_STORE.lock().defaults.ring_minutes.max(1) as u64;
+        if self.ring_started.elapsed() < Duration::from_secs(ring_minutes * 60) {
+            return;
+        }
+        let ids: Vec<String> = ALARM_STORE
+            .lock()
+            .ringing
+            .iter()
+            .map(|r| r.id.clone())
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+        for id in ids {
+            self.stop(&id, StopReason::TimedOut);
+        }
+        self.emit_changed();
+    }
+}
+
+/// Start the scheduler thread
+pub fn start_alarm_scheduler(app: AppHandle) {
+    let (tx, rx) = mpsc::channel();
+    *SCHEDULER.lock() = Some(tx);
+
+    thread::spawn(move || {
+        let mut scheduler = Scheduler {
+            app,
+            ringer: None,
+            ring_started: Instant::now(),
+            paused_player: false,
+        };
+        let now = Local::now();
+        // Catch up on what came due shortly before the app started
+        let mut last_check = now - chrono::Duration::seconds(MISSED_GRACE_SECS);
+        let store = ALARM_STORE.lock();
+        println!(
+            "⏰ Alarm scheduler started ({} alarms, {} timers)",
+            store.alarms.len(),
+            store.timers.len()
+        );
+        drop(store);
+
+        loop {
+            match rx.recv_timeout(TICK) {
+                Ok(command) => scheduler.handle(command),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let now = Local::now();
+            // Never look back further than the grace period, e.g. when the
+            // clock jumps forward on NTP sync
+            let from = last_check
+                .max(now - chrono::Duration::seconds(MISSED_GRACE_SECS))
+                .min(now);
+            let due = scheduler.take_due(from, now);
+            last_check = now;
+            for due in due {
+                scheduler.ring(due);
+            }
+            scheduler.check_timeout();
+        }
+    });
+}
+
+fn notify(command: SchedulerCommand) -> Result<(), String> {
+    SCHEDULER
+        .lock()
+        .as_ref()
+        .ok_or("Alarm scheduler is not running")?
+        .send(command)
+        .map_err(|_| "Alarm scheduler is not running".to_string())
+}
+
+fn validate_label(label: &str) -> Result<String, String> {
+    let label = label.trim();
+    if label.chars().count() > MAX_LABEL_LEN {
+        return Err(format!("Label is longer than {} characters", MAX_LABEL_LEN));
+    }
+    Ok(label.to_string())
+}
+
+fn validate_volume(volume: Option<u8>) -> Result<(), String> {
+    match volume {
+        Some(v) if v > 100 => Err("Volume must be between 0 and 100".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn validate_sound(sound: &Option<String>) -> Result<(), String> {
+    match sound {
+        Some(path) if !Path::new(path).is_file() => Err(format!("File not found: {}", path)),
+        _ => Ok(()),
+    }
+}
+
+fn validate_settings(settings: &AlarmSettings) -> Result<String, String> {
+    if settings.hour > 23 || settings.minute > 59 {
+        return Err(format!(
+            "Invalid time {:02}:{:02}",
+            settings.hour, settings.minute
+        ));
+    }
+    validate_volume(settings.volume)?;
+    validate_sound(&settings.sound)?;
+    validate_label(&settings.label)
+}
+
+/// Apply a change to the store, save it and let the scheduler announce it
+fn update_store<T>(f: impl FnOnce(&mut AlarmStore) -> Result<T, String>) -> Result<T, String> {
+    let result = {
+        let mut store = ALARM_STORE.lock();
+        let result = f(&mut store)?;
+        save_store(&store)?;
+        result
+    };
+    let _ = notify(SchedulerCommand::Changed);
+    Ok(result)
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+/// Alarms with their next fire time, running timers and what's ringing
+#[tauri::command]
+pub fn get_alarms() -> AlarmStatus {
+    status(&ALARM_STORE.lock())
+}
+
+#[tauri::command]
+pub fn create_alarm(alarm: AlarmSettings) -> Result<Alarm, String> {
+    let label = validate_settings(&alarm)?;
+    let mut days = alarm.days;
+    days.sort();
+    days.dedup();
+    let alarm = Alarm {
+        id: new_id(),
+        label,
+        hour: alarm.hour,
+        minute: alarm.minute,
+        days,
+        enabled: true,
+        volume: alarm.volume,
+        sound: alarm.sound,
+        snoozed_until: None,
+        next_fire: None,
+    };
+    update_store(|store| {
+        store.alarms.push(alarm.clone());
+        Ok(Alarm {
+            next_fire: next_fire(&alarm, Local::now()),
+            ..alarm
+        })
+    })
+}
+
+/// Change an alarm's settings, which also enables it again
+#[tauri::command]
+pub fn update_alarm(id: String, alarm: AlarmSettings) -> Result<Alarm, String> {
+    let label = validate_settings(&alarm)?;
+    update_store(|store| {
+        let existing = store
+            .alarms
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or_else(|| format!("Alarm not found: {}", id))?;
+        existing.label = label;
+        existing.hour = alarm.hour;
+        existing.minute = alarm.minute;
+        existing.days = alarm.days;
+        existing.days.sort();
+        existing.days.dedup();
+        existing.volume = alarm.volume;
+        existing.sound = alarm.sound;
+        existing.enabled = true;
+        existing.snoozed_until = None;
+        Ok(Alarm {
+            next_fire: next_fire(existing, Local::now()),
+            ..existing.clone()
+        })
+    })
+}
+
+/// Turning an alarm off also cancels a snooze
+#[tauri::command]
+pub fn set_alarm_enabled(id: String, enabled: bool) -> Result<(), String> {
+    update_store(|store| {
+        let alarm = store
+            .alarms
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or_else(|| format!("Alarm not found: {}", id))?;
+        alarm.enabled = enabled;
+        alarm.snoozed_until = None;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn delete_alarm(id: String) -> Result<(), String> {
+    update_store(|store| {
+        let before = store.alarms.len();
+        store.alarms.retain(|a| a.id != id);
+        if store.alarms.len() == before {
+            return Err(format!("Alarm not found: {}", id));
+        }
+        Ok(())
+    })
+}
+
+/// Start a countdown timer
+#[tauri::command]
+pub fn start_timer(duration_secs: u64, label: Option<String>) -> Result<Timer, String> {
+    if duration_secs == 0 || duration_secs > MAX_TIMER_SECS {
+        return Err(format!(
+            "Timer must be between 1 second and {} hours",
+            MAX_TIMER_SECS / 3600
+        ));
+    }
+    let timer = Timer {
+        id: new_id(),
+        label: validate_label(label.as_deref().unwrap_or(""))?,
+        duration_ms: duration_secs * 1000,
+        ends_at: Some(Local::now().timestamp_millis() + duration_secs as i64 * 1000),
+        remaining_ms: duration_secs * 1000,
+    };
+    update_store(|store| {
+        store.timers.push(timer.clone());
+        Ok(timer)
+    })
+}
+
+#[tauri::command]
+pub fn pause_timer(id: String) -> Result<(), String> {
+    update_store(|store| {
+        let timer = store
+            .timers
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("Timer not found: {}", id))?;
+        if let Some(ends_at) = timer.ends_at.take() {
+            timer.remaining_ms = (ends_at - Local::now().timestamp_millis()).max(0) as u64;
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn resume_timer(id: String) -> Result<(), String> {
+    update_store(|store| {
+        let timer = store
+            .timers
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("Timer not found: {}", id))?;
+        if timer.ends_at.is_none() {
+            timer.ends_at = Some(Local::now().timestamp_millis() + timer.remaining_ms as i64);
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn cancel_timer(id: String) -> Result<(), String> {
+    update_store(|store| {
+        let before = store.timers.len();
+        store.timers.retain(|t| t.id != id);
+        if store.timers.len() == before {
+            return Err(format!("Timer not found: {}", id));
+        }
+        Ok(())
+    })
+}
+
+/// Silence a ringing alarm or timer and ring it again in `snooze_minutes`
+#[tauri::command]
+pub fn snooze_alarm(id: String) -> Result<(), String> {
+    notify(SchedulerCommand::Snooze(id))
+}
+
+/// Silence a ringing alarm or timer
+#[tauri::command]
+pub fn dismiss_alarm(id: String) -> Result<(), String> {
+    notify(SchedulerCommand::Dismiss(id))
+}
+
+#[tauri::command]
+pub fn get_alarm_defaults() -> AlarmDefaults {
+    ALARM_STORE.lock().defaults.clone()
+}
+
+#[tauri::command]
+pub fn set_alarm_defaults(defaults: AlarmDefaults) -> Result<(), String> {
+    validate_volume(Some(defaults.volume))?;
+    validate_sound(&defaults.sound)?;
+    if defaults.snooze_minutes == 0 || defaults.ring_minutes == 0 {
+        return Err("Snooze and ring time must be at least a minute".to_string());
+    }
+    update_store(|store| {
+        store.defaults = defaults;
+        Ok(())
+    })
+}
@@ -0,0 +1,935 @@
+//! Display Brightness
+//!
+//! Brightness is set through one of several backends:
+//! - KDE: PowerDevil's BrightnessControl on the session bus
+//! - GNOME: gsd-power's Screen interface on the session bus
+//! - sysfs: /sys/class/backlight, written through logind's SetBrightness when
+//!   the file isn't writable without root (embedded boards without a desktop)
+//! - DDC/CI: an external monitor over /dev/i2c-* (needs the i2c-dev module and
+//!   read/write access to the device)
+//!
+//! The first one that works is used, in that order: a desktop's own service
+//! keeps its OSD and saved level in sync, and DDC/CI is slow. The choice is
+//! cached until its device goes away or it keeps failing. A backend can be
+//! forced in ~/.config/honeybee/brightness.json.
+//!
+//! Changes made elsewhere are picked up from the desktop's DBus signal, inotify
+//! on the backlight files, or by polling DDC/CI, and emitted as
+//! `brightness-changed`.
+
+use dbus::{blocking::Connection, message::MatchRule};
+use notify::{Event, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Read, Write},
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::fsutil::write_atomic;
+
+/// Backends tried by auto-detection, in order
+const DETECT_ORDER: [BrightnessBackendKind; 4] = [
+    BrightnessBackendKind::Kde,
+    BrightnessBackendKind::Gnome,
+    BrightnessBackendKind::Sysfs,
+    BrightnessBackendKind::Ddc,
+];
+
+/// How often backends without change notifications are read
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the watcher checks that its backend is still the one in use
+const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Failed calls in a row before the backend is detected again
+const MAX_FAILURES: u32 = 3;
+
+lazy_static::lazy_static! {
+    static ref BACKEND: Mutex<Option<Arc<dyn BrightnessBackend>>> = Mutex::new(None);
+}
+
+/// Failed calls in a row on the cached backend
+static FAILURES: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BrightnessBackendKind {
+    Kde,
+    Gnome,
+    Sysfs,
+    Ddc,
+}
+
+impl BrightnessBackendKind {
+    fn name(self) -> &'static str {
+        match self {
+            BrightnessBackendKind::Kde => "KDE",
+            BrightnessBackendKind::Gnome => "GNOME",
+            BrightnessBackendKind::Sysfs => "sysfs backlight",
+            BrightnessBackendKind::Ddc => "DDC/CI",
+        }
+    }
+}
+
+/// Brightness configuration (~/.config/honeybee/brightness.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrightnessConfig {
+    /// Always use this backend instead of detecting one
+    pub backend: Option<BrightnessBackendKind>,
+}
+
+trait BrightnessBackend: Send + Sync {
+    fn kind(&self) -> BrightnessBackendKind;
+
+    /// Current brightness as percentage (0-100)
+    fn get(&self) -> Result<u8, String>;
+
+    /// Set brightness (percentage 0-100)
+    fn set(&self, level: u8) -> Result<(), String>;
+
+    /// Send on `changed` whenever the brightness may have changed, until the
+    /// returned guard is dropped. None when the backend can only be polled.
+    fn watch(&self, _changed: Sender<()>) -> Result<Option<WatchGuard>, String> {
+        Ok(None)
+    }
+
+    /// False once the device is gone (unplugged, driver unloaded), so the
+    /// backend is detected again without waiting for more failures
+    fn is_present(&self) -> bool {
+        true
+    }
+}
+
+/// Keeps a change notification alive
+type WatchGuard = Box<dyn Send>;
+
+fn config_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/brightness.json"))
+        .unwrap_or_else(|| PathBuf::from("brightness.json"))
+}
+
+fn load_config() -> BrightnessConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn to_percentage(value: u32, max: u32) -> u8 {
+    if max == 0 {
+        return 0;
+    }
+    ((value as f64 / max as f64) * 100.0)
+        .round()
+        .clamp(0.0, 100.0) as u8
+}
+
+fn from_percentage(level: u8, max: u32) -> u32 {
+    ((level as f64 / 100.0) * max as f64).round() as u32
+}
+
+/// Run busctl, `what` names the call in errors
+fn busctl(args: &[&str], what: &str) -> Result<String, String> {
+    let output = Command::new("busctl")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to call {}: {}", what, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The value of a busctl reply like "i 9600" (type indicator followed by value)
+fn parse_busctl_int(reply: &str, what: &str) -> Result<i32, String> {
+    reply
+        .split_whitespace()
+        .nth(1)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("Invalid {} response", what))
+}
+
+/// A session bus signal, received on a thread of its own
+struct SignalWatch {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for SignalWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn watch_signal(rule: MatchRule<'static>, changed: Sender<()>) -> Result<WatchGuard, String> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let connection = match Connection::new_session() {
+            Ok(connection) => connection,
+            Err(e) => {
+                let _ = ready_tx.send(Err(format!("No session bus: {}", e)));
+                return;
+            }
+        };
+        let added = connection
+            .add_match(rule, move |_: (), _, _| changed.send(()).is_ok())
+            .map(|_| ())
+            .map_err(|e| format!("Failed to watch brightness signal: {}", e));
+        let ok = added.is_ok();
+        let _ = ready_tx.send(added);
+        // Ends with the guard, or when the bus goes away (which drops
+        // `changed` and tells the watcher)
+        while ok && !thread_stop.load(Ordering::Relaxed) {
+            if connection.process(Duration::from_secs(1)).is_err() {
+                break;
+            }
+        }
+    });
+
+    ready_rx
+        .recv()
+        .map_err(|_| "Brightness signal watcher failed to start".to_string())??;
+    Ok(Box::new(SignalWatch { stop }))
+}
+
+// ============================================================================
+// KDE (PowerDevil over DBus)
+// ============================================================================
+
+struct KdeBackend;
+
+impl KdeBackend {
+    fn probe() -> Result<Self, String> {
+        if Self::call_int("brightnessMax")? <= 0 {
+            return Err("Invalid max brightness value".to_string());
+        }
+        Ok(KdeBackend)
+    }
+
+    fn call(method: &str, args: &[&str]) -> Result<String, String> {
+        let mut call = vec![
+            "--user",
+            "call",
+            "org.kde.Solid.PowerManagement",
+            "/org/kde/Solid/PowerManagement/Actions/BrightnessControl",
+            "org.kde.Solid.PowerManagement.Actions.BrightnessControl",
+            method,
+        ];
+        call.extend_from_slice(args);
+        busctl(&call, method)
+    }
+
+    fn call_int(method: &str) -> Result<i32, String> {
+        parse_busctl_int(&Self::call(method, &[])?, method)
+    }
+}
+
+impl BrightnessBackend for KdeBackend {
+    fn kind(&self) -> BrightnessBackendKind {
+        BrightnessBackendKind::Kde
+    }
+
+    fn get(&self) -> Result<u8, String> {
+        let max = Self::call_int("brightnessMax")?;
+        let current = Self::call_int("brightness")?;
+
+        if max <= 0 {
+            return Err("Invalid max brightness value".to_string());
+        }
+
+        Ok(to_percentage(current.max(0) as u32, max as u32))
+    }
+
+    fn set(&self, level: u8) -> Result<(), String> {
+        let max = Self::call_int("brightnessMax")?;
+        let target_value = from_percentage(level, max.max(0) as u32);
+        Self::call("setBrightness", &["i", &target_value.to_string()]).map(|_| ())
+    }
+
+    fn watch(&self, changed: Sender<()>) -> Result<Option<WatchGuard>, String> {
+        let rule = MatchRule::new_signal(
+            "org.kde.Solid.PowerManagement.Actions.BrightnessControl",
+            "brightnessChanged",
+        )
+        .with_path("/org/kde/Solid/PowerManagement/Actions/BrightnessControl");
+        watch_signal(rule, changed).map(Some)
+    }
+}
+
+// ============================================================================
+// GNOME (gsd-power over DBus)
+// ============================================================================
+
+struct GnomeBackend;
+
+impl GnomeBackend {
+    const PROPERTY: [&'static str; 4] = [
+        "org.gnome.SettingsDaemon.Power",
+        "/org/gnome/SettingsDaemon/Power",
+        "org.gnome.SettingsDaemon.Power.Screen",
+        "Brightness",
+    ];
+
+    fn probe() -> Result<Self, String> {
+        GnomeBackend.get()?;
+        Ok(GnomeBackend)
+    }
+}
+
+impl BrightnessBackend for GnomeBackend {
+    fn kind(&self) -> BrightnessBackendKind {
+        BrightnessBackendKind::Gnome
+    }
+
+    fn get(&self) -> Result<u8, String> {
+        let mut args = vec!["--user", "get-property"];
+        args.extend_from_slice(&Self::PROPERTY);
+        // Already a percentage, -1 when gsd-power has no backlight to control
+        let value = parse_busctl_int(&busctl(&args, "Brightness")?, "Brightness")?;
+        if value < 0 {
+            return Err("GNOME has no brightness control for this display".to_string());
+        }
+        Ok(value.min(100) as u8)
+    }
+
+    fn set(&self, level: u8) -> Result<(), String> {
+        let level = level.to_string();
+        let mut args = vec!["--user", "set-property"];
+        args.extend_from_slice(&Self::PROPERTY);
+        args.extend_from_slice(&["i", &level]);
+        busctl(&args, "Brightness").map(|_| ())
+    }
+
+    fn watch(&self, changed: Sender<()>) -> Result<Option<WatchGuard>, String> {
+        let rule = MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged")
+            .with_path("/org/gnome/SettingsDaemon/Power");
+        watch_signal(rule, changed).map(Some)
+    }
+}
+
+// ============================================================================
+// SYSFS BACKLIGHT (with logind for writes)
+// ============================================================================
+
+/// Where backlight devices are listed. HONEYBEE_SYSFS_ROOT points this at a
+/// fake sysfs tree for testing.
+fn backlight_dir() -> PathBuf {
+    std::env::var_os("HONEYBEE_SYSFS_ROOT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/sys"))
+        .join("class/backlight")
+}
+
+/// A backlight device, e.g. /sys/class/backlight/intel_backlight
+#[derive(Debug, Clone)]
+struct SysfsBackend {
+    name: String,
+    path: PathBuf,
+    max: u32,
+}
+
+fn read_sysfs_value(path: &Path) -> Result<u32, String> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid value in {}", path.display()))
+}
+
+impl SysfsBackend {
+    /// Pick the backlight to control. Like systemd, firmware interfaces win
+    /// over platform drivers over raw hardware registers; ties go to the
+    /// first by name.
+    fn probe() -> Result<Self, String> {
+        let dir = backlight_dir();
+        fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let max = read_sysfs_value(&path.join("max_brightness"))
+                    .ok()
+                    .filter(|max| *max > 0)?;
+                let rank = match fs::read_to_string(path.join("type"))
+                    .unwrap_or_default()
+                    .trim()
+                {
+                    "firmware" => 0,
+                    "platform" => 1,
+                    "raw" => 2,
+                    _ => 3,
+                };
+                let name = entry.file_name().to_string_lossy().to_string();
+                Some((rank, SysfsBackend { name, path, max }))
+            })
+            .min_by(|(rank_a, a), (rank_b, b)| rank_a.cmp(rank_b).then_with(|| a.name.cmp(&b.name)))
+            .map(|(_, device)| device)
+            .ok_or_else(|| "No backlight device found".to_string())
+    }
+
+    /// The brightness file is root-only without a udev rule, logind lets the
+    /// user of the active session set it instead
+    fn set_logind(&self, value: u32) -> Result<(), String> {
+        busctl(
+            &[
+                "call",
+                "org.freedesktop.login1",
+                "/org/freedesktop/login1/session/auto",
+                "org.freedesktop.login1.Session",
+                "SetBrightness",
+                "ssu",
+                "backlight",
+                &self.name,
+                &value.to_string(),
+            ],
+            "SetBrightness",
+        )
+        .map(|_| ())
+    }
+}
+
+impl BrightnessBackend for SysfsBackend {
+    fn kind(&self) -> BrightnessBackendKind {
+        BrightnessBackendKind::Sysfs
+    }
+
+    fn get(&self) -> Result<u8, String> {
+        let current = read_sysfs_value(&self.path.join("brightness"))?;
+        Ok(to_percentage(current, self.max))
+    }
+
+    fn set(&self, level: u8) -> Result<(), String> {
+        // Devices with few steps would round a low level down to 0 (panel off)
+        let value = from_percentage(level, self.max).max(1);
+
+        match fs::write(self.path.join("brightness"), value.to_string()) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => self.set_logind(value),
+            Err(e) => Err(format!("Failed to set brightness: {}", e)),
+        }
+    }
+
+    /// Writes to `brightness` show up as modifications, and the kernel
+    /// notifies on `actual_brightness` when firmware handles a brightness key
+    fn watch(&self, changed: Sender<()>) -> Result<Option<WatchGuard>, String> {
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if event.is_ok_and(|event| event.kind.is_modify()) {
+                let _ = changed.send(());
+            }
+        })
+        .map_err(|e| format!("Failed to create backlight watcher: {}", e))?;
+
+        for file in ["brightness", "actual_brightness"] {
+            let path = self.path.join(file);
+            if path.exists() {
+                watcher
+                    .watch(&path, RecursiveMode::NonRecursive)
+                    .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+            }
+        }
+        Ok(Some(Box::new(watcher)))
+    }
+
+    fn is_present(&self) -> bool {
+        self.path.exists()
+    }
+}
+
+// ============================================================================
+// DDC/CI (external monitors over I2C)
+// ============================================================================
+
+/// ioctl selecting the device address for following reads and writes
+const I2C_SLAVE: u64 = 0x0703;
+
+/// DDC/CI address of the monitor
+const DDC_ADDRESS: u16 = 0x37;
+
+/// EDID EEPROM address, present on buses with a monitor attached
+const EDID_ADDRESS: u16 = 0x50;
+
+const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// VCP feature code for luminance
+const VCP_BRIGHTNESS: u8 = 0x10;
+
+/// Monitors need this long to prepare a reply or apply a change
+const DDC_DELAY: Duration = Duration::from_millis(50);
+
+struct DdcBackend {
+    bus: PathBuf,
+    /// One transaction on the bus at a time
+    lock: Mutex<()>,
+}
+
+fn open_i2c(bus: &Path, address: u16) -> Result<File, String> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(bus)
+        .map_err(|e| format!("Failed to open {}: {}", bus.display(), e))?;
+    if unsafe { libc::ioctl(file.as_raw_fd(), I2C_SLAVE as _, address as libc::c_ulong) } < 0 {
+        return Err(format!(
+            "Failed to select I2C address on {}: {}",
+            bus.display(),
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(file)
+}
+
+fn has_edid(bus: &Path) -> bool {
+    let Ok(mut file) = open_i2c(bus, EDID_ADDRESS) else {
+        return false;
+    };
+    let mut header = [0u8; 8];
+    file.write_all(&[0]).is_ok() && file.read_exact(&mut header).is_ok() && header == EDID_HEADER
+}
+
+/// Send a DDC/CI message: host address, length with the protocol flag, the
+/// payload and a checksum over all of it starting from the monitor address
+fn write_ddc(file: &mut File, payload: &[u8]) -> Result<(), String> {
+    let mut packet = vec![0x51, 0x80 | payload.len() as u8];
+    packet.extend_from_slice(payload);
+    let checksum = packet.iter().fold(0x6e, |sum, byte| sum ^ byte);
+    packet.push(checksum);
+    file.write_all(&packet)
+        .map_err(|e| format!("DDC/CI write failed: {}", e))
+}
+
+impl DdcBackend {
+    /// The first I2C bus with a monitor that answers DDC/CI brightness reads
+    fn probe() -> Result<Self, String> {
+        let mut buses: Vec<(u32, PathBuf)> = fs::read_dir("/dev")
+            .map_err(|e| format!("Failed to read /dev: {}", e))?
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let number = name.strip_prefix("i2c-")?.parse().ok()?;
+                Some((number, entry.path()))
+            })
+            .collect();
+        if buses.is_empty() {
+            return Err("No I2C buses (is the i2c-dev module loaded?)".to_string());
+        }
+        buses.sort();
+
+        buses
+            .into_iter()
+            .filter(|(_, bus)| has_edid(bus))
+            .map(|(_, bus)| DdcBackend {
+                bus,
+                lock: Mutex::new(()),
+            })
+            .find(|backend| backend.read_vcp(VCP_BRIGHTNESS).is_ok())
+            .ok_or_else(|| "No monitor with DDC/CI found".to_string())
+    }
+
+    /// A VCP feature as (current, max)
+    fn read_vcp(&self, code: u8) -> Result<(u16, u16), String> {
+        let _guard = self.lock.lock();
+        let mut file = open_i2c(&self.bus, DDC_ADDRESS)?;
+        write_ddc(&mut file, &[0x01, code])?;
+        thread::sleep(DDC_DELAY);
+
+        // Source, length, reply opcode, result, feature, type, max, current
+        // and a checksum, which some monitors get wrong so it isn't checked
+        let mut reply = [0u8; 11];
+        file.read_exact(&mut reply)
+            .map_err(|e| format!("DDC/CI read failed: {}", e))?;
+        if reply[2] != 0x02 || reply[4] != code {
+            return Err("Invalid DDC/CI reply".to_string());
+        }
+        if reply[3] != 0 {
+            return Err(format!("Monitor doesn't support VCP feature {:#04x}", code));
+        }
+        let max = u16::from_be_bytes([reply[6], reply[7]]);
+        let current = u16::from_be_bytes([reply[8], reply[9]]);
+        Ok((current, max))
+    }
+
+    fn write_vcp(&self, code: u8, value: u16) -> Result<(), String> {
+        let _guard = self.lock.lock();
+        let mut file = open_i2c(&self.bus, DDC_ADDRESS)?;
+        let [high, low] = value.to_be_bytes();
+        write_ddc(&mut file, &[0x03, code, high, low])?;
+        thread::sleep(DDC_DELAY);
+        Ok(())
+    }
+}
+
+impl BrightnessBackend for DdcBackend {
+    fn kind(&self) -> BrightnessBackendKind {
+        BrightnessBackendKind::Ddc
+    }
+
+    fn get(&self) -> Result<u8, String> {
+        let (current, max) = self.read_vcp(VCP_BRIGHTNESS)?;
+        if max == 0 {
+            return Err("Invalid max brightness value".to_string());
+        }
+        Ok(to_percentage(current as u32, max as u32))
+    }
+
+    fn set(&self, level: u8) -> Result<(), String> {
+        let (_, max) = self.read_vcp(VCP_BRIGHTNESS)?;
+        let value = from_percentage(level, max as u32).min(u16::MAX as u32) as u16;
+        self.write_vcp(VCP_BRIGHTNESS, value)
+    }
+
+    /// Only the bus, DDC/CI errors are also what a busy monitor gives
+    fn is_present(&self) -> bool {
+        self.bus.exists()
+    }
+}
+
+// ============================================================================
+// BACKEND SELECTION
+// ============================================================================
+
+fn probe(kind: BrightnessBackendKind) -> Result<Arc<dyn BrightnessBackend>, String> {
+    Ok(match kind {
+        BrightnessBackendKind::Kde => Arc::new(KdeBackend::probe()?),
+        BrightnessBackendKind::Gnome => Arc::new(GnomeBackend::probe()?),
+        BrightnessBackendKind::Sysfs => Arc::new(SysfsBackend::probe()?),
+        BrightnessBackendKind::Ddc => Arc::new(DdcBackend::probe()?),
+    })
+}
+
+fn detect(config: &BrightnessConfig) -> Result<Arc<dyn BrightnessBackend>, String> {
+    if let Some(kind) = config.backend {
+        return probe(kind)
+            .map_err(|e| format!("{} brightness is not available: {}", kind.name(), e));
+    }
+
+    let backend = DETECT_ORDER
+        .into_iter()
+        .find_map(|kind| probe(kind).ok())
+        .ok_or("No way to control brightness found")?;
+    println!("💡 Brightness backend: {}", backend.kind().name());
+    Ok(backend)
+}
+
+fn backend() -> Result<Arc<dyn BrightnessBackend>, String> {
+    let mut cached = BACKEND.lock();
+    if let Some(backend) = cached.as_ref() {
+        return Ok(backend.clone());
+    }
+    let backend = detect(&load_config())?;
+    *cached = Some(backend.clone());
+    FAILURES.store(0, Ordering::SeqCst);
+    Ok(backend)
+}
+
+/// Run `f` on the backend. When its device is gone or it failed
+/// `MAX_FAILURES` times in a row it's detected again on the next call, e.g.
+/// when the desktop restarted or the monitor was unplugged. A single error
+/// (a busy monitor, a slow bus) keeps it.
+fn with_backend<T>(
+    f: impl FnOnce(&dyn BrightnessBackend) -> Result<T, String>,
+) -> Result<T, String> {
+    let backend = backend()?;
+    let result = f(backend.as_ref());
+    if result.is_ok() {
+        FAILURES.store(0, Ordering::SeqCst);
+        return result;
+    }
+
+    let failures = FAILURES.fetch_add(1, Ordering::SeqCst) + 1;
+    if failures >= MAX_FAILURES || !backend.is_present() {
+        let mut cached = BACKEND.lock();
+        // Unless the config switched to another one meanwhile
+        if cached
+            .as_ref()
+            .is_some_and(|active| Arc::ptr_eq(active, &backend))
+        {
+            *cached = None;
+        }
+    }
+    result
+}
+
+/// The backend in use, or why there is none
+pub fn active_backend() -> Result<BrightnessBackendKind, String> {
+    backend().map(|backend| backend.kind())
+}
+
+// ============================================================================
+// CHANGE NOTIFICATIONS
+// ============================================================================
+
+fn is_active(backend: &Arc<dyn BrightnessBackend>) -> bool {
+    BACKEND
+        .lock()
+        .as_ref()
+        .is_some_and(|active| Arc::ptr_eq(active, backend))
+}
+
+/// Emit `brightness-changed` when the brightness changes, also by a
+/// brightness key, the desktop or another process, so the overlay slider
+/// stays in sync. Follows backend changes and re-detection.
+pub fn start_brightness_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last: Option<u8> = None;
+        let mut check = || {
+            if let Ok(level) = get_brightness() {
+                if last != Some(level) {
+                    last = Some(level);
+                    let _ = app.emit("brightness-changed", level);
+                }
+            }
+        };
+
+        loop {
+            let backend = match backend() {
+                Ok(backend) => backend,
+                Err(_) => {
+                    thread::sleep(RECHECK_INTERVAL);
+                    continue;
+                }
+            };
+            check();
+
+            let (tx, rx) = mpsc::channel();
+            let watch = backend.watch(tx).unwrap_or_else(|e| {
+                eprintln!(
+                    "Brightness: change notifications unavailable, polling: {}",
+                    e
+                );
+                None
+            });
+
+            match watch {
+                Some(_guard) => loop {
+                    match rx.recv_timeout(RECHECK_INTERVAL) {
+                        Ok(()) => check(),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => {
+                            // The watch died (e.g. the session bus restarted)
+                            thread::sleep(POLL_INTERVAL);
+                            break;
+                        }
+                    }
+                    if !is_active(&backend) {
+                        break;
+                    }
+                },
+                None => loop {
+                    thread::sleep(POLL_INTERVAL);
+                    check();
+                    if !is_active(&backend) {
+                        break;
+                    }
+                },
+            }
+        }
+    });
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+/// Get current display brightness as percentage (0-100)
+#[tauri::command]
+pub fn get_brightness() -> Result<u8, String> {
+    with_backend(|backend| backend.get())
+}
+
+/// Set display brightness (percentage 0-100)
+#[tauri::command]
+pub fn set_brightness(level: u8) -> Result<(), String> {
+    // Clamp to safe range (never fully black, minimum 5%)
+    let safe_level = level.clamp(5, 100);
+
+    with_backend(|backend| backend.set(safe_level))
+}
+
+#[tauri::command]
+pub fn get_brightness_config() -> BrightnessConfig {
+    load_config()
+}
+
+/// Save the config and switch to the backend it picks. A forced backend that
+/// doesn't work here is refused.
+#[tauri::command]
+pub fn set_brightness_config(config: BrightnessConfig) -> Result<(), String> {
+    // Probing can take seconds (DDC/CI), don't hold up brightness calls
+    let backend = detect(&config)?;
+
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to save brightness config: {}", e))?;
+
+    *BACKEND.lock() = Some(backend);
+    FAILURES.store(0, Ordering::SeqCst);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake sysfs tree under the temp dir, removed on drop
+    struct FakeSysfs(PathBuf);
+
+    impl FakeSysfs {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "honeybee-sysfs-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(root.join("class/backlight")).unwrap();
+            FakeSysfs(root)
+        }
+
+        fn device(&self, name: &str, kind: &str, brightness: u32, max: u32) -> PathBuf {
+            let path = self.0.join("class/backlight").join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("type"), format!("{}\n", kind)).unwrap();
+            fs::write(path.join("brightness"), format!("{}\n", brightness)).unwrap();
+            fs::write(path.join("max_brightness"), format!("{}\n", max)).unwrap();
+            path
+        }
+    }
+
+    impl Drop for FakeSysfs {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn probe_prefers_firmware_over_platform_over_raw() {
+        let sysfs = FakeSysfs::new("probe");
+        sysfs.device("acpi_video0", "raw", 10, 100);
+        sysfs.device("intel_backlight", "platform", 100, 1000);
+        sysfs.device("nvidia_0", "firmware", 0, 0);
+        std::env::set_var("HONEYBEE_SYSFS_ROOT", &sysfs.0);
+
+        // A device without steps is skipped whatever its type
+        let backend = SysfsBackend::probe().unwrap();
+        assert_eq!(backend.name, "intel_backlight");
+        assert_eq!(backend.max, 1000);
+        assert_eq!(backend.get().unwrap(), 10);
+
+        sysfs.device("amdgpu_bl0", "firmware", 50, 255);
+        assert_eq!(SysfsBackend::probe().unwrap().name, "amdgpu_bl0");
+    }
+
+    #[test]
+    fn converts_between_percentage_and_raw() {
+        assert_eq!(to_percentage(0, 255), 0);
+        assert_eq!(to_percentage(128, 255), 50);
+        assert_eq!(to_percentage(255, 255), 100);
+        assert_eq!(to_percentage(300, 255), 100);
+        assert_eq!(from_percentage(0, 255), 0);
+        assert_eq!(from_percentage(50, 255), 128);
+        assert_eq!(from_percentage(100, 255), 255);
+
+        assert_eq!(to_percentage(10, 0), 0);
+        assert_eq!(from_percentage(50, 0), 0);
+    }
+
+    #[test]
+    fn set_writes_raw_value_and_keeps_panel_on() {
+        let sysfs = FakeSysfs::new("set");
+        let path = sysfs.device("intel_backlight", "raw", 0, 7);
+        let backend = SysfsBackend {
+            name: "intel_backlight".to_string(),
+            path: path.clone(),
+            max: 7,
+        };
+
+        backend.set(50).unwrap();
+        assert_eq!(fs::read_to_string(path.join("brightness")).unwrap(), "4");
+        backend.set(1).unwrap();
+        assert_eq!(fs::read_to_string(path.join("brightness")).unwrap(), "1");
+    }
+
+    #[test]
+    fn unreadable_brightness_is_an_error() {
+        let sysfs = FakeSysfs::new("unreadable");
+        let path = sysfs.device("intel_backlight", "raw", 0, 100);
+        let backend = SysfsBackend {
+            name: "intel_backlight".to_string(),
+            path: path.clone(),
+            max: 100,
+        };
+
+        fs::remove_file(path.join("brightness")).unwrap();
+        assert!(backend.get().unwrap_err().starts_with("Failed to read"));
+
+        fs::create_dir(path.join("brightness")).unwrap();
+        assert!(backend.get().is_err());
+
+        fs::remove_dir(path.join("brightness")).unwrap();
+        fs::write(path.join("brightness"), "bogus\n").unwrap();
+        assert!(backend.get().unwrap_err().starts_with("Invalid value"));
+    }
+
+    #[test]
+    fn backend_is_kept_until_it_keeps_failing_or_is_gone() {
+        let sysfs = FakeSysfs::new("failures");
+        let path = sysfs.device("intel_backlight", "raw", 50, 100);
+        let cache = |path: &Path| {
+            let backend: Arc<dyn BrightnessBackend> = Arc::new(SysfsBackend {
+                name: "intel_backlight".to_string(),
+                path: path.to_path_buf(),
+                max: 100,
+            });
+            *BACKEND.lock() = Some(backend);
+            FAILURES.store(0, Ordering::SeqCst);
+        };
+        let cached = || BACKEND.lock().is_some();
+
+        cache(&path);
+        fs::write(path.join("brightness"), "bogus\n").unwrap();
+        for _ in 1..MAX_FAILURES {
+            assert!(get_brightness().is_err());
+            assert!(cached());
+        }
+        fs::write(path.join("brightness"), "40\n").unwrap();
+        assert_eq!(get_brightness().unwrap(), 40);
+        fs::write(path.join("brightness"), "bogus\n").unwrap();
+        for _ in 1..MAX_FAILURES {
+            assert!(get_brightness().is_err());
+        }
+        assert!(cached());
+        assert!(get_brightness().is_err());
+        assert!(!cached());
+
+        // An unplugged device is dropped right away
+        cache(&path);
+        fs::remove_dir_all(&path).unwrap();
+        assert!(get_brightness().is_err());
+        assert!(!cached());
+    }
+}
@@ -0,0 +1,1796 @@
+//! Calendar
+//!
+//! Events are kept in ~/.config/honeybee/calendar.json. An event can repeat by
+//! a recurrence rule (the common part of RFC 5545 RRULEs: daily, weekly,
+//! monthly and yearly with an interval, a count or an end, and by-day,
+//! by-month-day and by-month parts), single occurrences can be left out, and
+//! it can have reminders some minutes before it starts.
+//!
+//! Times are unix seconds. Repeats are expanded in local wall-clock time, so a
+//! weekly 9:00 event stays at 9:00 across DST changes. There is no time zone
+//! database here: UTC times in .ics files are converted, times with a TZID
+//! are read as local time.
+//!
+//! Events are imported from and exported to .ics files on a USB drive or any
+//! local path. A reminder thread emits `calendar-reminder` when a reminder
+//! comes due (also ones missed by at most `MISSED_GRACE_SECS` while the app
+//! was down) and `calendar-changed` when events were edited or imported.
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::alarms::Weekday;
+use crate::fsutil::write_atomic;
+
+/// Reminders missed while the app was down still go off if at most this late
+const MISSED_GRACE_SECS: i64 = 5 * 60;
+
+/// Longest the reminder thread sleeps, so clock changes are picked up
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// Most occurrences returned by one range query
+const MAX_OCCURRENCES: usize = 5000;
+
+/// Bounds the expansion of rules that rarely or never match
+const MAX_PERIODS: i64 = 50_000;
+
+const MAX_TITLE_LEN: usize = 200;
+
+const MAX_TEXT_LEN: usize = 4000;
+
+const MAX_REMINDERS: usize = 10;
+
+/// Reminders can be set up to four weeks ahead
+const MAX_REMINDER_MINUTES: i32 = 4 * 7 * 24 * 60;
+
+const MAX_ICS_BYTES: u64 = 10 * 1024 * 1024;
+
+const ICS_EXTENSION: &str = "ics";
+
+const EXPORT_FILE_NAME: &str = "honeybee-calendar.ics";
+
+/// Where USB drives get mounted
+const REMOVABLE_ROOTS: &[&str] = &["/media", "/run/media", "/mnt"];
+
+/// Folder levels searched for .ics files (e.g. /media/<user>/<drive>/<folder>)
+const SEARCH_DEPTH: usize = 4;
+
+const MAX_FOUND_FILES: usize = 200;
+
+/// Content lines are folded at this many bytes
+const ICS_LINE_LEN: usize = 75;
+
+lazy_static::lazy_static! {
+    static ref CALENDAR: Mutex<CalendarStore> = Mutex::new(load_store());
+    static ref REMINDERS: Mutex<Option<mpsc::Sender<()>>> = Mutex::new(None);
+}
+
+/// For event ids
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A weekday in a recurrence rule. In monthly and yearly rules `nth` picks
+/// one of them in the month: 2 is the second, -1 the last.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByDay {
+    pub weekday: Weekday,
+    #[serde(default)]
+    pub nth: Option<i8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// Every n days / weeks / months / years
+    #[serde(default = "default_interval")]
+    pub interval: u32,
+    /// Number of occurrences, including the first
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// No occurrences start after this (unix seconds)
+    #[serde(default)]
+    pub until: Option<i64>,
+    #[serde(default)]
+    pub by_day: Vec<ByDay>,
+    /// Days of the month, negative counts from the end
+    #[serde(default)]
+    pub by_month_day: Vec<i8>,
+    /// Months, 1-12
+    #[serde(default)]
+    pub by_month: Vec<u8>,
+}
+
+fn default_interval() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    /// Also the UID in .ics files
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub location: String,
+    /// Unix seconds, local midnight for all-day events
+    pub start: i64,
+    /// Unix seconds, exclusive (the next midnight for a one-day event)
+    pub end: i64,
+    #[serde(default)]
+    pub all_day: bool,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Starts of occurrences left out of the recurrence
+    #[serde(default)]
+    pub exceptions: Vec<i64>,
+    /// Minutes before the start to remind at, negative for after
+    #[serde(default)]
+    pub reminders: Vec<i32>,
+}
+
+/// Event fields set by the user
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventSettings {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub location: String,
+    pub start: i64,
+    pub end: i64,
+    #[serde(default)]
+    pub all_day: bool,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub reminders: Vec<i32>,
+}
+
+/// One occurrence of an event in a queried range
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarOccurrence {
+    pub event_id: String,
+    pub title: String,
+    pub description: String,
+    pub location: String,
+    pub start: i64,
+    pub end: i64,
+    pub all_day: bool,
+    pub recurring: bool,
+}
+
+/// Sent with `calendar-reminder`
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarReminder {
+    pub event_id: String,
+    pub title: String,
+    pub location: String,
+    /// Start of the occurrence (unix seconds)
+    pub start: i64,
+    pub all_day: bool,
+    pub minutes_before: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    /// What couldn't be imported as it was
+    pub warnings: Vec<String>,
+}
+
+/// An .ics file found by `find_calendar_files`
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub modified: u64, // unix timestamp
+    /// On a USB drive rather than in a local folder
+    pub removable: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CalendarStore {
+    events: Vec<CalendarEvent>,
+}
+
+fn store_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/calendar.json"))
+        .unwrap_or_else(|| PathBuf::from("calendar.json"))
+}
+
+fn load_store() -> CalendarStore {
+    fs::read_to_string(store_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(store: &CalendarStore) -> Result<(), String> {
+    let path = store_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(store).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to save calendar: {}", e))
+}
+
+fn new_id() -> String {
+    format!(
+        "{:x}{:x}@honeybee",
+        Local::now().timestamp_millis(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Apply a change to the store, save it and let the reminder thread know
+fn update_store<T>(f: impl FnOnce(&mut CalendarStore) -> Result<T, String>) -> Result<T, String> {
+    let result = {
+        let mut store = CALENDAR.lock();
+        let result = f(&mut store)?;
+        save_store(&store)?;
+        result
+    };
+    if let Some(tx) = REMINDERS.lock().as_ref() {
+        let _ = tx.send(());
+    }
+    Ok(result)
+}
+
+// ============================================================================
+// RECURRENCE
+// ============================================================================
+
+fn local_datetime(ts: i64) -> Option<NaiveDateTime> {
+    Local
+        .timestamp_opt(ts, 0)
+        .single()
+        .map(|at| at.naive_local())
+}
+
+/// Local wall-clock time as unix seconds. Times skipped by a DST change move
+/// an hour ahead, the event still happens that day.
+fn local_timestamp(at: NaiveDateTime) -> Option<i64> {
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .or_else(|| {
+            Local
+                .from_local_datetime(&(at + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map(|at| at.timestamp())
+}
+
+fn midnight(date: NaiveDate) -> Option<i64> {
+    local_timestamp(date.and_time(NaiveTime::MIN))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    (28..=31)
+        .rev()
+        .find(|&day| NaiveDate::from_ymd_opt(year, month, day).is_some())
+        .unwrap_or(0)
+}
+
+/// A by-month-day value as a day of the given month, if it has that day
+fn resolve_month_day(year: i32, month: u32, day: i8) -> Option<u32> {
+    let len = days_in_month(year, month) as i32;
+    let day = if day < 0 {
+        len + 1 + day as i32
+    } else {
+        day as i32
+    };
+    (1..=len).contains(&day).then_some(day as u32)
+}
+
+/// Days of a month matching a by-day value: every such weekday, or the nth
+fn weekday_days(year: i32, month: u32, by: &ByDay) -> Vec<u32> {
+    let weekday: chrono::Weekday = by.weekday.into();
+    let days: Vec<u32> = (1..=days_in_month(year, month))
+        .filter(|&day| {
+            NaiveDate::from_ymd_opt(year, month, day).is_some_and(|date| date.weekday() == weekday)
+        })
+        .collect();
+    let index = match by.nth {
+        None => return days,
+        Some(n) if n > 0 => Some(n as usize - 1),
+        Some(n) => days.len().checked_sub(n.unsigned_abs() as usize),
+    };
+    index
+        .and_then(|i| days.get(i))
+        .copied()
+        .into_iter()
+        .collect()
+}
+
+impl Recurrence {
+    fn weekdays(&self) -> Vec<chrono::Weekday> {
+        self.by_day.iter().map(|by| by.weekday.into()).collect()
+    }
+
+    fn month_matches(&self, month: u32) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&(month as u8))
+    }
+
+    /// Dates the rule produces in one month, for monthly and yearly rules
+    fn month_dates(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        if !self.month_matches(month) {
+            return Vec::new();
+        }
+        let mut days: Vec<u32> = if !self.by_month_day.is_empty() {
+            // By-day only narrows down the month days then
+            let weekdays = self.weekdays();
+            self.by_month_day
+                .iter()
+                .filter_map(|&day| resolve_month_day(year, month, day))
+                .filter(|&day| {
+                    weekdays.is_empty()
+                        || NaiveDate::from_ymd_opt(year, month, day)
+                            .is_some_and(|date| weekdays.contains(&date.weekday()))
+                })
+                .collect()
+        } else if !self.by_day.is_empty() {
+            self.by_day
+                .iter()
+                .flat_map(|by| weekday_days(year, month, by))
+                .collect()
+        } else {
+            // Months without that day (the 31st) are skipped
+            vec![default_day]
+        };
+        days.sort_unstable();
+        days.dedup();
+        days.into_iter()
+            .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+            .collect()
+    }
+
+    /// Which period after `first` `date` falls into, 0 for dates before it
+    fn period_of(&self, first: NaiveDate, date: NaiveDate) -> i64 {
+        let monday = |date: NaiveDate| {
+            date.num_days_from_ce() - date.weekday().num_days_from_monday() as i32
+        };
+        let units = match self.frequency {
+            Frequency::Daily => (date - first).num_days(),
+            Frequency::Weekly => ((monday(date) - monday(first)) / 7) as i64,
+            Frequency::Monthly => {
+                (date.year() - first.year()) as i64 * 12 + date.month() as i64
+                    - first.month() as i64
+            }
+            Frequency::Yearly => (date.year() - first.year()) as i64,
+        };
+        (units / self.interval.max(1) as i64).max(0)
+    }
+
+    /// The first day of the rule's `period`th day / week / month / year after
+    /// `first`, and the dates it produces in it, in order
+    fn period_dates(&self, first: NaiveDate, period: i64) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let step = u64::try_from(period.checked_mul(self.interval.max(1) as i64)?).ok()?;
+        match self.frequency {
+            Frequency::Daily => {
+                let date = first.checked_add_days(Days::new(step))?;
+                let weekdays = self.weekdays();
+                let matches = self.month_matches(date.month())
+                    && (self.by_month_day.is_empty()
+                        || self.by_month_day.iter().any(|&day| {
+                            resolve_month_day(date.year(), date.month(), day) == Some(date.day())
+                        }))
+                    && (weekdays.is_empty() || weekdays.contains(&date.weekday()));
+                Some((date, if matches { vec![date] } else { Vec::new() }))
+            }
+            Frequency::Weekly => {
+                // Weeks start on Monday
+                let monday = first
+                    .checked_sub_days(Days::new(first.weekday().num_days_from_monday() as u64))?
+                    .checked_add_days(Days::new(step.checked_mul(7)?))?;
+                let mut weekdays = self.weekdays();
+                if weekdays.is_empty() {
+                    weekdays.push(first.weekday());
+                }
+                let mut dates: Vec<NaiveDate> = weekdays
+                    .iter()
+                    .filter_map(|day| {
+                        monday.checked_add_days(Days::new(day.num_days_from_monday() as u64))
+                    })
+                    .filter(|date| self.month_matches(date.month()))
+                    .collect();
+                dates.sort_unstable();
+                dates.dedup();
+                Some((monday, dates))
+            }
+            Frequency::Monthly => {
+                let month = first
+                    .with_day(1)?
+                    .checked_add_months(Months::new(u32::try_from(step).ok()?))?;
+                let dates = self.month_dates(month.year(), month.month(), first.day());
+                Some((month, dates))
+            }
+            Frequency::Yearly => {
+                let year = first.year().checked_add(i32::try_from(step).ok()?)?;
+                let months: Vec<u32> = if !self.by_month.is_empty() {
+                    self.by_month.iter().map(|&month| month as u32).collect()
+                } else if !self.by_month_day.is_empty() {
+                    // Month days without a month are in every month (RFC 5545)
+                    (1..=12).collect()
+                } else {
+                    vec![first.month()]
+                };
+                let dates = months
+                    .into_iter()
+                    .flat_map(|month| self.month_dates(year, month, first.day()))
+                    .collect();
+                Some((NaiveDate::from_ymd_opt(year, 1, 1)?, dates))
+            }
+        }
+    }
+}
+
+/// Call `f` with the start of each occurrence of `event` up to `to`, in
+/// order, until it returns false. Periods of the rule that end before `from`
+/// are skipped, some earlier occurrences may still be passed to `f`.
+fn for_each_occurrence(event: &CalendarEvent, from: i64, to: i64, mut f: impl FnMut(i64) -> bool) {
+    if event.start > to {
+        return;
+    }
+    let Some(rule) = &event.recurrence else {
+        f(event.start);
+        return;
+    };
+    let (Some(first), Some(last)) = (local_datetime(event.start), local_datetime(to)) else {
+        return;
+    };
+
+    // The first occurrence counts even if the rule wouldn't produce it
+    let mut count = 1;
+    if !event.exceptions.contains(&event.start) && !f(event.start) {
+        return;
+    }
+    // Occurrences before `from` still count towards `count`, those rules are
+    // expanded from the start. One period less for DST and time of day.
+    let skip = match (rule.count, local_datetime(from)) {
+        (None, Some(from)) => (rule.period_of(first.date(), from.date()) - 1).max(0),
+        _ => 0,
+    };
+    for period in skip..skip.saturating_add(MAX_PERIODS) {
+        let Some((period_start, dates)) = rule.period_dates(first.date(), period) else {
+            return;
+        };
+        if period_start > last.date() {
+            return;
+        }
+        for date in dates.into_iter().filter(|date| *date > first.date()) {
+            let Some(start) = local_timestamp(date.and_time(first.time())) else {
+                continue;
+            };
+            if start > to
+                || rule.until.is_some_and(|until| start > until)
+                || rule.count.is_some_and(|max| count >= max)
+            {
+                return;
+            }
+            // Left out occurrences still count towards `count`
+            count += 1;
+            if !event.exceptions.contains(&start) && !f(start) {
+                return;
+            }
+        }
+    }
+}
+
+/// End of an occurrence starting at `start`. All-day events end at a
+/// midnight even when a DST change makes one of their days shorter.
+fn occurrence_end(event: &CalendarEvent, start: i64) -> i64 {
+    let length = event.end - event.start;
+    if !event.all_day || start == event.start {
+        return start + length;
+    }
+    let days = ((length + 43_200) / 86_400).max(1) as u64;
+    local_datetime(start)
+        .and_then(|at| at.date().checked_add_days(Days::new(days)))
+        .and_then(midnight)
+        .unwrap_or(start + length)
+}
+
+/// Reminders due after `from` and up to `to`, in order
+fn due_reminders(events: &[CalendarEvent], from: i64, to: i64) -> Vec<(i64, CalendarReminder)> {
+    let mut due = Vec::new();
+    for event in events {
+        let (Some(&lead), Some(&least)) =
+            (event.reminders.iter().max(), event.reminders.iter().min())
+        else {
+            continue;
+        };
+        // Only occurrences starting after `from` plus the shortest lead remind now
+        let earliest = from + least as i64 * 60;
+        for_each_occurrence(event, earliest, to + lead.max(0) as i64 * 60, |start| {
+            for &minutes in &event.reminders {
+                let at = start - minutes as i64 * 60;
+                if at > from && at <= to {
+                    due.push((
+                        at,
+                        CalendarReminder {
+                            event_id: event.id.clone(),
+                            title: event.title.clone(),
+                            location: event.location.clone(),
+                            start,
+                            all_day: event.all_day,
+                            minutes_before: minutes,
+                        },
+                    ));
+                }
+            }
+            true
+        });
+    }
+    due.sort_by_key(|(at, _)| *at);
+    due
+}
+
+/// Start the thread that emits reminders
+pub fn start_calendar_reminders(app: AppHandle) {
+    let (tx, rx) = mpsc::channel();
+    *REMINDERS.lock() = Some(tx);
+
+    thread::spawn(move || {
+        // Catch up on reminders that came due shortly before the app started
+        let mut last_check = Local::now().timestamp() - MISSED_GRACE_SECS;
+        println!(
+            "📅 Calendar reminders started ({} events)",
+            CALENDAR.lock().events.len()
+        );
+
+        loop {
+            // Expanding rules takes a while, don't block calendar commands
+            let events = CALENDAR.lock().events.clone();
+            let now = Local::now();
+            let next = due_reminders(
+                &events,
+                now.timestamp(),
+                now.timestamp() + MAX_WAIT.as_secs() as i64,
+            )
+            .first()
+            .map(|(at, _)| *at);
+            let wait = next
+                .map(|at| Duration::from_millis((at * 1000 - now.timestamp_millis()).max(0) as u64))
+                .unwrap_or(MAX_WAIT)
+                .min(MAX_WAIT);
+
+            match rx.recv_timeout(wait) {
+                Ok(()) => {
+                    let _ = app.emit("calendar-changed", ());
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let now = Local::now().timestamp();
+            // Never look back further than the grace period, e.g. when the
+            // clock jumps forward on NTP sync
+            let from = last_check.max(now - MISSED_GRACE_SECS).min(now);
+            let events = CALENDAR.lock().events.clone();
+            let due = due_reminders(&events, from, now);
+            last_check = now;
+            for (_, reminder) in due {
+                println!("🔔 Reminder: {}", reminder.title);
+                let _ = app.emit("calendar-reminder", &reminder);
+            }
+        }
+    });
+}
+
+// ============================================================================
+// VALIDATION
+// ============================================================================
+
+fn validate_text(text: &str, what: &str, max: usize) -> Result<String, String> {
+    let text = text.trim();
+    if text.chars().count() > max {
+        return Err(format!("{} is longer than {} characters", what, max));
+    }
+    Ok(text.to_string())
+}
+
+/// Check a rule and put its lists in order
+fn validate_recurrence(rule: &mut Recurrence) -> Result<(), String> {
+    if rule.interval == 0 {
+        return Err("Repeat interval must be at least 1".to_string());
+    }
+    if rule.count == Some(0) {
+        return Err("Repeat count must be at least 1".to_string());
+    }
+    if let Some(month) = rule.by_month.iter().find(|m| !(1..=12).contains(*m)) {
+        return Err(format!("Invalid month {}", month));
+    }
+    if let Some(day) = rule.by_month_day.iter().find(|d| **d == 0 || d.abs() > 31) {
+        return Err(format!("Invalid day of the month {}", day));
+    }
+    let nths = rule.by_day.iter().filter_map(|by| by.nth);
+    if let Some(nth) = nths.clone().find(|n| *n == 0 || n.abs() > 5) {
+        return Err(format!("Invalid weekday position {}", nth));
+    }
+    if rule.frequency == Frequency::Yearly && !rule.by_day.is_empty() && rule.by_month.is_empty() {
+        return Err("Yearly repeats on a weekday need a month".to_string());
+    }
+    if matches!(rule.frequency, Frequency::Daily | Frequency::Weekly) && nths.count() > 0 {
+        return Err("Weekday positions only apply to monthly and yearly repeats".to_string());
+    }
+    rule.by_day.sort_unstable();
+    rule.by_day.dedup();
+    rule.by_month_day.sort_unstable();
+    rule.by_month_day.dedup();
+    rule.by_month.sort_unstable();
+    rule.by_month.dedup();
+    Ok(())
+}
+
+fn build_event(id: String, settings: EventSettings) -> Result<CalendarEvent, String> {
+    let title = validate_text(&settings.title, "Title", MAX_TITLE_LEN)?;
+    let description = validate_text(&settings.description, "Description", MAX_TEXT_LEN)?;
+    let location = validate_text(&settings.location, "Location", MAX_TEXT_LEN)?;
+    if settings.end < settings.start {
+        return Err("Event ends before it starts".to_string());
+    }
+
+    let (mut start, mut end) = (settings.start, settings.end);
+    if settings.all_day {
+        // Whole days from local midnight to midnight
+        let invalid = || "Invalid event time".to_string();
+        let first = local_datetime(start).ok_or_else(invalid)?.date();
+        let last = local_datetime(end).ok_or_else(invalid)?;
+        let mut end_date = if last.time() == NaiveTime::MIN {
+            last.date()
+        } else {
+            last.date().succ_opt().ok_or_else(invalid)?
+        };
+        if end_date <= first {
+            end_date = first.succ_opt().ok_or_else(invalid)?;
+        }
+        start = midnight(first).ok_or_else(invalid)?;
+        end = midnight(end_date).ok_or_else(invalid)?;
+    }
+
+    let mut recurrence = settings.recurrence;
+    if let Some(rule) = recurrence.as_mut() {
+        validate_recurrence(rule)?;
+    }
+
+    let mut reminders = settings.reminders;
+    if reminders.len() > MAX_REMINDERS {
+        return Err(format!("At most {} reminders per event", MAX_REMINDERS));
+    }
+    if reminders.iter().any(|m| m.abs() > MAX_REMINDER_MINUTES) {
+        return Err("Reminders can be at most four weeks from the event".to_string());
+    }
+    reminders.sort_unstable();
+    reminders.dedup();
+
+    Ok(CalendarEvent {
+        id,
+        title,
+        description,
+        location,
+        start,
+        end,
+        all_day: settings.all_day,
+        recurrence,
+        exceptions: Vec::new(),
+        reminders,
+    })
+}
+
+// ============================================================================
+// ICS FILES
+// ============================================================================
+
+/// A content line: `NAME;PARAM=value:value`
+struct Property {
+    name: String,
+    params: HashMap<String, String>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// An event read from a file, before it's merged into the calendar
+struct ParsedEvent {
+    uid: Option<String>,
+    /// Set when this replaces one occurrence of a repeating event
+    recurrence_id: Option<i64>,
+    cancelled: bool,
+    event: CalendarEvent,
+}
+
+/// Join folded lines, continuations start with a space or tab
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let continuation = line.strip_prefix([' ', '\t']);
+        match (continuation, lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter
+    let mut quoted = false;
+    let (colon, _) = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?;
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((
+                key.trim().to_ascii_uppercase(),
+                value.trim_matches('"').to_string(),
+            ))
+        })
+        .collect();
+    Some(Property {
+        name,
+        params,
+        value: line[colon + 1..].to_string(),
+    })
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// A DATE or DATE-TIME value as unix seconds, and whether it was a date
+fn parse_ics_time(value: &str) -> Result<(i64, bool), String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid date: {}", value);
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+        return midnight(date).map(|ts| (ts, true)).ok_or_else(invalid);
+    }
+    let (local, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(local) => (local, true),
+        None => (value, false),
+    };
+    let at = NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+    let ts = if utc {
+        Utc.from_utc_datetime(&at).timestamp()
+    } else {
+        local_timestamp(at).ok_or_else(invalid)?
+    };
+    Ok((ts, false))
+}
+
+/// A duration like `P1D`, `PT1H30M` or `-PT15M`, in seconds
+fn parse_ics_duration(value: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid duration: {}", value);
+    let value = value.trim();
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix(['P', 'p']).ok_or_else(invalid)?;
+
+    let mut seconds: i64 = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    let mut any = false;
+    for c in rest.chars().map(|c| c.to_ascii_uppercase()) {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        if c == 'T' && number.is_empty() && !in_time {
+            in_time = true;
+            continue;
+        }
+        let unit = match (c, in_time) {
+            ('W', false) => 7 * 86_400,
+            ('D', false) => 86_400,
+            ('H', true) => 3600,
+            ('M', true) => 60,
+            ('S', true) => 1,
+            _ => return Err(invalid()),
+        };
+        let n: i64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        seconds = n
+            .checked_mul(unit)
+            .and_then(|s| seconds.checked_add(s))
+            .ok_or_else(invalid)?;
+        any = true;
+    }
+    if !any || !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(sign * seconds)
+}
+
+fn weekday_from_code(code: &str) -> Option<Weekday> {
+    Some(match code.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return None,
+    })
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Monday => "MO",
+        Weekday::Tuesday => "TU",
+        Weekday::Wednesday => "WE",
+        Weekday::Thursday => "TH",
+        Weekday::Friday => "FR",
+        Weekday::Saturday => "SA",
+        Weekday::Sunday => "SU",
+    }
+}
+
+/// `MO`, `2TU` or `-1FR`
+fn parse_by_day(value: &str) -> Result<ByDay, String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid weekday in recurrence rule: {}", value);
+    let split = value
+        .len()
+        .checked_sub(2)
+        .filter(|&i| value.is_char_boundary(i))
+        .ok_or_else(invalid)?;
+    let (nth, code) = value.split_at(split);
+    Ok(ByDay {
+        weekday: weekday_from_code(code).ok_or_else(invalid)?,
+        nth: match nth {
+            "" => None,
+            nth => Some(nth.parse().map_err(|_| invalid())?),
+        },
+    })
+}
+
+fn parse_rrule(value: &str) -> Result<Recurrence, String> {
+    let mut frequency = None;
+    let mut rule = Recurrence {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+        by_month: Vec::new(),
+    };
+
+    for part in value.trim().split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid recurrence rule: {}", part))?;
+        let key = key.to_ascii_uppercase();
+        let invalid = || format!("Invalid {} in recurrence rule: {}", key, value);
+        match key.as_str() {
+            "FREQ" => {
+                frequency = Some(match value.to_ascii_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    other => return Err(format!("{} repeats are not supported", other)),
+                })
+            }
+            "INTERVAL" => rule.interval = value.parse().map_err(|_| invalid())?,
+            "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid())?),
+            "UNTIL" => {
+                rule.until = Some(match parse_ics_time(value)? {
+                    // A date includes the whole day
+                    (ts, true) => local_datetime(ts)
+                        .and_then(|at| at.date().succ_opt())
+                        .and_then(midnight)
+                        .map(|next| next - 1)
+                        .unwrap_or(ts),
+                    (ts, false) => ts,
+                })
+            }
+            "BYDAY" => {
+                rule.by_day = value
+                    .split(',')
+                    .map(parse_by_day)
+                    .collect::<Result<_, _>>()?
+            }
+            "BYMONTHDAY" => {
+                rule.by_month_day = value
+                    .split(',')
+                    .map(|day| day.trim().parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?
+            }
+            "BYMONTH" => {
+                rule.by_month = value
+                    .split(',')
+                    .map(|month| month.trim().parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?
+            }
+            // Weeks always start on Monday here
+            "WKST" => {}
+            other => return Err(format!("{} in recurrence rules is not supported", other)),
+        }
+    }
+
+    rule.frequency = frequency.ok_or("Recurrence rule has no frequency")?;
+    validate_recurrence(&mut rule)?;
+    Ok(rule)
+}
+
+/// A VALARM trigger as minutes before the start, if it's a supported one
+fn parse_trigger(alarm: &[Property], start: i64, end: i64) -> Option<i32> {
+    let trigger = alarm.iter().find(|p| p.name == "TRIGGER")?;
+    let seconds_before = if trigger.param("VALUE") == Some("DATE-TIME") {
+        start - parse_ics_time(&trigger.value).ok()?.0
+    } else {
+        let offset = parse_ics_duration(&trigger.value).ok()?;
+        match trigger.param("RELATED") {
+            Some("END") => start - (end + offset),
+            _ => -offset,
+        }
+    };
+    let minutes = i32::try_from(seconds_before / 60).ok()?;
+    (minutes.abs() <= MAX_REMINDER_MINUTES).then_some(minutes)
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    text.trim().chars().take(max).collect()
+}
+
+fn parse_vevent(
+    props: &[Property],
+    alarms: &[Vec<Property>],
+    warnings: &mut Vec<String>,
+    time_zones: &mut BTreeSet<String>,
+) -> Result<ParsedEvent, String> {
+    let find = |name: &str| props.iter().find(|p| p.name == name);
+    let text = |name: &str, max: usize| {
+        find(name)
+            .map(|p| truncate(&unescape_text(&p.value), max))
+            .unwrap_or_default()
+    };
+    let title = text("SUMMARY", MAX_TITLE_LEN);
+    let name = if title.is_empty() {
+        "Untitled event".to_string()
+    } else {
+        format!("'{}'", title)
+    };
+    let mut parse_time = |prop: &Property| {
+        if let Some(tzid) = prop.param("TZID") {
+            time_zones.insert(tzid.to_string());
+        }
+        parse_ics_time(&prop.value)
+    };
+
+    let dtstart = find("DTSTART").ok_or_else(|| format!("{} has no start time", name))?;
+    let (start, all_day) = parse_time(dtstart).map_err(|e| format!("{}: {}", name, e))?;
+    let end = if let Some(dtend) = find("DTEND") {
+        parse_time(dtend).map_err(|e| format!("{}: {}", name, e))?.0
+    } else if let Some(duration) = find("DURATION") {
+        start + parse_ics_duration(&duration.value).map_err(|e| format!("{}: {}", name, e))?
+    } else if all_day {
+        start + 86_400
+    } else {
+        start
+    };
+    let recurrence_id = match find("RECURRENCE-ID") {
+        Some(prop) => Some(parse_time(prop).map_err(|e| format!("{}: {}", name, e))?.0),
+        None => None,
+    };
+    let mut exceptions = Vec::new();
+    for prop in props.iter().filter(|p| p.name == "EXDATE") {
+        for value in prop.value.split(',') {
+            if let Ok((ts, _)) = parse_ics_time(value) {
+                exceptions.push(ts);
+            }
+        }
+    }
+
+    let recurrence = match find("RRULE").map(|p| parse_rrule(&p.value)) {
+        Some(Ok(rule)) => Some(rule),
+        Some(Err(e)) => {
+            warnings.push(format!(
+                "{}: {}, only the first occurrence was imported",
+                name, e
+            ));
+            None
+        }
+        None => None,
+    };
+    let mut reminders: Vec<i32> = alarms
+        .iter()
+        .filter_map(|alarm| parse_trigger(alarm, start, end))
+        .collect();
+    reminders.truncate(MAX_REMINDERS);
+
+    let settings = EventSettings {
+        title,
+        description: text("DESCRIPTION", MAX_TEXT_LEN),
+        location: text("LOCATION", MAX_TEXT_LEN),
+        start,
+        end: end.max(start),
+        all_day,
+        recurrence,
+        reminders,
+    };
+    let mut event = build_event(String::new(), settings).map_err(|e| format!("{}: {}", name, e))?;
+    event.exceptions = exceptions;
+
+    Ok(ParsedEvent {
+        uid: find("UID")
+            .map(|p| unescape_text(p.value.trim()))
+            .filter(|uid| !uid.is_empty()),
+        recurrence_id,
+        cancelled: find("STATUS").is_some_and(|p| p.value.trim().eq_ignore_ascii_case("CANCELLED")),
+        event,
+    })
+}
+
+/// The events in an iCalendar file, with warnings and the number skipped
+fn parse_ics(text: &str) -> Result<(Vec<ParsedEvent>, Vec<String>, usize), String> {
+    let lines = unfold(text.trim_start_matches('\u{feff}'));
+    if !lines
+        .first()
+        .is_some_and(|line| line.trim().eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        return Err("Not an iCalendar file".to_string());
+    }
+
+    let mut events = Vec::new();
+    let mut warnings = Vec::new();
+    let mut skipped = 0;
+    let mut time_zones = BTreeSet::new();
+    // Open components, innermost last
+    let mut components: Vec<String> = Vec::new();
+    let mut props = Vec::new();
+    let mut alarms: Vec<Vec<Property>> = Vec::new();
+
+    for line in lines {
+        let Some(prop) = parse_property(&line) else {
+            continue;
+        };
+        let parent = components
+            .len()
+            .checked_sub(2)
+            .map(|i| components[i].as_str());
+        match prop.name.as_str() {
+            "BEGIN" => {
+                let component = prop.value.trim().to_ascii_uppercase();
+                if component == "VEVENT" {
+                    props.clear();
+                    alarms.clear();
+                } else if component == "VALARM" && components.last().is_some_and(|c| c == "VEVENT")
+                {
+                    alarms.push(Vec::new());
+                }
+                components.push(component);
+            }
+            "END" => {
+                if components.pop().as_deref() == Some("VEVENT") {
+                    match parse_vevent(&props, &alarms, &mut warnings, &mut time_zones) {
+                        Ok(event) => events.push(event),
+                        Err(e) => {
+                            warnings.push(e);
+                            skipped += 1;
+                        }
+                    }
+                }
+            }
+            _ => match components.last().map(String::as_str) {
+                Some("VEVENT") => props.push(prop),
+                Some("VALARM") if parent == Some("VEVENT") => {
+                    if let Some(alarm) = alarms.last_mut() {
+                        alarm.push(prop);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    for tzid in time_zones {
+        warnings.push(format!("Times in {} were read as local time", tzid));
+    }
+    Ok((events, warnings, skipped))
+}
+
+fn format_local(ts: i64, date_only: bool) -> String {
+    let format = if date_only { "%Y%m%d" } else { "%Y%m%dT%H%M%S" };
+    local_datetime(ts)
+        .map(|at| at.format(format).to_string())
+        .unwrap_or_default()
+}
+
+fn format_utc(ts: i64) -> String {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .map(|at| at.format("%Y%m%dT%H%M%SZ").to_string())
+        .unwrap_or_default()
+}
+
+fn format_rrule(rule: &Recurrence, time: impl Fn(i64) -> String) -> String {
+    let mut parts = vec![format!(
+        "FREQ={}",
+        match rule.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    )];
+    if rule.interval > 1 {
+        parts.push(format!("INTERVAL={}", rule.interval));
+    }
+    if let Some(count) = rule.count {
+        parts.push(format!("COUNT={}", count));
+    }
+    if let Some(until) = rule.until {
+        parts.push(format!("UNTIL={}", time(until)));
+    }
+    let join = |values: Vec<String>| values.join(",");
+    if !rule.by_day.is_empty() {
+        let days = rule
+            .by_day
+            .iter()
+            .map(|by| match by.nth {
+                Some(nth) => format!("{}{}", nth, weekday_code(by.weekday)),
+                None => weekday_code(by.weekday).to_string(),
+            })
+            .collect();
+        parts.push(format!("BYDAY={}", join(days)));
+    }
+    if !rule.by_month_day.is_empty() {
+        let days = rule.by_month_day.iter().map(|d| d.to_string()).collect();
+        parts.push(format!("BYMONTHDAY={}", join(days)));
+    }
+    if !rule.by_month.is_empty() {
+        let months = rule.by_month.iter().map(|m| m.to_string()).collect();
+        parts.push(format!("BYMONTH={}", join(months)));
+    }
+    parts.join(";")
+}
+
+fn event_to_ics(event: &CalendarEvent, stamp: &str, lines: &mut Vec<String>) {
+    // One-off events are exported in UTC. Repeating ones use floating local
+    // time, in UTC they would move by an hour on DST changes.
+    let param = if event.all_day { ";VALUE=DATE" } else { "" };
+    let time = |ts: i64| {
+        if event.all_day || event.recurrence.is_some() {
+            format_local(ts, event.all_day)
+        } else {
+            format_utc(ts)
+        }
+    };
+
+    lines.push("BEGIN:VEVENT".to_string());
+    lines.push(format!("UID:{}", escape_text(&event.id)));
+    lines.push(format!("DTSTAMP:{}", stamp));
+    lines.push(format!("DTSTART{}:{}", param, time(event.start)));
+    lines.push(format!("DTEND{}:{}", param, time(event.end)));
+    lines.push(format!("SUMMARY:{}", escape_text(&event.title)));
+    if !event.description.is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape_text(&event.description)));
+    }
+    if !event.location.is_empty() {
+        lines.push(format!("LOCATION:{}", escape_text(&event.location)));
+    }
+    if let Some(rule) = &event.recurrence {
+        lines.push(format!("RRULE:{}", format_rrule(rule, time)));
+        for exception in &event.exceptions {
+            lines.push(format!("EXDATE{}:{}", param, time(*exception)));
+        }
+    }
+    for minutes in &event.reminders {
+        let trigger = if *minutes >= 0 {
+            format!("-PT{}M", minutes)
+        } else {
+            format!("PT{}M", -minutes)
+        };
+        lines.push("BEGIN:VALARM".to_string());
+        lines.push("ACTION:DISPLAY".to_string());
+        lines.push(format!("DESCRIPTION:{}", escape_text(&event.title)));
+        lines.push(format!("TRIGGER:{}", trigger));
+        lines.push("END:VALARM".to_string());
+    }
+    lines.push("END:VEVENT".to_string());
+}
+
+/// Fold a content line into lines of at most `ICS_LINE_LEN` bytes
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > ICS_LINE_LEN {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded
+}
+
+fn calendar_to_ics(events: &[CalendarEvent]) -> String {
+    let stamp = format_utc(Utc::now().timestamp());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Honeybee//Calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for event in events {
+        event_to_ics(event, &stamp, &mut lines);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics: String = lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    ics.push_str("\r\n");
+    ics
+}
+
+fn has_ics_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(ICS_EXTENSION))
+}
+
+fn find_ics_files(dir: &Path, depth: usize, removable: bool, found: &mut Vec<CalendarFile>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if found.len() >= MAX_FOUND_FILES {
+            return;
+        }
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        // Symlinks aren't followed, they could loop
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() && depth > 1 {
+            find_ics_files(&path, depth - 1, removable, found);
+        } else if file_type.is_file() && has_ics_extension(&path) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            found.push(CalendarFile {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: path.to_string_lossy().to_string(),
+                size: metadata.len(),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+                removable,
+            });
+        }
+    }
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+/// Occurrences overlapping `from`..`to` (unix seconds), by start time
+#[tauri::command]
+pub fn list_calendar_events(from: i64, to: i64) -> Result<Vec<CalendarOccurrence>, String> {
+    if to < from {
+        return Err("Range ends before it starts".to_string());
+    }
+    if to - from > MAX_RANGE_DAYS * 86_400 {
+        return Err(format!("Range is longer than {} days", MAX_RANGE_DAYS));
+    }
+
+    let store = CALENDAR.lock();
+    let mut occurrences = Vec::new();
+    for event in &store.events {
+        // Occurrences that started earlier may still be going on
+        let earliest = from - (event.end - event.start);
+        for_each_occurrence(event, earliest, to, |start| {
+            let end = occurrence_end(event, start);
+            if end > from || start >= from {
+                occurrences.push(CalendarOccurrence {
+                    event_id: event.id.clone(),
+                    title: event.title.clone(),
+                    description: event.description.clone(),
+                    location: event.location.clone(),
+                    start,
+                    end,
+                    all_day: event.all_day,
+                    recurring: event.recurrence.is_some(),
+                });
+            }
+            occurrences.len() < MAX_OCCURRENCES
+        });
+        if occurrences.len() >= MAX_OCCURRENCES {
+            break;
+        }
+    }
+    occurrences.sort_by(|a, b| (a.start, &a.title).cmp(&(b.start, &b.title)));
+    Ok(occurrences)
+}
+
+#[tauri::command]
+pub fn get_calendar_event(id: String) -> Result<CalendarEvent, String> {
+    CALENDAR
+        .lock()
+        .events
+        .iter()
+        .find(|e| e.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Event not found: {}", id))
+}
+
+#[tauri::command]
+pub fn create_calendar_event(event: EventSettings) -> Result<CalendarEvent, String> {
+    let event = build_event(new_id(), event)?;
+    update_store(|store| {
+        store.events.push(event.clone());
+        Ok(event)
+    })
+}
+
+#[tauri::command]
+pub fn update_calendar_event(id: String, event: EventSettings) -> Result<CalendarEvent, String> {
+    let mut updated = build_event(id.clone(), event)?;
+    update_store(|store| {
+        let event = store
+            .events
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| format!("Event not found: {}", id))?;
+        // Left out occurrences only still apply if the series didn't move
+        if updated.start == event.start && updated.recurrence.is_some() {
+            updated.exceptions = std::mem::take(&mut event.exceptions);
+        }
+        *event = updated.clone();
+        Ok(updated)
+    })
+}
+
+#[tauri::command]
+pub fn delete_calendar_event(id: String) -> Result<(), String> {
+    update_store(|store| {
+        let before = store.events.len();
+        store.events.retain(|e| e.id != id);
+        if store.events.len() == before {
+            return Err(format!("Event not found: {}", id));
+        }
+        Ok(())
+    })
+}
+
+/// Leave one occurrence (by its start) out of a repeating event
+#[tauri::command]
+pub fn delete_calendar_occurrence(id: String, start: i64) -> Result<(), String> {
+    update_store(|store| {
+        let event = store
+            .events
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| format!("Event not found: {}", id))?;
+        if event.recurrence.is_none() {
+            return Err("Event doesn't repeat".to_string());
+        }
+        if !event.exceptions.contains(&start) {
+            event.exceptions.push(start);
+            event.exceptions.sort_unstable();
+        }
+        Ok(())
+    })
+}
+
+/// Import the events of an .ics file. Events already in the calendar (by
+/// UID) are replaced, so importing the same file again doesn't duplicate.
+#[tauri::command]
+pub async fn import_calendar(path: String) -> Result<ImportSummary, String> {
+    let path = PathBuf::from(path);
+    if !has_ics_extension(&path) || !path.is_file() {
+        return Err(format!("Not an .ics file: {}", path.display()));
+    }
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size > MAX_ICS_BYTES {
+        return Err(format!(
+            "File is larger than {} MB",
+            MAX_ICS_BYTES / 1024 / 1024
+        ));
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let (mut parsed, warnings, skipped) = parse_ics(&String::from_utf8_lossy(&bytes))?;
+
+    // Series first, so changed occurrences find their series
+    parsed.sort_by_key(|p| p.recurrence_id.is_some());
+    let summary = update_store(|store| {
+        let mut summary = ImportSummary {
+            skipped,
+            warnings,
+            ..Default::default()
+        };
+        for ParsedEvent {
+            uid,
+            recurrence_id,
+            cancelled,
+            mut event,
+        } in parsed
+        {
+            let uid = uid.unwrap_or_else(new_id);
+            event.id = uid.clone();
+
+            // A changed occurrence becomes its own event, left out of the series
+            if let Some(recurrence_id) = recurrence_id {
+                if let Some(series) = store.events.iter_mut().find(|e| e.id == uid) {
+                    if !series.exceptions.contains(&recurrence_id) {
+                        series.exceptions.push(recurrence_id);
+                        series.exceptions.sort_unstable();
+                    }
+                }
+                event.id = format!("{}/{}", uid, recurrence_id);
+            }
+            if cancelled {
+                store.events.retain(|e| e.id != event.id);
+                summary.skipped += 1;
+                continue;
+            }
+            match store.events.iter_mut().find(|e| e.id == event.id) {
+                Some(existing) => {
+                    *existing = event;
+                    summary.updated += 1;
+                }
+                None => {
+                    store.events.push(event);
+                    summary.added += 1;
+                }
+            }
+        }
+        Ok(summary)
+    })?;
+
+    println!(
+        "📅 Imported {}: {} added, {} updated, {} skipped",
+        path.display(),
+        summary.added,
+        summary.updated,
+        summary.skipped
+    );
+    Ok(summary)
+}
+
+/// Write every event to an .ics file. Given a folder (like a USB drive), the
+/// file is created in it. Returns the file written.
+#[tauri::command]
+pub async fn export_calendar(path: String) -> Result<String, String> {
+    let mut path = PathBuf::from(path);
+    if path.is_dir() {
+        path = path.join(EXPORT_FILE_NAME);
+    } else if !has_ics_extension(&path) {
+        return Err("Export file must end in .ics".to_string());
+    }
+    match path.parent() {
+        Some(parent) if parent.is_dir() => {}
+        _ => return Err(format!("Folder not found for {}", path.display())),
+    }
+
+    let (ics, count) = {
+        let store = CALENDAR.lock();
+        (calendar_to_ics(&store.events), store.events.len())
+    };
+    write_atomic(&path, ics).map_err(|e| format!("Failed to export calendar: {}", e))?;
+
+    println!("📅 Exported {} events to {}", count, path.display());
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// .ics files on USB drives and in Downloads / Documents, newest first
+#[tauri::command]
+pub async fn find_calendar_files() -> Vec<CalendarFile> {
+    let mut found = Vec::new();
+    for root in REMOVABLE_ROOTS {
+        find_ics_files(Path::new(root), SEARCH_DEPTH, true, &mut found);
+    }
+    for dir in [dirs::download_dir(), dirs::document_dir()]
+        .into_iter()
+        .flatten()
+    {
+        find_ics_files(&dir, SEARCH_DEPTH, false, &mut found);
+    }
+    found.sort_by_key(|file| std::cmp::Reverse(file.modified));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Once;
+
+    /// Expansion is in local time, pin it to a zone with DST
+    fn berlin() {
+        static TZ: Once = Once::new();
+        TZ.call_once(|| std::env::set_var("TZ", "Europe/Berlin"));
+    }
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn ts(year: i32, month: u32, day: u32, hour: u32) -> i64 {
+        local_timestamp(at(year, month, day, hour)).unwrap()
+    }
+
+    fn repeating(start: i64, rrule: &str) -> CalendarEvent {
+        let settings = EventSettings {
+            title: "Test".to_string(),
+            description: String::new(),
+            location: String::new(),
+            start,
+            end: start + 3600,
+            all_day: false,
+            recurrence: Some(parse_rrule(rrule).unwrap()),
+            reminders: Vec::new(),
+        };
+        build_event("test@honeybee".to_string(), settings).unwrap()
+    }
+
+    fn occurrences(event: &CalendarEvent, to: i64) -> Vec<NaiveDateTime> {
+        let mut starts = Vec::new();
+        for_each_occurrence(event, i64::MIN, to, |start| {
+            starts.push(local_datetime(start).unwrap());
+            true
+        });
+        starts
+    }
+
+    #[test]
+    fn count_includes_dtstart() {
+        berlin();
+        let event = repeating(ts(2026, 1, 5, 9), "FREQ=DAILY;COUNT=3");
+        assert_eq!(
+            occurrences(&event, ts(2026, 2, 1, 0)),
+            [at(2026, 1, 5, 9), at(2026, 1, 6, 9), at(2026, 1, 7, 9)]
+        );
+
+        // Also when the rule itself wouldn't produce the start (a Monday)
+        let event = repeating(ts(2026, 1, 5, 9), "FREQ=WEEKLY;BYDAY=WE;COUNT=3");
+        assert_eq!(
+            occurrences(&event, ts(2026, 2, 1, 0)),
+            [at(2026, 1, 5, 9), at(2026, 1, 7, 9), at(2026, 1, 14, 9)]
+        );
+    }
+
+    #[test]
+    fn until_date_includes_the_whole_day() {
+        berlin();
+        let event = repeating(ts(2026, 1, 5, 9), "FREQ=DAILY;UNTIL=20260107");
+        assert_eq!(
+            occurrences(&event, ts(2026, 2, 1, 0)),
+            [at(2026, 1, 5, 9), at(2026, 1, 6, 9), at(2026, 1, 7, 9)]
+        );
+    }
+
+    #[test]
+    fn monthly_last_friday() {
+        berlin();
+        let event = repeating(ts(2026, 1, 30, 18), "FREQ=MONTHLY;BYDAY=-1FR;COUNT=4");
+        assert_eq!(
+            occurrences(&event, ts(2027, 1, 1, 0)),
+            [
+                at(2026, 1, 30, 18),
+                at(2026, 2, 27, 18),
+                at(2026, 3, 27, 18),
+                at(2026, 4, 24, 18),
+            ]
+        );
+    }
+
+    #[test]
+    fn month_day_31_skips_short_months() {
+        berlin();
+        let expected = [
+            at(2026, 1, 31, 9),
+            at(2026, 3, 31, 9),
+            at(2026, 5, 31, 9),
+            at(2026, 7, 31, 9),
+        ];
+        let event = repeating(ts(2026, 1, 31, 9), "FREQ=MONTHLY;BYMONTHDAY=31;COUNT=4");
+        assert_eq!(occurrences(&event, ts(2027, 1, 1, 0)), expected);
+        let event = repeating(ts(2026, 1, 31, 9), "FREQ=MONTHLY;COUNT=4");
+        assert_eq!(occurrences(&event, ts(2027, 1, 1, 0)), expected);
+    }
+
+    #[test]
+    fn yearly_month_day_without_month_is_every_month() {
+        berlin();
+        let event = repeating(ts(2026, 1, 1, 8), "FREQ=YEARLY;BYMONTHDAY=1;COUNT=3");
+        assert_eq!(
+            occurrences(&event, ts(2027, 1, 1, 0)),
+            [at(2026, 1, 1, 8), at(2026, 2, 1, 8), at(2026, 3, 1, 8)]
+        );
+    }
+
+    #[test]
+    fn exdate_leaves_out_an_occurrence_but_counts() {
+        berlin();
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   UID:exdate@test\r\n\
+                   SUMMARY:Standup\r\n\
+                   DTSTART:20260105T090000\r\n\
+                   DTEND:20260105T091500\r\n\
+                   RRULE:FREQ=DAILY;COUNT=4\r\n\
+                   EXDATE:20260106T090000\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let (events, warnings, skipped) = parse_ics(ics).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!((events.len(), skipped), (1, 0));
+        assert_eq!(
+            occurrences(&events[0].event, ts(2026, 2, 1, 0)),
+            [at(2026, 1, 5, 9), at(2026, 1, 7, 9), at(2026, 1, 8, 9)]
+        );
+    }
+
+    #[test]
+    fn weekly_by_day_keeps_wall_clock_time_across_dst() {
+        berlin();
+        // Clocks go forward on Sunday 2026-03-29
+        let event = repeating(ts(2026, 3, 23, 9), "FREQ=WEEKLY;BYDAY=MO,TH");
+        assert_eq!(
+            occurrences(&event, ts(2026, 4, 3, 0)),
+            [
+                at(2026, 3, 23, 9),
+                at(2026, 3, 26, 9),
+                at(2026, 3, 30, 9),
+                at(2026, 4, 2, 9),
+            ]
+        );
+        assert_eq!(ts(2026, 3, 30, 9) - ts(2026, 3, 26, 9), 4 * 86_400 - 3600);
+    }
+
+    #[test]
+    fn skipping_ahead_finds_the_same_occurrences() {
+        berlin();
+        let rules = [
+            "FREQ=DAILY;INTERVAL=3",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU",
+            "FREQ=MONTHLY;BYDAY=-1FR",
+            "FREQ=MONTHLY;INTERVAL=5;BYMONTHDAY=31",
+            "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=29",
+        ];
+        let (from, to) = (ts(2031, 3, 10, 0), ts(2035, 1, 1, 0));
+        for rule in rules {
+            let event = repeating(ts(2026, 1, 5, 23), rule);
+            let all: Vec<_> = occurrences(&event, to)
+                .into_iter()
+                .filter(|&start| local_timestamp(start).unwrap() >= from)
+                .collect();
+            let mut skipped = Vec::new();
+            for_each_occurrence(&event, from, to, |start| {
+                if start >= from {
+                    skipped.push(local_datetime(start).unwrap());
+                }
+                true
+            });
+            assert!(!all.is_empty(), "{}", rule);
+            assert_eq!(skipped, all, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn reminders_of_late_occurrences_are_due() {
+        berlin();
+        let mut event = repeating(ts(2026, 1, 5, 9), "FREQ=DAILY");
+        event.reminders = vec![10, 60];
+        let due = due_reminders(&[event], ts(2030, 6, 1, 7), ts(2030, 6, 1, 8) + 55 * 60);
+        let starts: Vec<_> = due.iter().map(|(at, _)| *at).collect();
+        assert_eq!(starts, [ts(2030, 6, 1, 8), ts(2030, 6, 1, 9) - 600]);
+    }
+
+    #[test]
+    fn folded_lines_round_trip() {
+        berlin();
+        let mut event = repeating(ts(2026, 1, 5, 9), "FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10");
+        event.title = "Chorprobe im Gemeindehaus, Saal 2; bitte Noten mitbringen".to_string();
+        event.description = "Erste Zeile mit Umlauten äöü und ß, die deutlich länger \
+                             als fünfundsiebzig Bytes ist.\nZweite Zeile: 🎵🎶 Ende"
+            .to_string();
+        event.exceptions = vec![ts(2026, 1, 7, 9)];
+        event.reminders = vec![15, 60];
+
+        let ics = calendar_to_ics(std::slice::from_ref(&event));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= ICS_LINE_LEN, "{:?}", line);
+        }
+
+        let (events, warnings, skipped) = parse_ics(&ics).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!((events.len(), skipped), (1, 0));
+        let parsed = &events[0].event;
+        assert_eq!(events[0].uid.as_deref(), Some(event.id.as_str()));
+        assert_eq!(parsed.title, event.title);
+        assert_eq!(parsed.description, event.description);
+        assert_eq!((parsed.start, parsed.end), (event.start, event.end));
+        assert_eq!(parsed.exceptions, event.exceptions);
+        assert_eq!(parsed.reminders, event.reminders);
+        let (rule, original) = (
+            parsed.recurrence.as_ref().unwrap(),
+            event.recurrence.as_ref().unwrap(),
+        );
+        assert_eq!(rule.frequency, original.frequency);
+        assert_eq!(rule.count, original.count);
+        assert_eq!(rule.by_day, original.by_day);
+    }
+}
@@ -18,6 +18,8 @@ use std::{
 };
 use tauri::{AppHandle, Emitter};
 
+use crate::storage::{self, MediaKind};
+
 // Global camera state
 static CAMERA_RUNNING: AtomicBool = AtomicBool::new(false);
 static STOP_SIGNAL: AtomicBool = AtomicBool::new(false);
@@ -125,9 +127,19 @@ pub async fn capture_photo(app: AppHandle) -> Result<PhotoSaved, String> {
         }
     };
 
-    // Get Pictures directory
-    let pictures_dir = dirs::picture_dir().ok_or("Failed to get Pictures directory")?;
-    let camera_dir = pictures_dir.join("honeybee-camera");
+    // Refuse to save when the disk is critically full or the quota is used up
+    if let Err(e) = storage::check_capture_allowed(&app, MediaKind::Photos) {
+        let result = PhotoSaved {
+            path: String::new(),
+            success: false,
+            error: Some(e),
+        };
+        let _ = app.emit("photo-saved", result.clone());
+        return Ok(result);
+    }
+
+    // Get camera directory (~/Pictures/honeybee-camera)
+    let camera_dir = crate::gallery::camera_dir()?;
 
     // Create directory if needed
     if !camera_dir.exists() {
@@ -159,6 +171,14 @@ pub async fn capture_photo(app: AppHandle) -> Result<PhotoSaved, String> {
     };
 
     let _ = app.emit("photo-saved", result.clone());
+
+    // Hash the new photo into the gallery index for duplicate detection.
+    // Decoding takes a while, the shot is already reported above.
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = crate::gallery::index_image(&filepath) {
+            eprintln!("Failed to index photo: {}", e);
+        }
+    });
     Ok(result)
 }
 
@@ -0,0 +1,418 @@
+//! Audio file decoding
+//!
+//! Streams an audio file as blocks of interleaved f32 samples, whatever its
+//! format: WAV, FLAC, MP3, Vorbis and AAC go through symphonia, Ogg Opus
+//! through libopus (which symphonia can't decode).
+
+use audiopus::{
+    coder::Decoder as OpusDecoder,
+    packet::{self as opus_packet, Packet},
+    Channels, MutSignals, SampleRate,
+};
+use ogg::PacketReader;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::Path,
+};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+    units::Time,
+};
+
+/// Opus always decodes at 48 kHz
+const OPUS_RATE: u32 = 48000;
+
+/// Largest Opus packet duration (120 ms at 48 kHz)
+const MAX_OPUS_FRAME: usize = 5760;
+
+/// Audio decoded and discarded before an Opus seek target so the decoder
+/// converges (80 ms, as recommended by RFC 7845)
+const OPUS_PREROLL: u64 = 3840;
+
+/// Decoder for one audio file
+pub struct AudioDecoder {
+    source: Source,
+    sample_rate: u32,
+    channels: u16,
+    total_frames: Option<u64>,
+}
+
+enum Source {
+    Symphonia(SymphoniaSource),
+    Opus(OpusSource),
+}
+
+struct SymphoniaSource {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    buf: Option<SampleBuffer<f32>>,
+    /// After a seek, audio before this timestamp is dropped
+    skip_until: Option<u64>,
+}
+
+struct OpusSource {
+    packets: PacketReader<BufReader<File>>,
+    /// Packets read ahead while seeking
+    pending: VecDeque<ogg::Packet>,
+    decoder: OpusDecoder,
+    opus_channels: Channels,
+    channels: usize,
+    pre_skip: u64,
+    /// Samples (per channel) still to drop from the start of the stream
+    skip: usize,
+    /// Samples (per channel) decoded so far, including the pre-skip
+    decoded: u64,
+    pcm: Vec<f32>,
+}
+
+impl AudioDecoder {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "opus" => Self::open_opus(path),
+            // Ogg holds either: our recordings are Opus, music is usually Vorbis
+            "ogg" | "oga" if crate::ogg_opus::read_info(path).is_ok() => Self::open_opus(path),
+            _ => Self::open_symphonia(path, &ext),
+        }
+    }
+
+    fn open_symphonia(path: &Path, ext: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension(ext);
+
+        let format_options = FormatOptions {
+            enable_gapless: true,
+            ..Default::default()
+        };
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &format_options, &MetadataOptions::default())
+            .map_err(|e| format!("Unsupported audio file: {}", e))?;
+        let format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or("No audio track found")?;
+        let params = &track.codec_params;
+        let sample_rate = params.sample_rate.ok_or("Unknown sample rate")?;
+        let channels = params
+            .channels
+            .map(|c| c.count() as u16)
+            .ok_or("Unknown channel layout")?;
+        let total_frames = params.n_frames;
+        let track_id = track.id;
+
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .map_err(|e| format!("Unsupported codec: {}", e))?;
+
+        Ok(Self {
+            source: Source::Symphonia(SymphoniaSource {
+                format,
+                decoder,
+                track_id,
+                buf: None,
+                skip_until: None,
+            }),
+            sample_rate,
+            channels,
+            total_frames,
+        })
+    }
+
+    fn open_opus(path: &Path) -> Result<Self, String> {
+        let info = crate::ogg_opus::read_info(path)
+            .map_err(|e| format!("Unsupported audio file: {}", e))?;
+        let (opus_channels, channels) = match info.channels {
+            1 => (Channels::Mono, 1),
+            2 => (Channels::Stereo, 2),
+            n => return Err(format!("Unsupported Opus channel count: {}", n)),
+        };
+
+        let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+        let mut packets = PacketReader::new(BufReader::new(file));
+        // Skip OpusHead and OpusTags, read_info already parsed what we need
+        for _ in 0..2 {
+            packets
+                .read_packet()
+                .map_err(|e| format!("Invalid Ogg stream: {}", e))?
+                .ok_or("Truncated Ogg Opus file")?;
+        }
+
+        let decoder = OpusDecoder::new(SampleRate::Hz48000, opus_channels)
+            .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
+
+        Ok(Self {
+            source: Source::Opus(OpusSource {
+                packets,
+                pending: VecDeque::new(),
+                decoder,
+                opus_channels,
+                channels,
+                pre_skip: info.pre_skip as u64,
+                skip: info.pre_skip as usize,
+                decoded: 0,
+                pcm: vec![0.0; MAX_OPUS_FRAME * channels],
+            }),
+            sample_rate: OPUS_RATE,
+            channels: channels as u16,
+            total_frames: Some(info.duration_ms * OPUS_RATE as u64 / 1000),
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Frames per channel, if the container records it
+    pub fn total_frames(&self) -> Option<u64> {
+        self.total_frames
+    }
+
+    /// Continue decoding from `position_ms`
+    pub fn seek(&mut self, position_ms: u64) -> Result<(), String> {
+        match &mut self.source {
+            Source::Symphonia(source) => source.seek(position_ms),
+            Source::Opus(source) => source.seek(position_ms),
+        }
+    }
+
+    /// Decode the next block into `out` (cleared first).
+    /// Returns false at the end of the stream.
+    pub fn next_block(&mut self, out: &mut Vec<f32>) -> Result<bool, String> {
+        out.clear();
+        match &mut self.source {
+            Source::Symphonia(source) => source.next_block(out),
+            Source::Opus(source) => source.next_block(out),
+        }
+    }
+}
+
+impl SymphoniaSource {
+    fn next_block(&mut self, out: &mut Vec<f32>) -> Result<bool, String> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                    return Ok(false);
+                }
+                // Chained streams aren't supported, treat as the end
+                Err(SymphoniaError::ResetRequired) => return Ok(false),
+                Err(e) => return Err(format!("Failed to read audio: {}", e)),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // A corrupt packet only loses that packet
+                Err(SymphoniaError::DecodeError(e)) => {
+                    eprintln!("Skipping undecodable audio packet: {}", e);
+                    continue;
+                }
+                Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+            };
+            let frames = decoded.frames();
+            if frames == 0 {
+                continue;
+            }
+
+            // Timestamps of the supported formats count frames (time base 1 / sample rate)
+            let mut skip = 0;
+            if let Some(until) = self.skip_until {
+                if packet.ts() + frames as u64 <= until {
+                    continue;
+                }
+                skip = until.saturating_sub(packet.ts()) as usize;
+                self.skip_until = None;
+            }
+
+            let channels = decoded.spec().channels.count();
+            let needed = decoded.capacity() * channels;
+            if self.buf.as_ref().is_none_or(|b| b.capacity() < needed) {
+                self.buf = Some(SampleBuffer::new(decoded.capacity() as u64, *decoded.spec()));
+            }
+            if let Some(buf) = self.buf.as_mut() {
+                buf.copy_interleaved_ref(decoded);
+                out.extend_from_slice(&buf.samples()[skip * channels..]);
+            }
+            return Ok(true);
+        }
+    }
+
+    fn seek(&mut self, position_ms: u64) -> Result<(), String> {
+        let to = SeekTo::Time {
+            time: Time::from(position_ms as f64 / 1000.0),
+            track_id: Some(self.track_id),
+        };
+        let seeked = self
+            .format
+            .seek(SeekMode::Accurate, to)
+            .map_err(|e| format!("Failed to seek: {}", e))?;
+        self.decoder.reset();
+        self.skip_until = Some(seeked.required_ts);
+        Ok(())
+    }
+}
+
+impl OpusSource {
+    fn read_packet(&mut self) -> Result<Option<ogg::Packet>, String> {
+        if let Some(packet) = self.pending.pop_front() {
+            return Ok(Some(packet));
+        }
+        self.packets
+            .read_packet()
+            .map_err(|e| format!("Failed to read audio: {}", e))
+    }
+
+    fn next_block(&mut self, out: &mut Vec<f32>) -> Result<bool, String> {
+        loop {
+            let Some(packet) = self.read_packet()? else {
+                return Ok(false);
+            };
+
+            let input = Packet::try_from(packet.data.as_slice())
+                .map_err(|e| format!("Invalid Opus packet: {}", e))?;
+            let output = MutSignals::try_from(&mut self.pcm)
+                .map_err(|e| format!("Failed to decode audio: {}", e))?;
+            let mut samples = self
+                .decoder
+                .decode_float(Some(input), output, false)
+                .map_err(|e| format!("Failed to decode audio: {}", e))?;
+
+            // The last page's granule position marks where the real audio ends
+            if packet.last_in_stream() {
+                let end = packet.absgp_page();
+                samples = samples.min(end.saturating_sub(self.decoded) as usize);
+            }
+            self.decoded += samples as u64;
+
+            let skip = self.skip.min(samples);
+            self.skip -= skip;
+            if skip < samples {
+                out.extend_from_slice(&self.pcm[skip * self.channels..samples * self.channels]);
+                return Ok(true);
+            }
+        }
+    }
+
+    fn seek(&mut self, position_ms: u64) -> Result<(), String> {
+        let target = self.pre_skip + position_ms * OPUS_RATE as u64 / 1000;
+
+        // Lands on the first page ending at or after the goal
+        let found = self
+            .packets
+            .seek_absgp(None, target.saturating_sub(OPUS_PREROLL))
+            .map_err(|e| format!("Failed to seek: {}", e))?;
+        self.pending.clear();
+        if !found {
+            // Past the end
+            self.skip = 0;
+            return Ok(());
+        }
+
+        // Pages only carry their end position, work out where this one starts.
+        // (The last page's end is trimmed, so seeks into it land up to a frame late.)
+        let mut page_samples = 0u64;
+        let mut page_end = 0u64;
+        while let Some(packet) = self
+            .packets
+            .read_packet()
+            .map_err(|e| format!("Failed to seek: {}", e))?
+        {
+            if let Ok(n) = Packet::try_from(packet.data.as_slice())
+                .and_then(|p| opus_packet::nb_samples(p, SampleRate::Hz48000))
+            {
+                page_samples += n as u64;
+            }
+            page_end = packet.absgp_page();
+            let last = packet.last_in_page();
+            self.pending.push_back(packet);
+            if last {
+                break;
+            }
+        }
+
+        let page_start = page_end.saturating_sub(page_samples);
+        self.decoder = OpusDecoder::new(SampleRate::Hz48000, self.opus_channels)
+            .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
+        self.decoded = page_start;
+        self.skip = target.saturating_sub(page_start) as usize;
+        Ok(())
+    }
+}
+
+/// Streaming linear resampler over interleaved f32 frames
+pub struct Resampler {
+    channels: usize,
+    /// Input frames per output frame
+    step: f64,
+    /// Position of the next output frame within `pending`
+    pos: f64,
+    pending: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        Self {
+            channels,
+            step: input_rate as f64 / output_rate as f64,
+            pos: 0.0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Add input frames and append every output frame that can be produced
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.pending.extend_from_slice(input);
+        let frames = self.pending.len() / self.channels;
+
+        // Interpolation needs the frame after `pos`, the last one waits for more input
+        while self.pos + 1.0 < frames as f64 {
+            let i = self.pos as usize;
+            let frac = (self.pos - i as f64) as f32;
+            for c in 0..self.channels {
+                let a = self.pending[i * self.channels + c];
+                let b = self.pending[(i + 1) * self.channels + c];
+                out.push(a + (b - a) * frac);
+            }
+            self.pos += self.step;
+        }
+
+        let consumed = (self.pos as usize).min(frames);
+        self.pending.drain(..consumed * self.channels);
+        self.pos -= consumed as f64;
+    }
+
+    /// Emit what's left, holding the last frame
+    pub fn flush(&mut self, out: &mut Vec<f32>) {
+        let frames = self.pending.len() / self.channels;
+        while self.pos < frames as f64 {
+            let i = self.pos as usize;
+            out.extend_from_slice(&self.pending[i * self.channels..(i + 1) * self.channels]);
+            self.pos += self.step;
+        }
+        self.pending.clear();
+        self.pos = 0.0;
+    }
+}
@@ -0,0 +1,385 @@
+//! Audio Ducking
+//!
+//! Turns media down while the voice assistant is in a session so the user and
+//! the assistant can hear each other. The voice agent IPC listener reports
+//! sessions with `set_session_active`, and a controller thread fades the
+//! playback engine's gain down (or pauses it), optionally lowering the sink
+//! volume too, then undoes exactly what it changed when the session ends.
+//!
+//! Session reports are treated as a level, not as edges: repeated starts don't
+//! duck twice and any end-like event (end, ready, error, lost connection)
+//! restores. A session that goes quiet for `SESSION_TIMEOUT` is assumed over.
+//! What was changed is saved to ~/.cache/honeybee/ducking.json before touching
+//! anything, so a crash while ducked is undone on the next start.
+//!
+//! Ringing alarms also change the sink volume, through `raise_for_alarm` /
+//! `restore_after_alarm` here, so the two don't overwrite each other: while an
+//! alarm rings ducking leaves the sink alone, and whichever finishes first
+//! hands the other the level it should return to.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::fsutil::write_atomic;
+use crate::player::{self, PlaybackStatus};
+use crate::system;
+
+/// Sessions without any voice agent event for this long are assumed over
+const SESSION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Interval between gain updates while fading
+const FADE_STEP: Duration = Duration::from_millis(20);
+
+/// The sink volume is set through a subprocess, so it fades in fewer steps
+const SINK_FADE_STEPS: u32 = 5;
+
+/// Time for the engine to act on pause / resume before fading
+const PLAYER_SETTLE: Duration = Duration::from_millis(100);
+
+const MAX_FADE_MS: u64 = 5000;
+
+lazy_static::lazy_static! {
+    static ref DUCKING_TX: Mutex<Option<mpsc::Sender<bool>>> = Mutex::new(None);
+    /// Who has changed the sink volume. Held while setting it.
+    static ref SINK: Mutex<SinkOwners> = Mutex::new(SinkOwners::default());
+    // Serializes config file updates
+    static ref CONFIG_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// How media is ducked
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuckMode {
+    /// Turn playback down to `level`
+    #[default]
+    Lower,
+    /// Pause playback and resume it afterwards
+    Pause,
+}
+
+/// Ducking configuration (~/.config/honeybee/ducking.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuckingConfig {
+    pub enabled: bool,
+    pub mode: DuckMode,
+    /// Volume while ducked, as a percentage of the normal volume
+    pub level: u8,
+    /// Fade duration in and out
+    pub fade_ms: u64,
+    /// Also lower the system output volume. This turns the assistant down
+    /// too, but catches audio that doesn't go through the player (videos).
+    pub duck_sink: bool,
+}
+
+impl Default for DuckingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: DuckMode::Lower,
+            level: 20,
+            fade_ms: 400,
+            duck_sink: false,
+        }
+    }
+}
+
+/// What was changed while ducking, saved until restored
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Ducked {
+    /// Playback gain before ducking
+    gain: f32,
+    /// Playback was playing and got paused
+    paused_playback: bool,
+    /// Sink volume before ducking and the level it was set to
+    sink_volume: Option<(u8, u8)>,
+}
+
+/// Changes to the sink volume that are to be undone
+#[derive(Debug, Default)]
+struct SinkOwners {
+    /// Ducking: volume before ducking and the ducked level
+    ducked: Option<(u8, u8)>,
+    /// A ringing alarm: volume to return to and the alarm level
+    alarm: Option<(u8, u8)>,
+}
+
+fn config_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/ducking.json"))
+        .unwrap_or_else(|| PathBuf::from("ducking.json"))
+}
+
+fn state_path() -> PathBuf {
+    dirs::cache_dir()
+        .map(|cache| cache.join("honeybee/ducking.json"))
+        .unwrap_or_else(|| PathBuf::from("ducking-state.json"))
+}
+
+fn load_config() -> DuckingConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn load_ducked() -> Option<Ducked> {
+    fs::read_to_string(state_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+fn save_ducked(ducked: &Ducked) -> Result<(), String> {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    }
+    let json = serde_json::to_string(ducked).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to save ducking state: {}", e))
+}
+
+fn clear_ducked() {
+    let _ = fs::remove_file(state_path());
+}
+
+/// Fade the playback gain and, if given, the sink volume together
+fn fade(from_gain: f32, to_gain: f32, sink: Option<(u8, u8)>, duration: Duration) {
+    let steps = (duration.as_millis() / FADE_STEP.as_millis()).max(1) as u32;
+    let sink_every = steps.div_ceil(SINK_FADE_STEPS).max(1);
+
+    for step in 1..=steps {
+        let t = step as f32 / steps as f32;
+        player::set_gain(from_gain + (to_gain - from_gain) * t);
+        if let Some((from, to)) = sink {
+            if step % sink_every == 0 || step == steps {
+                let level = from as f32 + (to as f32 - from as f32) * t;
+                // An alarm that started mid-fade has the sink now
+                let owners = SINK.lock();
+                if owners.alarm.is_none() {
+                    if let Err(e) = system::set_volume(level.round() as u8) {
+                        eprintln!("Ducking: failed to set volume: {}", e);
+                    }
+                }
+            }
+        }
+        if step < steps {
+            thread::sleep(FADE_STEP);
+        }
+    }
+}
+
+fn duck(config: &DuckingConfig) -> Ducked {
+    let level = config.level.min(100);
+    let playing = player::get_player_state().status == PlaybackStatus::Playing;
+    let mut ducked = Ducked {
+        gain: player::gain(),
+        paused_playback: config.mode == DuckMode::Pause && playing,
+        sink_volume: None,
+    };
+    if config.duck_sink {
+        let mut owners = SINK.lock();
+        // A ringing alarm must stay loud
+        if owners.alarm.is_none() {
+            match system::get_volume() {
+                Ok(volume) => {
+                    let target = (volume as u32 * level as u32 / 100) as u8;
+                    ducked.sink_volume = Some((volume, target));
+                    owners.ducked = ducked.sink_volume;
+                }
+                Err(e) => eprintln!("Ducking: failed to read volume: {}", e),
+            }
+        }
+    }
+
+    // Saved first, so a crash mid-fade is still restored
+    if let Err(e) = save_ducked(&ducked) {
+        eprintln!("Ducking: {}", e);
+    }
+
+    let fade_time = Duration::from_millis(config.fade_ms.min(MAX_FADE_MS));
+    let target_gain = match config.mode {
+        DuckMode::Lower => ducked.gain * level as f32 / 100.0,
+        DuckMode::Pause => 0.0,
+    };
+    fade(ducked.gain, target_gain, ducked.sink_volume, fade_time);
+
+    if config.mode == DuckMode::Pause {
+        if ducked.paused_playback {
+            if let Err(e) = player::player_pause() {
+                eprintln!("Ducking: failed to pause playback: {}", e);
+            }
+            thread::sleep(PLAYER_SETTLE);
+        }
+        // Anything started during the session plays normally
+        player::set_gain(ducked.gain);
+    }
+
+    println!("🔉 Ducked media for voice session");
+    ducked
+}
+
+/// Undo `duck`, leaving alone whatever the user changed in the meantime
+fn restore(ducked: &Ducked, fade_time: Duration) {
+    let mut from_gain = player::gain();
+    if ducked.paused_playback && player::get_player_state().status == PlaybackStatus::Paused {
+        from_gain = 0.0;
+        player::set_gain(0.0);
+        if let Err(e) = player::player_resume() {
+            eprintln!("Ducking: failed to resume playback: {}", e);
+        }
+        thread::sleep(PLAYER_SETTLE);
+    }
+
+    let sink = ducked.sink_volume.and_then(|(original, ducked_to)| {
+        let mut owners = SINK.lock();
+        owners.ducked = None;
+        // The alarm returns to the undone level when it stops
+        if let Some((_, alarm_level)) = owners.alarm {
+            owners.alarm = Some((original, alarm_level));
+            return None;
+        }
+        match system::get_volume() {
+            Ok(current) if current == ducked_to => Some((current, original)),
+            // Changed by the user while ducked, their setting wins
+            Ok(_) => None,
+            Err(e) => {
+                eprintln!("Ducking: failed to read volume: {}", e);
+                Some((ducked_to, original))
+            }
+        }
+    });
+
+    fade(from_gain, ducked.gain, sink, fade_time);
+    clear_ducked();
+    println!("🔊 Restored media after voice session");
+}
+
+/// Called by the voice agent IPC listener on session events
+pub fn set_session_active(active: bool) {
+    if let Some(tx) = DUCKING_TX.lock().as_ref() {
+        let _ = tx.send(active);
+    }
+}
+
+/// Undo a duck left over from a previous run and follow voice sessions
+pub fn start_ducking_controller() {
+    let (tx, rx) = mpsc::channel();
+    *DUCKING_TX.lock() = Some(tx);
+
+    thread::spawn(move || {
+        let mut ducked = None;
+        let mut last_activity = Instant::now();
+        let mut session_active = false;
+
+        if let Some(leftover) = load_ducked() {
+            // The player starts at full gain and stopped, only the sink needs fixing
+            let leftover = Ducked {
+                gain: 1.0,
+                paused_playback: false,
+                ..leftover
+            };
+            restore(&leftover, Duration::ZERO);
+        }
+
+        loop {
+            match rx.recv_timeout(Duration::from_secs(1)) {
+                Ok(active) => {
+                    session_active = active;
+                    last_activity = Instant::now();
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            // Catch up on events queued during a fade
+            while let Ok(active) = rx.try_recv() {
+                session_active = active;
+                last_activity = Instant::now();
+            }
+            if session_active && last_activity.elapsed() >= SESSION_TIMEOUT {
+                println!("🔊 Voice session timed out");
+                session_active = false;
+            }
+
+            match (&ducked, session_active) {
+                (None, true) => {
+                    let config = load_config();
+                    if config.enabled {
+                        ducked = Some(duck(&config));
+                    }
+                }
+                (Some(d), false) => {
+                    let fade_ms = load_config().fade_ms.min(MAX_FADE_MS);
+                    restore(d, Duration::from_millis(fade_ms));
+                    ducked = None;
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+// ============================================================================
+// ALARM VOLUME
+// ============================================================================
+
+/// Set the sink to `level` for a ringing alarm, until `restore_after_alarm`
+pub fn raise_for_alarm(level: u8) -> Result<(), String> {
+    let mut owners = SINK.lock();
+    if owners.alarm.is_some() {
+        return Ok(());
+    }
+    let current = system::get_volume()?;
+    system::set_volume(level)?;
+    owners.alarm = Some((current, level));
+    Ok(())
+}
+
+/// Undo `raise_for_alarm`: back to the ducked level if a session is still
+/// ducked, else to the volume before the alarm. Left alone if the user changed
+/// the volume while it rang.
+pub fn restore_after_alarm() {
+    let mut owners = SINK.lock();
+    let Some((original, alarm_level)) = owners.alarm.take() else {
+        return;
+    };
+    let target = owners.ducked.map_or(original, |(_, ducked_to)| ducked_to);
+    if system::get_volume().is_ok_and(|current| current == alarm_level) {
+        if let Err(e) = system::set_volume(target) {
+            eprintln!("Failed to restore volume after alarm: {}", e);
+        }
+    }
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_ducking_config() -> DuckingConfig {
+    load_config()
+}
+
+#[tauri::command]
+pub fn set_ducking_config(config: DuckingConfig) -> Result<(), String> {
+    if config.level > 100 {
+        return Err("Duck level must be between 0 and 100".to_string());
+    }
+    if config.fade_ms > MAX_FADE_MS {
+        return Err(format!("Fade time must be at most {} ms", MAX_FADE_MS));
+    }
+
+    let _guard = CONFIG_LOCK.lock();
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to save ducking config: {}", e))
+}
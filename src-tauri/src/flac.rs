@@ -0,0 +1,532 @@
+//! Minimal FLAC encoder for 16-bit recordings
+//!
+//! Fixed-blocksize stream, independent channels, per-subframe choice of
+//! CONSTANT / FIXED (orders 0-4) / VERBATIM and Rice-coded residuals with a
+//! searched partition order. Not as tight as libFLAC at high levels, but
+//! lossless and typically ~50-60% of the WAV size for speech.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::wav::WavReader;
+
+/// Frames per FLAC block (4096 is the libFLAC default for 44.1/48 kHz)
+const BLOCK_SIZE: usize = 4096;
+
+const BITS_PER_SAMPLE: u32 = 16;
+
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_PARTITION_ORDER: u32 = 8;
+/// 4-bit Rice parameters, 15 is reserved as the escape code
+const MAX_RICE_PARAM: u32 = 14;
+
+/// Offset of STREAMINFO's min frame size field ("fLaC" + block header + 4)
+const STREAMINFO_FRAME_SIZE_OFFSET: u64 = 4 + 4 + 4;
+
+/// MSB-first bit writer
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::with_capacity(BLOCK_SIZE * 4),
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    fn write(&mut self, value: u64, bits: u32) {
+        debug_assert!(bits <= 32);
+        if bits == 0 {
+            return;
+        }
+        self.acc = (self.acc << bits) | (value & ((1u64 << bits) - 1));
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+    }
+
+    fn write_signed(&mut self, value: i32, bits: u32) {
+        self.write(value as u32 as u64, bits);
+    }
+
+    /// `q` zeros followed by a one
+    fn write_unary(&mut self, mut q: u32) {
+        while q >= 32 {
+            self.write(0, 32);
+            q -= 32;
+        }
+        self.write(1, q + 1);
+    }
+
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// UTF-8 style variable length coding used for the frame number
+fn write_utf8_number(w: &mut BitWriter, n: u64) {
+    if n < 0x80 {
+        w.write(n, 8);
+        return;
+    }
+    let mut extra = 1;
+    while n >= 1u64 << (6 * extra + (6 - extra)) {
+        extra += 1;
+    }
+    let lead_bits = 7 - (extra + 1);
+    let lead_marker = (0xFFu64 << (8 - (extra + 1))) & 0xFF;
+    w.write(lead_marker | (n >> (6 * extra)), 8);
+    debug_assert!(n >> (6 * extra) < 1u64 << lead_bits);
+    for i in (0..extra).rev() {
+        w.write(0x80 | ((n >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn zigzag(r: i32) -> u32 {
+    ((r << 1) ^ (r >> 31)) as u32
+}
+
+/// Residual of the fixed polynomial predictor of `order`
+fn fixed_residual(samples: &[i32], order: usize, out: &mut Vec<i32>) {
+    out.clear();
+    for i in order..samples.len() {
+        let s = samples;
+        let r = match order {
+            0 => s[i],
+            1 => s[i] - s[i - 1],
+            2 => s[i] - 2 * s[i - 1] + s[i - 2],
+            3 => s[i] - 3 * s[i - 1] + 3 * s[i - 2] - s[i - 3],
+            _ => s[i] - 4 * s[i - 1] + 6 * s[i - 2] - 4 * s[i - 3] + s[i - 4],
+        };
+        out.push(r);
+    }
+}
+
+/// Best Rice parameter and its cost in bits for a partition
+fn rice_cost(residual: &[i32]) -> (u32, u64) {
+    let n = residual.len() as u64;
+    if n == 0 {
+        return (0, 0);
+    }
+    let sum: u64 = residual.iter().map(|&r| zigzag(r) as u64).sum();
+    let mean = sum / n;
+    let guess = if mean == 0 { 0 } else { 63 - mean.leading_zeros() };
+
+    let cost = |k: u32| n * (k as u64 + 1) + residual.iter().map(|&r| (zigzag(r) >> k) as u64).sum::<u64>();
+
+    let mut best = (guess.min(MAX_RICE_PARAM), cost(guess.min(MAX_RICE_PARAM)));
+    for k in [guess.saturating_sub(1), guess + 1] {
+        let k = k.min(MAX_RICE_PARAM);
+        let c = cost(k);
+        if c < best.1 {
+            best = (k, c);
+        }
+    }
+    best
+}
+
+/// Choose the partition order and Rice parameters for a residual.
+/// Returns (partition order, params, total bits including parameter fields).
+fn plan_residual(residual: &[i32], block_size: usize, order: usize) -> (u32, Vec<u32>, u64) {
+    let mut best: Option<(u32, Vec<u32>, u64)> = None;
+
+    for porder in 0..=MAX_PARTITION_ORDER {
+        let partitions = 1usize << porder;
+        if !block_size.is_multiple_of(partitions) || block_size / partitions <= order {
+            break;
+        }
+        let part_len = block_size / partitions;
+
+        let mut params = Vec::with_capacity(partitions);
+        let mut bits = 2 + 4; // coding method + partition order
+        let mut start = 0;
+        for p in 0..partitions {
+            let len = if p == 0 { part_len - order } else { part_len };
+            let (k, cost) = rice_cost(&residual[start..start + len]);
+            params.push(k);
+            bits += 4 + cost;
+            start += len;
+        }
+
+        if best.as_ref().map(|b| bits < b.2).unwrap_or(true) {
+            best = Some((porder, params, bits));
+        }
+    }
+
+    best.expect("partition order 0 is always valid")
+}
+
+fn encode_subframe(w: &mut BitWriter, samples: &[i32], residual: &mut Vec<i32>) {
+    let block_size = samples.len();
+
+    // CONSTANT (digital silence is common in recordings)
+    if samples.iter().all(|&s| s == samples[0]) {
+        w.write(0, 1);
+        w.write(0b000000, 6);
+        w.write(0, 1);
+        w.write_signed(samples[0], BITS_PER_SAMPLE);
+        return;
+    }
+
+    // Pick the fixed order with the smallest residual magnitude
+    let mut best_order = 0;
+    let mut best_sum = u64::MAX;
+    for order in 0..=MAX_FIXED_ORDER.min(block_size - 1) {
+        fixed_residual(samples, order, residual);
+        let sum: u64 = residual.iter().map(|&r| r.unsigned_abs() as u64).sum();
+        if sum < best_sum {
+            best_sum = sum;
+            best_order = order;
+        }
+    }
+
+    fixed_residual(samples, best_order, residual);
+    let (porder, params, residual_bits) = plan_residual(residual, block_size, best_order);
+    let fixed_bits = best_order as u64 * BITS_PER_SAMPLE as u64 + residual_bits;
+    let verbatim_bits = block_size as u64 * BITS_PER_SAMPLE as u64;
+
+    if fixed_bits >= verbatim_bits {
+        w.write(0, 1);
+        w.write(0b000001, 6);
+        w.write(0, 1);
+        for &s in samples {
+            w.write_signed(s, BITS_PER_SAMPLE);
+        }
+        return;
+    }
+
+    w.write(0, 1);
+    w.write(0b001000 | best_order as u64, 6);
+    w.write(0, 1);
+    for &s in &samples[..best_order] {
+        w.write_signed(s, BITS_PER_SAMPLE);
+    }
+
+    w.write(0b00, 2); // Rice, 4-bit parameters
+    w.write(porder as u64, 4);
+    let part_len = block_size >> porder;
+    let mut start = 0;
+    for (p, &k) in params.iter().enumerate() {
+        let len = if p == 0 { part_len - best_order } else { part_len };
+        w.write(k as u64, 4);
+        for &r in &residual[start..start + len] {
+            let u = zigzag(r);
+            w.write_unary(u >> k);
+            w.write(u as u64, k);
+        }
+        start += len;
+    }
+}
+
+/// Encode one frame (`channels` planar blocks of equal length) into bytes
+fn encode_frame(frame_number: u64, planes: &[Vec<i32>], residual: &mut Vec<i32>) -> Vec<u8> {
+    let block_size = planes[0].len();
+    let mut w = BitWriter::new();
+
+    // Frame header
+    w.write(0b11111111111110, 14); // sync
+    w.write(0, 1); // reserved
+    w.write(0, 1); // fixed blocksize stream
+    w.write(0b0111, 4); // 16-bit blocksize-1 at end of header
+    w.write(0b0000, 4); // sample rate from STREAMINFO
+    w.write(planes.len() as u64 - 1, 4); // independent channels
+    w.write(0b100, 3); // 16 bits per sample
+    w.write(0, 1); // reserved
+    write_utf8_number(&mut w, frame_number);
+    w.write(block_size as u64 - 1, 16);
+    let crc = crc8(&w.bytes);
+    w.write(crc as u64, 8);
+
+    for plane in planes {
+        encode_subframe(&mut w, plane, residual);
+    }
+
+    w.align();
+    let crc = crc16(&w.bytes);
+    w.write(crc as u64, 16);
+    w.bytes
+}
+
+/// Encode a 16-bit WAV file to FLAC
+pub fn encode_wav(wav_path: &Path, flac_path: &Path) -> io::Result<()> {
+    let mut reader = WavReader::open(wav_path)?;
+    let info = reader.info().clone();
+    let channels = info.channels as usize;
+    if channels == 0 || channels > 8 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "FLAC supports 1-8 channels"));
+    }
+    if info.sample_rate == 0 || info.sample_rate >= 1 << 20 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Unsupported sample rate for FLAC"));
+    }
+
+    let mut out = BufWriter::new(File::create(flac_path)?);
+
+    // "fLaC" + STREAMINFO (frame sizes / total samples patched at the end)
+    out.write_all(b"fLaC")?;
+    let header = (1u32 << 31) | 34; // last metadata block, 34 bytes
+    out.write_all(&header.to_be_bytes())?;
+    let mut streaminfo = BitWriter::new();
+    streaminfo.write(BLOCK_SIZE as u64, 16); // min block size
+    streaminfo.write(BLOCK_SIZE as u64, 16); // max block size
+    streaminfo.write(0, 24); // min frame size (patched)
+    streaminfo.write(0, 24); // max frame size (patched)
+    streaminfo.write(info.sample_rate as u64, 20);
+    streaminfo.write(channels as u64 - 1, 3);
+    streaminfo.write(BITS_PER_SAMPLE as u64 - 1, 5);
+    streaminfo.write(0, 4); // total samples, upper bits (patched)
+    streaminfo.write(0, 32); // total samples, lower bits (patched)
+    out.write_all(&streaminfo.bytes)?;
+    out.write_all(&[0u8; 16])?; // MD5 unknown
+
+    let mut interleaved = Vec::with_capacity(BLOCK_SIZE * channels);
+    let mut planes: Vec<Vec<i32>> = vec![Vec::with_capacity(BLOCK_SIZE); channels];
+    let mut residual = Vec::with_capacity(BLOCK_SIZE);
+    let mut frame_number = 0u64;
+    let mut total_samples = 0u64;
+    let mut min_frame = u32::MAX;
+    let mut max_frame = 0u32;
+
+    loop {
+        let frames = reader.read_frames(&mut interleaved, BLOCK_SIZE)?;
+        if frames == 0 {
+            break;
+        }
+        for (c, plane) in planes.iter_mut().enumerate() {
+            plane.clear();
+            plane.extend(interleaved.iter().skip(c).step_by(channels).map(|&s| s as i32));
+        }
+
+        let frame = encode_frame(frame_number, &planes, &mut residual);
+        out.write_all(&frame)?;
+
+        // The last (short) frame doesn't count towards the minimum
+        if frames == BLOCK_SIZE {
+            min_frame = min_frame.min(frame.len() as u32);
+        }
+        max_frame = max_frame.max(frame.len() as u32);
+        frame_number += 1;
+        total_samples += frames as u64;
+    }
+    if min_frame == u32::MAX {
+        min_frame = max_frame;
+    }
+
+    // Patch frame sizes and total samples in STREAMINFO
+    let mut patch = BitWriter::new();
+    patch.write(min_frame as u64, 24);
+    patch.write(max_frame as u64, 24);
+    patch.write(info.sample_rate as u64, 20);
+    patch.write(channels as u64 - 1, 3);
+    patch.write(BITS_PER_SAMPLE as u64 - 1, 5);
+    patch.write(total_samples >> 32, 4);
+    patch.write(total_samples & 0xFFFF_FFFF, 32);
+
+    let mut file = out.into_inner().map_err(|e| e.into_error())?;
+    file.seek(SeekFrom::Start(STREAMINFO_FRAME_SIZE_OFFSET))?;
+    file.write_all(&patch.bytes)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Stream parameters from a FLAC file's STREAMINFO block
+#[derive(Debug, Clone)]
+pub struct StreamInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    /// Frames per channel, 0 if unknown
+    pub total_samples: u64,
+}
+
+impl StreamInfo {
+    pub fn duration_ms(&self) -> u64 {
+        self.total_samples * 1000 / self.sample_rate.max(1) as u64
+    }
+}
+
+/// Read STREAMINFO (always the first metadata block)
+pub fn read_streaminfo(path: &Path) -> io::Result<StreamInfo> {
+    let mut header = [0u8; 4 + 4 + 34];
+    File::open(path)?.read_exact(&mut header)?;
+    if &header[0..4] != b"fLaC" || header[4] & 0x7F != 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a FLAC file"));
+    }
+
+    // sample rate (20) | channels - 1 (3) | bits per sample - 1 (5) | total samples (36)
+    let mut packed = [0u8; 8];
+    packed.copy_from_slice(&header[18..26]);
+    let packed = u64::from_be_bytes(packed);
+    Ok(StreamInfo {
+        sample_rate: (packed >> 44) as u32,
+        channels: ((packed >> 41) & 0x7) as u16 + 1,
+        bits_per_sample: ((packed >> 36) & 0x1F) as u16 + 1,
+        total_samples: packed & ((1 << 36) - 1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::AudioDecoder;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("honeybee-flac-{}-{}", std::process::id(), name))
+    }
+
+    /// A plain 16-bit WAV, written by hand so full scale -32768 is possible
+    fn write_wav(path: &Path, sample_rate: u32, channels: u16, samples: &[i16]) {
+        let data_len = samples.len() as u32 * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    fn decode(path: &Path) -> Vec<i16> {
+        let mut decoder = AudioDecoder::open(path).unwrap();
+        let mut samples = Vec::new();
+        let mut block = Vec::new();
+        while decoder.next_block(&mut block).unwrap() {
+            // Symphonia scales i16 by 1/32768, exactly reversible
+            samples.extend(block.iter().map(|&s| (s * 32768.0) as i16));
+        }
+        samples
+    }
+
+    /// Encode, decode and compare sample for sample
+    fn round_trip(name: &str, sample_rate: u32, channels: u16, samples: &[i16]) {
+        let wav = temp_path(&format!("{}.wav", name));
+        let flac = temp_path(&format!("{}.flac", name));
+        write_wav(&wav, sample_rate, channels, samples);
+
+        encode_wav(&wav, &flac).unwrap();
+        let info = read_streaminfo(&flac).unwrap();
+        assert_eq!(info.sample_rate, sample_rate);
+        assert_eq!(info.channels, channels);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.total_samples, samples.len() as u64 / channels as u64);
+
+        let decoded = decode(&flac);
+        assert_eq!(decoded.len(), samples.len(), "{}", name);
+        if let Some(i) = (0..samples.len()).find(|&i| decoded[i] != samples[i]) {
+            panic!(
+                "{}: sample {} is {}, expected {}",
+                name, i, decoded[i], samples[i]
+            );
+        }
+
+        let _ = std::fs::remove_file(wav);
+        let _ = std::fs::remove_file(flac);
+    }
+
+    /// Deterministic white noise over the full range
+    fn noise(len: usize, seed: u32) -> Vec<i16> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 16) as i16
+            })
+            .collect()
+    }
+
+    fn sine(frames: usize, channels: u16, amplitude: f64) -> Vec<i16> {
+        (0..frames)
+            .flat_map(|i| {
+                (0..channels).map(move |c| {
+                    let phase = i as f64 * (440.0 + 110.0 * c as f64) / 48_000.0;
+                    (amplitude * (phase * std::f64::consts::TAU).sin()).round() as i16
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mono_round_trips() {
+        // A short last block, odd in length
+        let frames = BLOCK_SIZE * 2 + 1001;
+        round_trip("mono-silence", 16_000, 1, &vec![0; frames]);
+        round_trip("mono-noise", 16_000, 1, &noise(frames, 1));
+        round_trip("mono-sine", 48_000, 1, &sine(frames, 1, 12_000.0));
+    }
+
+    #[test]
+    fn stereo_round_trips() {
+        let frames = BLOCK_SIZE + 333;
+        round_trip("stereo-silence", 48_000, 2, &vec![0; frames * 2]);
+        round_trip("stereo-noise", 48_000, 2, &noise(frames * 2, 7));
+        round_trip("stereo-sine", 44_100, 2, &sine(frames, 2, 32_767.0));
+
+        // Full-scale square wave, the largest residuals fixed prediction sees
+        let square: Vec<i16> = (0..frames * 2)
+            .map(|i| {
+                if (i / 14) % 2 == 0 {
+                    i16::MAX
+                } else {
+                    i16::MIN
+                }
+            })
+            .collect();
+        round_trip("stereo-square", 48_000, 2, &square);
+    }
+
+    #[test]
+    fn tiny_and_exact_block_sizes_round_trip() {
+        round_trip("one-frame", 48_000, 1, &[1234]);
+        round_trip(
+            "three-frames",
+            48_000,
+            2,
+            &[1, -1, 300, -300, i16::MAX, i16::MIN],
+        );
+        round_trip("exact-block", 48_000, 1, &sine(BLOCK_SIZE, 1, 8_000.0));
+    }
+}
@@ -0,0 +1,54 @@
+//! File helpers shared by the modules that persist state
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Replace `path` with `bytes` so that a crash or power loss leaves either the
+/// old or the new content, never a truncated file: the data goes to a temp
+/// file next to it, is synced, and then renamed over the original.
+pub fn write_atomic(path: &Path, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+    let tmp = temp_path(path);
+    let result = File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(bytes.as_ref())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// "name.ext.tmp" in the same directory, renames don't cross filesystems
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_file_and_leaves_no_temp_file() {
+        let dir = std::env::temp_dir().join(format!("honeybee-fsutil-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+
+        write_atomic(&path, "old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A failed write keeps the old content
+        assert!(write_atomic(&dir.join("missing/state.json"), "x").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
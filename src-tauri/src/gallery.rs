@@ -1,8 +1,28 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
-use std::fs;
+use image::imageops::FilterType;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::fsutil::write_atomic;
 
 const CAMERA_DIR: &str = "honeybee-camera";
 
+/// Metadata index stored alongside the photos (hidden, not listed as an image)
+const INDEX_FILE: &str = ".gallery-index.json";
+
+/// Default max Hamming distance (out of 64 bits) for two images to count as duplicates
+const DEFAULT_DUPLICATE_THRESHOLD: u32 = 10;
+
+lazy_static::lazy_static! {
+    // Serializes read-modify-write cycles on the index file
+    static ref INDEX_LOCK: Mutex<()> = Mutex::new(());
+}
+
 #[derive(Clone, serde::Serialize)]
 pub struct GalleryImage {
     pub filename: String,
@@ -11,11 +31,146 @@ pub struct GalleryImage {
     pub modified: u64, // unix timestamp
 }
 
+/// Per-image entry in the gallery metadata index
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ImageMeta {
+    pub size: u64,
+    pub modified: u64,
+    pub ahash: u64, // average hash (8x8)
+    pub dhash: u64, // difference hash (9x8)
+}
+
+/// Gallery metadata index, keyed by filename
+#[derive(Default, Serialize, Deserialize)]
+struct GalleryIndex {
+    images: HashMap<String, ImageMeta>,
+}
+
+/// An image in a duplicate group with its similarity to the group's first image
+#[derive(Clone, serde::Serialize)]
+pub struct DuplicateImage {
+    pub image: GalleryImage,
+    pub similarity: f32, // 0.0 - 1.0, 1.0 = identical hashes
+}
+
+/// A group of near-identical images, newest first
+#[derive(Clone, serde::Serialize)]
+pub struct DuplicateGroup {
+    pub images: Vec<DuplicateImage>,
+    /// Lowest similarity between any two images of the group
+    pub min_similarity: f32,
+}
+
+/// ~/Pictures/honeybee-camera
+pub(crate) fn camera_dir() -> Result<PathBuf, String> {
+    let pictures_dir = dirs::picture_dir().ok_or("Failed to get Pictures directory")?;
+    Ok(pictures_dir.join(CAMERA_DIR))
+}
+
+fn load_index(dir: &Path) -> GalleryIndex {
+    fs::read_to_string(dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(dir: &Path, index: &GalleryIndex) -> Result<(), String> {
+    let json = serde_json::to_string(index).map_err(|e| e.to_string())?;
+    write_atomic(&dir.join(INDEX_FILE), json)
+        .map_err(|e| format!("Failed to write gallery index: {}", e))
+}
+
+/// Compute average hash and difference hash of an image file
+fn compute_hashes(path: &Path) -> Result<(u64, u64), String> {
+    let img = image::open(path)
+        .map_err(|e| format!("Failed to decode image: {}", e))?
+        .grayscale();
+
+    // aHash: 8x8, each bit = pixel brighter than the mean
+    let small = img.resize_exact(8, 8, FilterType::Triangle).to_luma8();
+    let mean = small.pixels().map(|p| p[0] as u32).sum::<u32>() / 64;
+    let mut ahash = 0u64;
+    for (i, p) in small.pixels().enumerate() {
+        if p[0] as u32 > mean {
+            ahash |= 1 << i;
+        }
+    }
+
+    // dHash: 9x8, each bit = pixel brighter than its right neighbour
+    let wide = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut dhash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            if wide.get_pixel(x, y)[0] > wide.get_pixel(x + 1, y)[0] {
+                dhash |= 1 << (y * 8 + x);
+            }
+        }
+    }
+
+    Ok((ahash, dhash))
+}
+
+/// Hash a single image and store it in the index (called after capture)
+pub fn index_image(path: &Path) -> Result<(), String> {
+    let dir = path.parent().ok_or("Invalid image path")?;
+    let filename = path
+        .file_name()
+        .ok_or("Invalid image path")?
+        .to_string_lossy()
+        .to_string();
+    let metadata = fs::metadata(path).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let (ahash, dhash) = compute_hashes(path)?;
+
+    let _guard = INDEX_LOCK.lock();
+    let mut index = load_index(dir);
+    index.images.insert(
+        filename,
+        ImageMeta {
+            size: metadata.len(),
+            modified: modified_secs(&metadata),
+            ahash,
+            dhash,
+        },
+    );
+    save_index(dir, &index)
+}
+
+pub(crate) fn remove_from_index(dir: &Path, filenames: &[String]) {
+    let _guard = INDEX_LOCK.lock();
+    let mut index = load_index(dir);
+    let before = index.images.len();
+    for name in filenames {
+        index.images.remove(name);
+    }
+    if index.images.len() != before {
+        if let Err(e) = save_index(dir, &index) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub(crate) fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| {
+            let ext_lower = ext.to_string_lossy().to_lowercase();
+            ext_lower == "jpg" || ext_lower == "jpeg" || ext_lower == "png"
+        })
+        .unwrap_or(false)
+}
+
 /// List all images in ~/Pictures/honeybee-camera/
 #[tauri::command]
 pub async fn list_gallery_images() -> Result<Vec<GalleryImage>, String> {
-    let pictures_dir = dirs::picture_dir().ok_or("Failed to get Pictures directory")?;
-    let camera_dir = pictures_dir.join(CAMERA_DIR);
+    let camera_dir = camera_dir()?;
 
     if !camera_dir.exists() {
         return Ok(Vec::new());
@@ -28,24 +183,14 @@ pub async fn list_gallery_images() -> Result<Vec<GalleryImage>, String> {
 
     for entry in entries.flatten() {
         let path = entry.path();
-        if let Some(ext) = path.extension() {
-            let ext_lower = ext.to_string_lossy().to_lowercase();
-            if ext_lower == "jpg" || ext_lower == "jpeg" || ext_lower == "png" {
-                if let Ok(metadata) = entry.metadata() {
-                    let modified = metadata
-                        .modified()
-                        .ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0);
-
-                    images.push(GalleryImage {
-                        filename: entry.file_name().to_string_lossy().to_string(),
-                        path: path.to_string_lossy().to_string(),
-                        size: metadata.len(),
-                        modified,
-                    });
-                }
+        if is_image_file(&path) {
+            if let Ok(metadata) = entry.metadata() {
+                images.push(GalleryImage {
+                    filename: entry.file_name().to_string_lossy().to_string(),
+                    path: path.to_string_lossy().to_string(),
+                    size: metadata.len(),
+                    modified: modified_secs(&metadata),
+                });
             }
         }
     }
@@ -64,18 +209,213 @@ pub async fn read_gallery_image(path: String) -> Result<String, String> {
     Ok(format!("data:image/jpeg;base64,{}", base64_data))
 }
 
+/// Resolve a path from the frontend to an image in the camera directory
+fn gallery_path(camera_dir: &Path, path: &str) -> Result<PathBuf, String> {
+    let target = PathBuf::from(path);
+    let escapes = target
+        .components()
+        .any(|c| matches!(c, std::path::Component::ParentDir));
+    if escapes || !target.starts_with(camera_dir) {
+        return Err(format!("Cannot delete files outside camera directory: {}", path));
+    }
+    if !is_image_file(&target) || !target.is_file() {
+        return Err(format!("Image not found: {}", path));
+    }
+    Ok(target)
+}
+
 /// Delete an image
 #[tauri::command]
 pub async fn delete_gallery_image(path: String) -> Result<bool, String> {
     // Safety: only allow deleting from the camera directory
-    let pictures_dir = dirs::picture_dir().ok_or("Failed to get Pictures directory")?;
-    let camera_dir = pictures_dir.join(CAMERA_DIR);
-    let target = std::path::Path::new(&path);
+    let camera_dir = camera_dir()?;
+    let target = gallery_path(&camera_dir, &path)?;
 
-    if !target.starts_with(&camera_dir) {
-        return Err("Cannot delete files outside camera directory".to_string());
-    }
+    fs::remove_file(&target).map_err(|e| format!("Failed to delete image: {}", e))?;
 
-    fs::remove_file(&path).map_err(|e| format!("Failed to delete image: {}", e))?;
+    if let Some(name) = target.file_name() {
+        remove_from_index(&camera_dir, &[name.to_string_lossy().to_string()]);
+    }
     Ok(true)
 }
+
+/// Delete several images at once (bulk duplicate cleanup). Returns how many were deleted.
+#[tauri::command]
+pub async fn delete_gallery_images(paths: Vec<String>) -> Result<u32, String> {
+    let camera_dir = camera_dir()?;
+
+    // Validate everything up front so a bad path doesn't leave a half-done cleanup
+    let targets = paths
+        .iter()
+        .map(|path| gallery_path(&camera_dir, path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut deleted = Vec::new();
+    for target in &targets {
+        match fs::remove_file(target) {
+            Ok(_) => {
+                if let Some(name) = target.file_name() {
+                    deleted.push(name.to_string_lossy().to_string());
+                }
+            }
+            Err(e) => eprintln!("Failed to delete image {}: {}", target.display(), e),
+        }
+    }
+
+    remove_from_index(&camera_dir, &deleted);
+    Ok(deleted.len() as u32)
+}
+
+/// Find groups of duplicate / near-duplicate images using perceptual hashes.
+/// `threshold` is the max Hamming distance (0-64) on both hashes; lower = stricter.
+#[tauri::command]
+pub async fn find_duplicate_images(threshold: Option<u32>) -> Result<Vec<DuplicateGroup>, String> {
+    let threshold = threshold.unwrap_or(DEFAULT_DUPLICATE_THRESHOLD).min(64);
+    let camera_dir = camera_dir()?;
+
+    if !camera_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    // Hashing decodes every new image, keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || find_duplicates_in(&camera_dir, threshold))
+        .await
+        .map_err(|e| format!("Duplicate scan failed: {}", e))?
+}
+
+fn find_duplicates_in(dir: &Path, threshold: u32) -> Result<Vec<DuplicateGroup>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    let _guard = INDEX_LOCK.lock();
+    let old_index = load_index(dir);
+    let mut index = GalleryIndex::default();
+    let mut images: Vec<(GalleryImage, ImageMeta)> = Vec::new();
+
+    // Refresh the index: reuse hashes for unchanged files, hash new/modified ones
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !is_image_file(&path) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let filename = entry.file_name().to_string_lossy().to_string();
+        let size = metadata.len();
+        let modified = modified_secs(&metadata);
+
+        let meta = match old_index.images.get(&filename) {
+            Some(m) if m.size == size && m.modified == modified => m.clone(),
+            _ => match compute_hashes(&path) {
+                Ok((ahash, dhash)) => ImageMeta { size, modified, ahash, dhash },
+                Err(e) => {
+                    eprintln!("Skipping {}: {}", filename, e);
+                    continue;
+                }
+            },
+        };
+
+        index.images.insert(filename.clone(), meta.clone());
+        images.push((
+            GalleryImage {
+                filename,
+                path: path.to_string_lossy().to_string(),
+                size,
+                modified,
+            },
+            meta,
+        ));
+    }
+
+    if let Err(e) = save_index(dir, &index) {
+        eprintln!("{}", e);
+    }
+
+    Ok(group_duplicates(images, threshold))
+}
+
+/// Hash distance between two images, 0 = identical hashes, 128 = opposite
+fn hash_distance(a: &ImageMeta, b: &ImageMeta) -> u32 {
+    (a.ahash ^ b.ahash).count_ones() + (a.dhash ^ b.dhash).count_ones()
+}
+
+fn within_threshold(a: &ImageMeta, b: &ImageMeta, threshold: u32) -> bool {
+    (a.ahash ^ b.ahash).count_ones() <= threshold
+        && (a.dhash ^ b.dhash).count_ones() <= threshold
+}
+
+/// Cluster images whose hashes are within `threshold` of every other image in
+/// the group (complete linkage). Chaining A~B~C must not put A and C together
+/// when they aren't similar: deleting a group's extras would remove
+/// non-duplicates.
+fn group_duplicates(mut images: Vec<(GalleryImage, ImageMeta)>, threshold: u32) -> Vec<DuplicateGroup> {
+    images.sort_by_key(|(image, _)| std::cmp::Reverse(image.modified));
+
+    // Newest first, so each group's first image is its newest
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    for i in 0..images.len() {
+        let fits = clusters.iter_mut().find(|members| {
+            members
+                .iter()
+                .all(|&m| within_threshold(&images[m].1, &images[i].1, threshold))
+        });
+        match fits {
+            Some(members) => members.push(i),
+            None => clusters.push(vec![i]),
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = clusters
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let first = &images[members[0]].1;
+            let group: Vec<DuplicateImage> = members
+                .iter()
+                .map(|&i| DuplicateImage {
+                    image: images[i].0.clone(),
+                    similarity: 1.0 - hash_distance(first, &images[i].1) as f32 / 128.0,
+                })
+                .collect();
+            let max_distance = members
+                .iter()
+                .enumerate()
+                .flat_map(|(k, &a)| members[k + 1..].iter().map(move |&b| (a, b)))
+                .map(|(a, b)| hash_distance(&images[a].1, &images[b].1))
+                .max()
+                .unwrap_or(0);
+            DuplicateGroup {
+                images: group,
+                min_similarity: 1.0 - max_distance as f32 / 128.0,
+            }
+        })
+        .collect();
+
+    // Newest groups first
+    groups.sort_by_key(|g| std::cmp::Reverse(g.images[0].image.modified));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gallery_path_stays_in_the_camera_dir() {
+        let dir = std::env::temp_dir().join(format!("honeybee-gallery-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("photo.jpg"), b"jpeg").unwrap();
+        fs::write(dir.join("notes.txt"), b"text").unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        assert_eq!(gallery_path(&dir, &path("photo.jpg")).unwrap(), dir.join("photo.jpg"));
+        assert!(gallery_path(&dir, &path("../../.ssh/authorized_keys")).is_err());
+        assert!(gallery_path(&dir, &path("../photo.jpg")).is_err());
+        assert!(gallery_path(&dir, "/etc/passwd").is_err());
+        assert!(gallery_path(&dir, &path("notes.txt")).is_err());
+        assert!(gallery_path(&dir, &path("missing.jpg")).is_err());
+        assert!(gallery_path(&dir, &path(INDEX_FILE)).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
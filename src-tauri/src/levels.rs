@@ -0,0 +1,142 @@
+//! Audio Level Metering
+//!
+//! RMS / peak levels computed inside audio callbacks without locking.
+//! Producers (the recorder) call `LevelMeter::process` from the callback and
+//! periodically `publish` a reading, which is emitted to the frontend as a
+//! `recording-level` event and broadcast to any Rust subscriber (e.g. the idle
+//! animation reacting to sound).
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+/// Fixed-point scale for accumulating squared samples in an integer atomic
+const SUM_SCALE: f64 = (1u64 << 24) as f64;
+
+/// Peak at or above this is reported as clipping
+const CLIP_THRESHOLD: f32 = 0.99;
+
+/// RMS below this (dBFS) is reported as silence
+const SILENCE_DB: f32 = -50.0;
+
+/// Floor used when converting 0.0 to dB
+const MIN_DB: f32 = -96.0;
+
+lazy_static::lazy_static! {
+    static ref LEVEL_TX: broadcast::Sender<AudioLevel> = broadcast::channel(64).0;
+}
+
+/// One level reading over the last publish interval
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioLevel {
+    /// Where the audio came from (e.g. "recorder")
+    pub source: &'static str,
+    /// Linear RMS (0.0 - 1.0)
+    pub rms: f32,
+    /// Linear peak (0.0 - 1.0)
+    pub peak: f32,
+    pub rms_db: f32,
+    pub peak_db: f32,
+    pub clipping: bool,
+    pub silent: bool,
+}
+
+/// Lock-free accumulator, safe to feed from a real-time audio callback
+pub struct LevelMeter {
+    peak_bits: AtomicU32,
+    sum_sq: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Default for LevelMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LevelMeter {
+    pub const fn new() -> Self {
+        Self {
+            peak_bits: AtomicU32::new(0),
+            sum_sq: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    /// Accumulate a block of samples (called from the audio callback)
+    pub fn process(&self, samples: &[f32]) {
+        if samples.is_empty() {
+            return;
+        }
+
+        let mut peak = 0f32;
+        let mut sum_sq = 0f64;
+        for &s in samples {
+            let a = s.abs();
+            if a > peak {
+                peak = a;
+            }
+            sum_sq += (s as f64) * (s as f64);
+        }
+
+        // Bit patterns of non-negative floats order the same as the values
+        self.peak_bits.fetch_max(peak.min(1.0).to_bits(), Ordering::Relaxed);
+        self.sum_sq
+            .fetch_add((sum_sq * SUM_SCALE) as u64, Ordering::Relaxed);
+        self.count.fetch_add(samples.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Read and reset the accumulated level
+    pub fn take(&self, source: &'static str) -> AudioLevel {
+        let peak = f32::from_bits(self.peak_bits.swap(0, Ordering::Relaxed));
+        let sum_sq = self.sum_sq.swap(0, Ordering::Relaxed) as f64 / SUM_SCALE;
+        let count = self.count.swap(0, Ordering::Relaxed);
+
+        let rms = if count > 0 {
+            (sum_sq / count as f64).sqrt() as f32
+        } else {
+            0.0
+        };
+        let rms_db = to_db(rms);
+
+        AudioLevel {
+            source,
+            rms,
+            peak,
+            rms_db,
+            peak_db: to_db(peak),
+            clipping: peak >= CLIP_THRESHOLD,
+            silent: rms_db < SILENCE_DB,
+        }
+    }
+
+    pub fn reset(&self) {
+        self.peak_bits.store(0, Ordering::Relaxed);
+        self.sum_sq.store(0, Ordering::Relaxed);
+        self.count.store(0, Ordering::Relaxed);
+    }
+}
+
+fn to_db(linear: f32) -> f32 {
+    if linear <= 0.0 {
+        MIN_DB
+    } else {
+        (20.0 * linear.log10()).max(MIN_DB)
+    }
+}
+
+/// Take a reading from `meter`, emit it as `recording-level` and broadcast it
+pub fn publish(app: &AppHandle, meter: &LevelMeter, source: &'static str) -> AudioLevel {
+    let level = meter.take(source);
+    let _ = app.emit("recording-level", level.clone());
+    // No subscribers is fine
+    let _ = LEVEL_TX.send(level.clone());
+    level
+}
+
+/// Subscribe to level readings from Rust (~20 Hz while a source is active)
+#[allow(dead_code)] // Rust-side consumers are optional, the frontend uses the event
+pub fn subscribe() -> broadcast::Receiver<AudioLevel> {
+    LEVEL_TX.subscribe()
+}
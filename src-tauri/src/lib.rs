@@ -1,21 +1,87 @@
+mod alarms;
+mod brightness;
+mod calendar;
 mod camera;
 mod commands;
+mod decode;
+mod ducking;
+mod flac;
+mod fsutil;
 mod gallery;
+mod levels;
+mod library;
+mod mic;
+mod mpris;
+mod ogg_opus;
+mod player;
+mod playlists;
 mod provisioning_ipc;
+mod pulse;
 mod recorder;
+mod storage;
 mod system;
 mod voice_agent_ipc;
+mod wav;
+mod waveform;
 
 #[cfg(debug_assertions)]
 use tauri::Manager;
 
+use alarms::{
+    cancel_timer, create_alarm, delete_alarm, dismiss_alarm, get_alarm_defaults, get_alarms,
+    pause_timer, resume_timer, set_alarm_defaults, set_alarm_enabled, snooze_alarm,
+    start_alarm_scheduler, start_timer, update_alarm,
+};
+use brightness::{
+    get_brightness, get_brightness_config, set_brightness, set_brightness_config,
+    start_brightness_watcher,
+};
+use calendar::{
+    create_calendar_event, delete_calendar_event, delete_calendar_occurrence, export_calendar,
+    find_calendar_files, get_calendar_event, import_calendar, list_calendar_events,
+    start_calendar_reminders, update_calendar_event,
+};
 use camera::{capture_photo, start_camera_stream, stop_camera_stream};
 use commands::{check_wifi_status, get_qr_code_image, start_qr_file_watcher, trigger_provisioning_retry};
-use gallery::{delete_gallery_image, list_gallery_images, read_gallery_image};
+use ducking::{get_ducking_config, set_ducking_config, start_ducking_controller};
+use gallery::{
+    delete_gallery_image, delete_gallery_images, find_duplicate_images, list_gallery_images,
+    read_gallery_image,
+};
+use library::{
+    get_library_config, get_library_status, get_track_cover, list_library_albums,
+    list_library_artists, list_library_tracks, rescan_library, set_library_config,
+    start_library_watcher,
+};
+use mic::{get_mic_status, start_mic_manager};
+use mpris::start_mpris_service;
+use player::{
+    get_player_state, player_enqueue, player_next, player_pause, player_play, player_play_index,
+    player_previous, player_remove, player_resume, player_seek, player_set_repeat,
+    player_set_shuffle, player_stop, start_player,
+};
+use playlists::{
+    add_to_playlist, create_playlist, delete_playlist, get_playlist, list_playlists,
+    rename_playlist, save_playlist,
+};
 use provisioning_ipc::{check_provisioning_socket, start_provisioning_ipc_listener};
-use recorder::{delete_recording, is_recording, list_recordings, read_audio_file, start_recording, stop_recording};
-use system::{get_brightness, set_brightness, get_volume, set_volume};
+use recorder::{
+    delete_recording, get_recorder_settings, is_recording, list_input_devices, list_recordings,
+    pause_recording, read_audio_file, recover_partial_recordings, rename_recording,
+    resume_recording, set_recorder_settings, set_recording_metadata, start_recording,
+    stop_recording,
+};
+use storage::{
+    get_storage_config, get_storage_usage, list_media_favorites, set_media_favorite,
+    set_storage_config, start_storage_monitor,
+};
+use system::{
+    get_input_volume_info, get_system_capabilities, get_volume, get_volume_info,
+    list_audio_outputs, set_default_audio_output, set_input_muted, set_input_volume, set_muted,
+    set_volume, start_volume_watcher, toggle_input_mute, toggle_mute,
+};
 use voice_agent_ipc::{check_voice_agent_socket, start_voice_agent_ipc_listener};
+use waveform::get_recording_waveform;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -33,8 +99,20 @@ pub fn run() {
             // System controls (brightness & volume)
             get_brightness,
             set_brightness,
+            get_brightness_config,
+            set_brightness_config,
             get_volume,
             set_volume,
+            get_volume_info,
+            set_muted,
+            toggle_mute,
+            list_audio_outputs,
+            set_default_audio_output,
+            get_input_volume_info,
+            set_input_volume,
+            set_input_muted,
+            toggle_input_mute,
+            get_system_capabilities,
             // Camera commands
             start_camera_stream,
             stop_camera_stream,
@@ -43,13 +121,89 @@ pub fn run() {
             list_gallery_images,
             read_gallery_image,
             delete_gallery_image,
+            delete_gallery_images,
+            find_duplicate_images,
             // Recorder commands
+            list_input_devices,
             start_recording,
             stop_recording,
+            pause_recording,
+            resume_recording,
             list_recordings,
             read_audio_file,
             delete_recording,
-            is_recording
+            rename_recording,
+            set_recording_metadata,
+            get_recording_waveform,
+            is_recording,
+            get_recorder_settings,
+            set_recorder_settings,
+            // Microphone
+            get_mic_status,
+            // Playback
+            player_play,
+            player_enqueue,
+            player_pause,
+            player_resume,
+            player_stop,
+            player_seek,
+            player_next,
+            player_previous,
+            player_play_index,
+            player_remove,
+            player_set_shuffle,
+            player_set_repeat,
+            get_player_state,
+            // Ducking during voice sessions
+            get_ducking_config,
+            set_ducking_config,
+            // Alarms & timers
+            get_alarms,
+            create_alarm,
+            update_alarm,
+            set_alarm_enabled,
+            delete_alarm,
+            start_timer,
+            pause_timer,
+            resume_timer,
+            cancel_timer,
+            snooze_alarm,
+            dismiss_alarm,
+            get_alarm_defaults,
+            set_alarm_defaults,
+            // Calendar
+            list_calendar_events,
+            get_calendar_event,
+            create_calendar_event,
+            update_calendar_event,
+            delete_calendar_event,
+            delete_calendar_occurrence,
+            import_calendar,
+            export_calendar,
+            find_calendar_files,
+            // Playlists
+            list_playlists,
+            get_playlist,
+            create_playlist,
+            save_playlist,
+            add_to_playlist,
+            rename_playlist,
+            delete_playlist,
+            // Music library
+            get_library_status,
+            get_library_config,
+            set_library_config,
+            rescan_library,
+            list_library_artists,
+            list_library_albums,
+            list_library_tracks,
+            get_track_cover,
+            // Storage management
+            get_storage_usage,
+            get_storage_config,
+            set_storage_config,
+            set_media_favorite,
+            list_media_favorites
         ])
         .setup(|app| {
             // Open devtools only in debug builds
@@ -77,6 +231,9 @@ pub fn run() {
                 }
             }
 
+            // Finalize recordings interrupted by a crash
+            recover_partial_recordings();
+
             // Start the QR file watcher
             let app_handle = app.handle().clone();
             start_qr_file_watcher(app_handle);
@@ -89,6 +246,41 @@ pub fn run() {
             let app_handle_voice = app.handle().clone();
             start_voice_agent_ipc_listener(app_handle_voice);
 
+            // Renew wakeword pauses while the mic is in use
+            start_mic_manager();
+
+            // Start the playback engine
+            let app_handle_player = app.handle().clone();
+            start_player(app_handle_player);
+
+            // Let playerctl and other MPRIS clients see and control playback
+            start_mpris_service();
+
+            // Turn media down while the voice assistant is in a session
+            start_ducking_controller();
+
+            // Ring alarms and timers, also while the UI is closed
+            let app_handle_alarms = app.handle().clone();
+            start_alarm_scheduler(app_handle_alarms);
+
+            // Emit calendar reminders when they come due
+            let app_handle_calendar = app.handle().clone();
+            start_calendar_reminders(app_handle_calendar);
+
+            // Keep the overlay sliders in sync with changes from elsewhere
+            let app_handle_volume = app.handle().clone();
+            start_volume_watcher(app_handle_volume);
+            let app_handle_brightness = app.handle().clone();
+            start_brightness_watcher(app_handle_brightness);
+
+            // Index the music folder and watch it for changes
+            let app_handle_library = app.handle().clone();
+            start_library_watcher(app_handle_library);
+
+            // Start the storage quota monitor
+            let app_handle_storage = app.handle().clone();
+            start_storage_monitor(app_handle_storage);
+
             Ok(())
         })
         .run(tauri::generate_context!())
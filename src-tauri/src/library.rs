@@ -0,0 +1,712 @@
+//! Music Library
+//!
+//! Indexes MP3 / FLAC / Ogg / M4A files under ~/Music, or the folder set in
+//! ~/.config/honeybee/library.json, with their tags (artist, album, title,
+//! track number, embedded cover art). Our own recordings are left out, the
+//! recorder lists those.
+//!
+//! The index is cached in ~/.cache/honeybee/library.json and kept up to date
+//! incrementally: a scan only re-reads files whose size or mtime changed, and a
+//! `notify` watcher re-reads what changes while the app runs. `library-updated`
+//! is emitted after every refresh.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+use symphonia::core::{
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey},
+    probe::Hint,
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::fsutil::write_atomic;
+use crate::recorder;
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "oga", "opus", "m4a"];
+
+/// Image files used as album art when a track has none embedded
+const FOLDER_COVERS: &[&str] = &["cover.jpg", "cover.png", "folder.jpg", "folder.png", "front.jpg"];
+
+const UNKNOWN_ARTIST: &str = "Unknown Artist";
+const UNKNOWN_ALBUM: &str = "Unknown Album";
+
+/// Bumped when `Track` changes so old caches are rebuilt
+const INDEX_VERSION: u32 = 1;
+
+/// Changes are applied once the folder has been quiet this long
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// How often the watcher checks for a config change or rescan request
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wait before trying again when the folder can't be watched (e.g. doesn't exist yet)
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+lazy_static::lazy_static! {
+    // Indexed tracks by path
+    static ref LIBRARY: Mutex<BTreeMap<String, Track>> = Mutex::new(BTreeMap::new());
+    // Serializes config file updates
+    static ref CONFIG_LOCK: Mutex<()> = Mutex::new(());
+}
+
+static SCANNING: AtomicBool = AtomicBool::new(false);
+
+/// Bumped to make the watcher thread rescan (config change, manual rescan)
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Library configuration (~/.config/honeybee/library.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryConfig {
+    /// Folder to index (None = ~/Music)
+    pub music_dir: Option<String>,
+}
+
+/// An indexed audio file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Track {
+    pub path: String,
+    /// Title tag, or the file name without extension
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub duration_ms: u64,
+    /// Cover art is embedded in the file
+    pub has_cover: bool,
+    pub size: u64,
+    pub modified: u64, // unix timestamp
+}
+
+impl Track {
+    /// Artist the track is filed under
+    fn artist_name(&self) -> &str {
+        self.album_artist
+            .as_deref()
+            .or(self.artist.as_deref())
+            .unwrap_or(UNKNOWN_ARTIST)
+    }
+
+    fn album_name(&self) -> &str {
+        self.album.as_deref().unwrap_or(UNKNOWN_ALBUM)
+    }
+}
+
+/// Artists and albums are grouped ignoring case, filters must match the same way
+fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtistInfo {
+    pub name: String,
+    pub album_count: usize,
+    pub track_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlbumInfo {
+    pub title: String,
+    pub artist: String,
+    pub track_count: usize,
+    pub duration_ms: u64,
+    /// Track to fetch the cover from with `get_track_cover`
+    pub cover_track: Option<String>,
+}
+
+/// Sent with `library-updated`
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryStatus {
+    pub music_dir: String,
+    pub scanning: bool,
+    pub track_count: usize,
+}
+
+/// Cached index on disk
+#[derive(Default, Serialize, Deserialize)]
+struct LibraryIndex {
+    version: u32,
+    music_dir: String,
+    tracks: Vec<Track>,
+}
+
+fn config_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/library.json"))
+        .unwrap_or_else(|| PathBuf::from("library.json"))
+}
+
+fn index_path() -> PathBuf {
+    dirs::cache_dir()
+        .map(|cache| cache.join("honeybee/library.json"))
+        .unwrap_or_else(|| PathBuf::from("library-index.json"))
+}
+
+fn load_config() -> LibraryConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Folder being indexed
+fn music_dir() -> PathBuf {
+    load_config()
+        .music_dir
+        .map(PathBuf::from)
+        .or_else(dirs::audio_dir)
+        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
+        .unwrap_or_else(|| PathBuf::from("Music"))
+}
+
+fn load_index(dir: &Path) -> BTreeMap<String, Track> {
+    let index: LibraryIndex = fs::read_to_string(index_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    if index.version != INDEX_VERSION || Path::new(&index.music_dir) != dir {
+        return BTreeMap::new();
+    }
+    index
+        .tracks
+        .into_iter()
+        .map(|t| (t.path.clone(), t))
+        .collect()
+}
+
+fn save_index(dir: &Path) -> Result<(), String> {
+    let index = LibraryIndex {
+        version: INDEX_VERSION,
+        music_dir: dir.to_string_lossy().to_string(),
+        tracks: LIBRARY.lock().values().cloned().collect(),
+    };
+    let path = index_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    }
+    let json = serde_json::to_string(&index).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to write library index: {}", e))
+}
+
+fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// The music folder, with the recordings folder looked up once per scan
+struct MusicDir<'a> {
+    root: &'a Path,
+    recordings: Option<PathBuf>,
+}
+
+impl<'a> MusicDir<'a> {
+    fn new(root: &'a Path) -> Self {
+        Self {
+            root,
+            recordings: recorder::recordings_dir().ok(),
+        }
+    }
+
+    /// Hidden files and our own recordings aren't part of the library
+    fn is_ignored(&self, path: &Path) -> bool {
+        if self.recordings.as_ref().is_some_and(|rec| path.starts_with(rec)) {
+            return true;
+        }
+        path.strip_prefix(self.root)
+            .map(|rel| {
+                rel.components()
+                    .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+            })
+            .unwrap_or(true)
+    }
+}
+
+/// Size and mtime of a file, used to tell whether its entry is stale
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some((metadata.len(), modified))
+}
+
+/// Audio files under `dir`, without following directory symlinks
+fn walk(dir: &Path, music: &MusicDir, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if music.is_ignored(&path) {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(ft) if ft.is_dir() => walk(&path, music, out),
+            Ok(_) if is_audio_file(&path) && path.is_file() => out.push(path),
+            _ => {}
+        }
+    }
+}
+
+/// Leading number of tags like "3" or "3/12"
+fn parse_number(value: &str) -> Option<u32> {
+    let digits: String = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Tags gathered from all metadata blocks of a file
+#[derive(Default)]
+struct TagSet {
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    album_artist: Option<String>,
+    track_number: Option<u32>,
+    disc_number: Option<u32>,
+    has_cover: bool,
+}
+
+impl TagSet {
+    /// Earlier blocks win, so ID3v2 in front of an MP3 beats trailing tags
+    fn add(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let value = tag.value.to_string().trim().to_string();
+            if value.is_empty() {
+                continue;
+            }
+            let slot = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => &mut self.title,
+                Some(StandardTagKey::Artist) => &mut self.artist,
+                Some(StandardTagKey::Album) => &mut self.album,
+                Some(StandardTagKey::AlbumArtist) => &mut self.album_artist,
+                Some(StandardTagKey::TrackNumber) => {
+                    self.track_number = self.track_number.or(parse_number(&value));
+                    continue;
+                }
+                Some(StandardTagKey::DiscNumber) => {
+                    self.disc_number = self.disc_number.or(parse_number(&value));
+                    continue;
+                }
+                _ => continue,
+            };
+            slot.get_or_insert(value);
+        }
+        self.has_cover |= !revision.visuals().is_empty();
+    }
+}
+
+/// Probe a file, handing every metadata block to `visit`. Returns the duration.
+fn probe_file(path: &Path, mut visit: impl FnMut(&MetadataRevision)) -> Result<u64, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension() {
+        hint.with_extension(&ext.to_string_lossy());
+    }
+
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Unsupported audio file: {}", e))?;
+
+    // Metadata found while probing (ID3v2), then the container's own
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            visit(revision);
+        }
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        visit(revision);
+    }
+
+    let duration_ms = probed
+        .format
+        .default_track()
+        .and_then(|t| {
+            let rate = t.codec_params.sample_rate?;
+            Some(t.codec_params.n_frames? * 1000 / rate.max(1) as u64)
+        })
+        .unwrap_or(0);
+    Ok(duration_ms)
+}
+
+fn read_track(path: &Path, (size, modified): (u64, u64)) -> Result<Track, String> {
+    let mut tags = TagSet::default();
+    let duration_ms = probe_file(path, |revision| tags.add(revision))?;
+
+    Ok(Track {
+        path: path.to_string_lossy().to_string(),
+        title: tags.title.unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        }),
+        artist: tags.artist,
+        album: tags.album,
+        album_artist: tags.album_artist,
+        track_number: tags.track_number,
+        disc_number: tags.disc_number,
+        duration_ms,
+        has_cover: tags.has_cover,
+        size,
+        modified,
+    })
+}
+
+/// Indexed entry for a file, if it's in the library
+pub(crate) fn find_track(path: &str) -> Option<Track> {
+    LIBRARY.lock().get(path).cloned()
+}
+
+fn status() -> LibraryStatus {
+    LibraryStatus {
+        music_dir: music_dir().to_string_lossy().to_string(),
+        scanning: SCANNING.load(Ordering::SeqCst),
+        track_count: LIBRARY.lock().len(),
+    }
+}
+
+fn emit_updated(app: &AppHandle) {
+    let _ = app.emit("library-updated", status());
+}
+
+/// Entry for `path`, re-read only if the file changed since `known`
+fn refresh_track(path: &Path, known: Option<&Track>) -> Option<Track> {
+    let stamp = file_stamp(path)?;
+    if let Some(track) = known.filter(|t| (t.size, t.modified) == stamp) {
+        return Some(track.clone());
+    }
+    match read_track(path, stamp) {
+        Ok(track) => Some(track),
+        Err(e) => {
+            eprintln!("Skipping {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Walk the whole folder, reusing cached entries for unchanged files
+fn scan(app: &AppHandle, music: &MusicDir, cached: BTreeMap<String, Track>) {
+    SCANNING.store(true, Ordering::SeqCst);
+    emit_updated(app);
+    let started = Instant::now();
+
+    let mut files = Vec::new();
+    walk(music.root, music, &mut files);
+    let tracks: BTreeMap<String, Track> = files
+        .iter()
+        .filter_map(|path| refresh_track(path, cached.get(path.to_string_lossy().as_ref())))
+        .map(|t| (t.path.clone(), t))
+        .collect();
+
+    println!(
+        "🎵 Indexed {} tracks in {:?} ({:.1}s)",
+        tracks.len(),
+        music.root,
+        started.elapsed().as_secs_f32()
+    );
+    *LIBRARY.lock() = tracks;
+    if let Err(e) = save_index(music.root) {
+        eprintln!("{}", e);
+    }
+    SCANNING.store(false, Ordering::SeqCst);
+    emit_updated(app);
+}
+
+/// Update the entries under changed paths
+fn apply_changes(app: &AppHandle, music: &MusicDir, paths: HashSet<PathBuf>) {
+    let mut changed = false;
+    for path in paths {
+        let key = path.to_string_lossy().to_string();
+        if path.is_dir() {
+            // New or moved-in folder
+            let mut files = Vec::new();
+            walk(&path, music, &mut files);
+            for file in files {
+                let file_key = file.to_string_lossy().to_string();
+                let known = LIBRARY.lock().get(&file_key).cloned();
+                if let Some(track) = refresh_track(&file, known.as_ref()) {
+                    LIBRARY.lock().insert(file_key, track);
+                    changed = true;
+                }
+            }
+        } else if path.is_file() {
+            if !is_audio_file(&path) {
+                continue;
+            }
+            let known = LIBRARY.lock().get(&key).cloned();
+            if let Some(track) = refresh_track(&path, known.as_ref()) {
+                LIBRARY.lock().insert(key, track);
+                changed = true;
+            }
+        } else {
+            // Gone: the file itself, or everything under a removed folder
+            let prefix = format!("{}/", key);
+            let mut library = LIBRARY.lock();
+            let before = library.len();
+            library.retain(|p, _| p != &key && !p.starts_with(&prefix));
+            changed |= library.len() != before;
+        }
+    }
+
+    if changed {
+        if let Err(e) = save_index(music.root) {
+            eprintln!("{}", e);
+        }
+        emit_updated(app);
+    }
+}
+
+/// Sleep until a rescan is requested or `RETRY_INTERVAL` is up
+fn wait_for_retry(generation: u64) {
+    let started = Instant::now();
+    while GENERATION.load(Ordering::SeqCst) == generation && started.elapsed() < RETRY_INTERVAL {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Start watching the folder, events queue up on the returned channel
+fn start_watcher(
+    dir: &Path,
+) -> Result<(RecommendedWatcher, Receiver<notify::Result<Event>>), String> {
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create library watcher: {}", e))?;
+    watcher
+        .watch(dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch music folder {:?}: {}", dir, e))?;
+    println!("📁 Watching music folder: {:?}", dir);
+    Ok((watcher, rx))
+}
+
+/// Apply the watcher's events until a rescan is requested
+fn watch(app: &AppHandle, music: &MusicDir, rx: &Receiver<notify::Result<Event>>, generation: u64) {
+    let mut pending = HashSet::new();
+    let mut last_event = Instant::now();
+    while GENERATION.load(Ordering::SeqCst) == generation {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+                pending.extend(event.paths.into_iter().filter(|p| !music.is_ignored(p)));
+                last_event = Instant::now();
+            }
+            Ok(Err(e)) => eprintln!("Library watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return wait_for_retry(generation),
+        }
+
+        if !pending.is_empty() && last_event.elapsed() >= DEBOUNCE {
+            apply_changes(app, music, std::mem::take(&mut pending));
+        }
+    }
+}
+
+/// Index the music folder and keep the index up to date
+pub fn start_library_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut cached = load_index(&music_dir());
+        *LIBRARY.lock() = cached.clone();
+
+        loop {
+            let generation = GENERATION.load(Ordering::SeqCst);
+            let dir = music_dir();
+            let music = MusicDir::new(&dir);
+            // Watch before scanning so changes made meanwhile aren't missed
+            match start_watcher(&dir) {
+                Ok((_watcher, rx)) => {
+                    scan(&app, &music, cached);
+                    watch(&app, &music, &rx, generation);
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    scan(&app, &music, cached);
+                    wait_for_retry(generation);
+                }
+            }
+            cached = LIBRARY.lock().clone();
+        }
+    });
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_library_status() -> LibraryStatus {
+    status()
+}
+
+#[tauri::command]
+pub fn get_library_config() -> LibraryConfig {
+    load_config()
+}
+
+/// Update the library configuration and re-index
+#[tauri::command]
+pub fn set_library_config(config: LibraryConfig) -> Result<(), String> {
+    if let Some(dir) = &config.music_dir {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("Folder not found: {}", dir));
+        }
+    }
+
+    {
+        let _guard = CONFIG_LOCK.lock();
+        let path = config_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config dir: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        write_atomic(&path, json).map_err(|e| format!("Failed to save library config: {}", e))?;
+    }
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Re-walk the music folder (unchanged files are not re-read)
+#[tauri::command]
+pub fn rescan_library() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Artists with album and track counts, sorted by name
+#[tauri::command]
+pub fn list_library_artists() -> Vec<ArtistInfo> {
+    let library = LIBRARY.lock();
+    // Keyed by lowercased name, keeps the first spelling seen
+    let mut artists: HashMap<String, (&str, HashSet<String>, usize)> = HashMap::new();
+    for track in library.values() {
+        let entry = artists
+            .entry(track.artist_name().to_lowercase())
+            .or_insert_with(|| (track.artist_name(), HashSet::new(), 0));
+        entry.1.insert(track.album_name().to_lowercase());
+        entry.2 += 1;
+    }
+
+    let mut result: Vec<ArtistInfo> = artists
+        .into_values()
+        .map(|(name, albums, track_count)| ArtistInfo {
+            name: name.to_string(),
+            album_count: albums.len(),
+            track_count,
+        })
+        .collect();
+    result.sort_by_key(|a| a.name.to_lowercase());
+    result
+}
+
+/// Albums, optionally of one artist, sorted by artist then title
+#[tauri::command]
+pub fn list_library_albums(artist: Option<String>) -> Vec<AlbumInfo> {
+    let library = LIBRARY.lock();
+    let mut albums: BTreeMap<(String, String), AlbumInfo> = BTreeMap::new();
+    for track in library.values() {
+        if artist.as_deref().is_some_and(|a| !same_name(a, track.artist_name())) {
+            continue;
+        }
+        let album = albums
+            .entry((track.artist_name().to_lowercase(), track.album_name().to_lowercase()))
+            .or_insert_with(|| AlbumInfo {
+                title: track.album_name().to_string(),
+                artist: track.artist_name().to_string(),
+                track_count: 0,
+                duration_ms: 0,
+                cover_track: None,
+            });
+        album.track_count += 1;
+        album.duration_ms += track.duration_ms;
+        if album.cover_track.is_none() && track.has_cover {
+            album.cover_track = Some(track.path.clone());
+        }
+    }
+    albums.into_values().collect()
+}
+
+/// Tracks, optionally filtered by artist and album, in album order
+#[tauri::command]
+pub fn list_library_tracks(artist: Option<String>, album: Option<String>) -> Vec<Track> {
+    let library = LIBRARY.lock();
+    let mut tracks: Vec<Track> = library
+        .values()
+        .filter(|t| artist.as_deref().is_none_or(|a| same_name(a, t.artist_name())))
+        .filter(|t| album.as_deref().is_none_or(|a| same_name(a, t.album_name())))
+        .cloned()
+        .collect();
+    tracks.sort_by_key(|t| {
+        (
+            t.artist_name().to_lowercase(),
+            t.album_name().to_lowercase(),
+            t.disc_number.unwrap_or(0),
+            t.track_number.unwrap_or(u32::MAX),
+            t.title.to_lowercase(),
+        )
+    });
+    tracks
+}
+
+/// Cover art of a library track as a data URL: the embedded picture (front
+/// cover preferred), else a cover image in its folder
+#[tauri::command]
+pub async fn get_track_cover(path: String) -> Result<Option<String>, String> {
+    if !LIBRARY.lock().contains_key(&path) {
+        return Err("Track is not in the library".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        let mut cover: Option<(String, Box<[u8]>)> = None;
+        probe_file(&path, |revision| {
+            for visual in revision.visuals() {
+                let front = visual.usage == Some(StandardVisualKey::FrontCover);
+                if cover.is_none() || front {
+                    cover = Some((visual.media_type.clone(), visual.data.clone()));
+                }
+            }
+        })?;
+
+        if cover.is_none() {
+            let dir = path.parent().ok_or("Invalid track path")?;
+            cover = FOLDER_COVERS.iter().map(|name| dir.join(name)).find_map(|p| {
+                let media_type = if p.extension().is_some_and(|e| e == "png") {
+                    "image/png"
+                } else {
+                    "image/jpeg"
+                };
+                fs::read(&p).ok().map(|data| (media_type.to_string(), data.into()))
+            });
+        }
+
+        Ok(cover.map(|(media_type, data)| {
+            format!("data:{};base64,{}", media_type, STANDARD.encode(&data))
+        }))
+    })
+    .await
+    .map_err(|e| format!("Cover task failed: {}", e))?
+}
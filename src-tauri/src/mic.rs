@@ -0,0 +1,202 @@
+//! Microphone Arbitration
+//!
+//! Only one user of the input device at a time. In-app users (the recorder)
+//! take a `MicLease`, which pauses the voice agent's wakeword detection over
+//! its IPC socket and resumes it when dropped, including on panic. While the
+//! voice agent is in a session the mic is reported as owned by it and in-app
+//! users are refused.
+//!
+//! Pauses are sent with a lease that is renewed while the mic is held, so the
+//! agent recovers by itself if the app dies, and re-pauses if the agent restarts.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::voice_agent_ipc::{send_voice_agent_command, VoiceAgentCommand};
+
+/// How long a wakeword pause lasts unless renewed
+const PAUSE_LEASE_SECS: u64 = 30;
+
+/// How often the pause is renewed while the mic is held
+const RENEW_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Owner name the voice agent sees in commands
+const IPC_OWNER: &str = "honeybee-app";
+
+lazy_static::lazy_static! {
+    static ref MIC_STATE: Mutex<MicState> = Mutex::new(MicState::default());
+    /// Held while a state change and its command go out, so a pause can't
+    /// overtake a resume. MIC_STATE itself is never held over the socket.
+    static ref COMMAND_ORDER: Mutex<()> = Mutex::new(());
+}
+
+/// Who is using the microphone
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MicOwner {
+    Recorder,
+    VoiceAgent,
+}
+
+impl MicOwner {
+    fn label(self) -> &'static str {
+        match self {
+            MicOwner::Recorder => "the recorder",
+            MicOwner::VoiceAgent => "the voice assistant",
+        }
+    }
+}
+
+#[derive(Default)]
+struct MicState {
+    /// In-app owner holding a lease
+    app_owner: Option<MicOwner>,
+    /// Voice agent is in a session (wakeword heard, listening)
+    voice_agent_active: bool,
+    /// Last pause command reached the agent
+    wakeword_paused: bool,
+}
+
+impl MicState {
+    fn status(&self) -> MicStatus {
+        let owner = self
+            .app_owner
+            .or(self.voice_agent_active.then_some(MicOwner::VoiceAgent));
+        MicStatus {
+            owner,
+            wakeword_paused: self.wakeword_paused,
+        }
+    }
+}
+
+/// Current microphone owner, sent with `mic-owner-changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct MicStatus {
+    pub owner: Option<MicOwner>,
+    pub wakeword_paused: bool,
+}
+
+/// Exclusive use of the microphone, released on drop
+pub struct MicLease {
+    app: AppHandle,
+    owner: MicOwner,
+}
+
+impl Drop for MicLease {
+    fn drop(&mut self) {
+        release(&self.app, self.owner);
+    }
+}
+
+fn pause_wakeword() -> bool {
+    let command = VoiceAgentCommand::PauseWakeword {
+        owner: IPC_OWNER.to_string(),
+        lease_secs: PAUSE_LEASE_SECS,
+    };
+    match send_voice_agent_command(&command) {
+        Ok(_) => true,
+        Err(e) => {
+            // No agent running means nobody else is on the mic
+            println!("🎤 Could not pause wakeword detection: {}", e);
+            false
+        }
+    }
+}
+
+fn resume_wakeword() {
+    let command = VoiceAgentCommand::ResumeWakeword {
+        owner: IPC_OWNER.to_string(),
+    };
+    if let Err(e) = send_voice_agent_command(&command) {
+        println!("🎤 Could not resume wakeword detection: {}", e);
+    }
+}
+
+fn emit_status(app: &AppHandle, status: MicStatus) {
+    let _ = app.emit("mic-owner-changed", status);
+}
+
+/// Take the microphone for `owner`. Fails if someone else has it.
+/// Blocks on the voice agent socket, keep it off the async runtime.
+pub fn acquire(app: &AppHandle, owner: MicOwner) -> Result<MicLease, String> {
+    let _order = COMMAND_ORDER.lock();
+    {
+        let mut state = MIC_STATE.lock();
+        if let Some(current) = state.status().owner {
+            return Err(format!("Microphone is in use by {}", current.label()));
+        }
+        state.app_owner = Some(owner);
+    }
+
+    let paused = pause_wakeword();
+    let status = {
+        let mut state = MIC_STATE.lock();
+        state.wakeword_paused = paused;
+        state.status()
+    };
+    println!("🎤 Microphone acquired by {}", owner.label());
+    emit_status(app, status);
+
+    Ok(MicLease {
+        app: app.clone(),
+        owner,
+    })
+}
+
+fn release(app: &AppHandle, owner: MicOwner) {
+    let _order = COMMAND_ORDER.lock();
+    let status = {
+        let mut state = MIC_STATE.lock();
+        if state.app_owner != Some(owner) {
+            return;
+        }
+        state.app_owner = None;
+        state.wakeword_paused = false;
+        state.status()
+    };
+    resume_wakeword();
+
+    println!("🎤 Microphone released by {}", owner.label());
+    emit_status(app, status);
+}
+
+/// Called by the voice agent IPC listener when a session starts or ends
+pub fn set_voice_agent_active(app: &AppHandle, active: bool) {
+    let status = {
+        let mut state = MIC_STATE.lock();
+        if state.voice_agent_active == active {
+            return;
+        }
+        state.voice_agent_active = active;
+        state.status()
+    };
+    emit_status(app, status);
+}
+
+/// Get the current microphone owner
+#[tauri::command]
+pub fn get_mic_status() -> MicStatus {
+    MIC_STATE.lock().status()
+}
+
+/// Clear a wakeword pause left over from a previous run and keep pauses
+/// renewed while the mic is held
+pub fn start_mic_manager() {
+    thread::spawn(|| {
+        // A crash may have left the agent paused until its lease runs out
+        resume_wakeword();
+
+        loop {
+            thread::sleep(RENEW_INTERVAL);
+            let _order = COMMAND_ORDER.lock();
+            if MIC_STATE.lock().app_owner.is_none() {
+                continue;
+            }
+            let paused = pause_wakeword();
+            MIC_STATE.lock().wakeword_paused = paused;
+        }
+    });
+}
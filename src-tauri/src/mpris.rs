@@ -0,0 +1,565 @@
+//! MPRIS Remote Control
+//!
+//! Publishes the playback engine on the session bus as
+//! `org.mpris.MediaPlayer2.honeybee`, so `playerctl`, desktop widgets and
+//! home-automation scripts can see what's playing and control it like any
+//! other Linux media player.
+//!
+//! Properties are read from the player state, and `PropertiesChanged` /
+//! `Seeked` are sent as the engine broadcasts changes. Volume is the system
+//! output volume. To try it against a private bus:
+//!
+//! ```text
+//! eval $(dbus-launch --sh-syntax)   # or dbus-daemon --session --print-address --fork
+//! playerctl -p honeybee metadata
+//! ```
+//!
+//! `cargo test -- --ignored mpris` does the same against a fake engine.
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::{
+        stdintf::org_freedesktop_dbus::{PropertiesPropertiesChanged, RequestNameReply},
+        Connection,
+    },
+    channel::{MatchingReceiver, Sender},
+    message::{MatchRule, SignalArgs},
+    Message,
+};
+use dbus_crossroads::{Crossroads, IfaceBuilder, MethodErr};
+use std::{path::Path, thread, time::Duration};
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::library;
+use crate::player::{self, PlaybackStatus, PlayerEvent, PlayerState, RepeatMode};
+use crate::system;
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.honeybee";
+
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+const ROOT_IFACE: &str = "org.mpris.MediaPlayer2";
+
+const PLAYER_IFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Track id for "nothing loaded", reserved by the spec
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// How long to wait for bus messages before checking for player changes
+const PROCESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Formats the decoder can play (see `decode.rs`)
+const MIME_TYPES: &[&str] = &[
+    "audio/mpeg",
+    "audio/flac",
+    "audio/ogg",
+    "audio/opus",
+    "audio/wav",
+    "audio/mp4",
+    "audio/aac",
+];
+
+// ============================================================================
+// PROPERTY VALUES
+// ============================================================================
+
+fn playback_status(state: &PlayerState) -> String {
+    match state.status {
+        PlaybackStatus::Playing => "Playing",
+        PlaybackStatus::Paused => "Paused",
+        PlaybackStatus::Stopped => "Stopped",
+    }
+    .to_string()
+}
+
+fn loop_status(state: &PlayerState) -> String {
+    match state.repeat {
+        RepeatMode::Off => "None",
+        RepeatMode::One => "Track",
+        RepeatMode::All => "Playlist",
+    }
+    .to_string()
+}
+
+/// Unique per queue entry, so `SetPosition` can't seek a track that has since
+/// changed
+fn track_id(state: &PlayerState) -> dbus::Path<'static> {
+    match state.index {
+        Some(index) if state.path.is_some() => {
+            dbus::Path::from(format!("/org/honeybee/queue/{}", index))
+        }
+        _ => dbus::Path::from(NO_TRACK),
+    }
+}
+
+fn insert(metadata: &mut PropMap, key: &str, value: impl RefArg + 'static) {
+    metadata.insert(key.to_string(), Variant(Box::new(value)));
+}
+
+/// Title, artist and album come from the music library when the track is in
+/// it, otherwise the file name is the title
+fn metadata(state: &PlayerState) -> PropMap {
+    let mut metadata = PropMap::new();
+    insert(&mut metadata, "mpris:trackid", track_id(state));
+    let Some(path) = &state.path else {
+        return metadata;
+    };
+
+    let track = library::find_track(path);
+    let title = match &track {
+        Some(track) => track.title.clone(),
+        None => Path::new(path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone()),
+    };
+    insert(&mut metadata, "xesam:title", title);
+    insert(&mut metadata, "xesam:url", format!("file://{}", path));
+    if state.duration_ms > 0 {
+        insert(&mut metadata, "mpris:length", state.duration_ms as i64 * 1000);
+    }
+
+    if let Some(track) = track {
+        if let Some(artist) = track.artist {
+            insert(&mut metadata, "xesam:artist", vec![artist]);
+        }
+        if let Some(album_artist) = track.album_artist {
+            insert(&mut metadata, "xesam:albumArtist", vec![album_artist]);
+        }
+        if let Some(album) = track.album {
+            insert(&mut metadata, "xesam:album", album);
+        }
+        if let Some(number) = track.track_number {
+            insert(&mut metadata, "xesam:trackNumber", number as i32);
+        }
+        if let Some(number) = track.disc_number {
+            insert(&mut metadata, "xesam:discNumber", number as i32);
+        }
+    }
+    metadata
+}
+
+fn can_go_next(state: &PlayerState) -> bool {
+    state.index.is_some() && (state.queue.len() > 1 || state.repeat != RepeatMode::Off)
+}
+
+fn can_go_previous(state: &PlayerState) -> bool {
+    state.index.is_some()
+}
+
+fn can_play(state: &PlayerState) -> bool {
+    !state.queue.is_empty()
+}
+
+fn can_pause(state: &PlayerState) -> bool {
+    state.status != PlaybackStatus::Stopped
+}
+
+fn can_seek(state: &PlayerState) -> bool {
+    state.status != PlaybackStatus::Stopped && state.duration_ms > 0
+}
+
+/// Decode `file://` URIs, as sent by `playerctl open`
+fn uri_to_path(uri: &str) -> String {
+    let encoded = uri.strip_prefix("file://").unwrap_or(uri).as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let hex = encoded
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (encoded[i], hex) {
+            (b'%', Some(byte)) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                bytes.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+fn failed(error: String) -> MethodErr {
+    MethodErr::failed(&error)
+}
+
+// ============================================================================
+// INTERFACES
+// ============================================================================
+
+/// Root interface, the kiosk can't be raised or quit from outside
+fn register_root(b: &mut IfaceBuilder<()>) {
+    b.method("Raise", (), (), |_, _, _: ()| Ok(()));
+    b.method("Quit", (), (), |_, _, _: ()| Ok(()));
+    b.property("CanQuit")
+        .emits_changed_const()
+        .get(|_, _| Ok(false));
+    b.property("CanRaise")
+        .emits_changed_const()
+        .get(|_, _| Ok(false));
+    b.property("HasTrackList")
+        .emits_changed_const()
+        .get(|_, _| Ok(false));
+    b.property("Identity")
+        .emits_changed_const()
+        .get(|_, _| Ok("Honeybee".to_string()));
+    b.property("SupportedUriSchemes")
+        .emits_changed_const()
+        .get(|_, _| Ok(vec!["file".to_string()]));
+    b.property("SupportedMimeTypes")
+        .emits_changed_const()
+        .get(|_, _| Ok(MIME_TYPES.iter().map(|m| m.to_string()).collect::<Vec<_>>()));
+}
+
+fn register_player(b: &mut IfaceBuilder<()>) {
+    b.method("Next", (), (), |_, _, _: ()| {
+        player::player_next().map_err(failed)
+    });
+    b.method("Previous", (), (), |_, _, _: ()| {
+        player::player_previous().map_err(failed)
+    });
+    b.method("Pause", (), (), |_, _, _: ()| {
+        player::player_pause().map_err(failed)
+    });
+    b.method("PlayPause", (), (), |_, _, _: ()| {
+        match player::get_player_state().status {
+            PlaybackStatus::Playing => player::player_pause(),
+            _ => player::player_resume(),
+        }
+        .map_err(failed)
+    });
+    b.method("Stop", (), (), |_, _, _: ()| {
+        player::player_stop().map_err(failed)
+    });
+    b.method("Play", (), (), |_, _, _: ()| {
+        player::player_resume().map_err(failed)
+    });
+    // Relative seek in microseconds, past the end skips to the next track
+    b.method("Seek", ("Offset",), (), |_, _, (offset,): (i64,)| {
+        let state = player::get_player_state();
+        if !can_seek(&state) {
+            return Ok(());
+        }
+        let target_ms = (state.position_ms as i64 + offset / 1000).max(0) as u64;
+        if target_ms >= state.duration_ms {
+            return player::player_next().map_err(failed);
+        }
+        player::player_seek(target_ms).map_err(failed)
+    });
+    // Absolute seek in microseconds, ignored if the track has changed
+    b.method(
+        "SetPosition",
+        ("TrackId", "Position"),
+        (),
+        |_, _, (id, position): (dbus::Path<'static>, i64)| {
+            let state = player::get_player_state();
+            let position_ms = position / 1000;
+            if id != track_id(&state)
+                || !can_seek(&state)
+                || position_ms < 0
+                || position_ms as u64 > state.duration_ms
+            {
+                return Ok(());
+            }
+            player::player_seek(position_ms as u64).map_err(failed)
+        },
+    );
+    // Replaces the queue with a single file
+    b.method("OpenUri", ("Uri",), (), |_, _, (uri,): (String,)| {
+        if !uri.starts_with("file://") && !uri.starts_with('/') {
+            return Err(MethodErr::failed(&format!(
+                "Only local files can be opened: {}",
+                uri
+            )));
+        }
+        player::player_play(vec![uri_to_path(&uri)], None).map_err(failed)
+    });
+    b.signal::<(i64,), _>("Seeked", ("Position",));
+
+    b.property("PlaybackStatus")
+        .get(|_, _| Ok(playback_status(&player::get_player_state())));
+    b.property("LoopStatus")
+        .get(|_, _| Ok(loop_status(&player::get_player_state())))
+        .set(|_, _, value: String| {
+            let repeat = match value.as_str() {
+                "None" => RepeatMode::Off,
+                "Track" => RepeatMode::One,
+                "Playlist" => RepeatMode::All,
+                _ => return Err(MethodErr::invalid_arg(&value)),
+            };
+            // The change is announced once the engine has applied it
+            player::player_set_repeat(repeat).map_err(failed)?;
+            Ok(None)
+        });
+    b.property("Shuffle")
+        .get(|_, _| Ok(player::get_player_state().shuffle))
+        .set(|_, _, shuffle: bool| {
+            player::player_set_shuffle(shuffle).map_err(failed)?;
+            Ok(None)
+        });
+    // Playback speed is fixed
+    b.property("Rate")
+        .emits_changed_const()
+        .get(|_, _| Ok(1.0))
+        .set(|_, _, _: f64| Ok(None));
+    b.property("MinimumRate")
+        .emits_changed_const()
+        .get(|_, _| Ok(1.0));
+    b.property("MaximumRate")
+        .emits_changed_const()
+        .get(|_, _| Ok(1.0));
+    b.property("Metadata")
+        .get(|_, _| Ok(metadata(&player::get_player_state())));
+    // System output volume (0.0 - 1.0), changes made elsewhere aren't signalled
+    b.property("Volume")
+        .emits_changed_false()
+        .get(|_, _| {
+            let level = system::get_volume().map_err(failed)?;
+            Ok(level as f64 / 100.0)
+        })
+        .set(|_, _, volume: f64| {
+            let level = (volume.clamp(0.0, 1.0) * 100.0).round() as u8;
+            system::set_volume(level).map_err(failed)?;
+            Ok(None)
+        });
+    // Read live, clients interpolate between `Seeked` signals
+    b.property("Position").emits_changed_false().get(|_, _| {
+        let state = player::get_player_state();
+        if state.status == PlaybackStatus::Stopped {
+            return Ok(0i64);
+        }
+        Ok(state.position_ms as i64 * 1000)
+    });
+    b.property("CanGoNext")
+        .get(|_, _| Ok(can_go_next(&player::get_player_state())));
+    b.property("CanGoPrevious")
+        .get(|_, _| Ok(can_go_previous(&player::get_player_state())));
+    b.property("CanPlay")
+        .get(|_, _| Ok(can_play(&player::get_player_state())));
+    b.property("CanPause")
+        .get(|_, _| Ok(can_pause(&player::get_player_state())));
+    b.property("CanSeek")
+        .get(|_, _| Ok(can_seek(&player::get_player_state())));
+    b.property("CanControl")
+        .emits_changed_const()
+        .get(|_, _| Ok(true));
+}
+
+// ============================================================================
+// SERVICE
+// ============================================================================
+
+/// Send `PropertiesChanged` for whatever differs between two states
+fn send_changes(connection: &Connection, old: &PlayerState, new: &PlayerState) {
+    let mut changed = PropMap::new();
+    let mut set = |name: &str, value: Box<dyn RefArg>| {
+        changed.insert(name.to_string(), Variant(value));
+    };
+
+    if old.status != new.status {
+        set("PlaybackStatus", Box::new(playback_status(new)));
+    }
+    if old.repeat != new.repeat {
+        set("LoopStatus", Box::new(loop_status(new)));
+    }
+    if old.shuffle != new.shuffle {
+        set("Shuffle", Box::new(new.shuffle));
+    }
+    if old.path != new.path || old.index != new.index || old.duration_ms != new.duration_ms {
+        set("Metadata", Box::new(metadata(new)));
+    }
+    let flags = [
+        ("CanGoNext", can_go_next as fn(&PlayerState) -> bool),
+        ("CanGoPrevious", can_go_previous),
+        ("CanPlay", can_play),
+        ("CanPause", can_pause),
+        ("CanSeek", can_seek),
+    ];
+    for (name, flag) in flags {
+        if flag(old) != flag(new) {
+            set(name, Box::new(flag(new)));
+        }
+    }
+
+    if changed.is_empty() {
+        return;
+    }
+    let signal = PropertiesPropertiesChanged {
+        interface_name: PLAYER_IFACE.to_string(),
+        changed_properties: changed,
+        invalidated_properties: Vec::new(),
+    };
+    let _ = connection.send(signal.to_emit_message(&dbus::Path::from(OBJECT_PATH)));
+}
+
+fn send_seeked(connection: &Connection, position_ms: u64) {
+    if let Ok(message) = Message::new_signal(OBJECT_PATH, PLAYER_IFACE, "Seeked") {
+        let _ = connection.send(message.append1(position_ms as i64 * 1000));
+    }
+}
+
+fn run_mpris_service() -> Result<(), String> {
+    // Subscribe before reading the state so no change is missed in between
+    let mut events = player::subscribe();
+    let mut last = player::get_player_state();
+
+    let connection =
+        Connection::new_session().map_err(|e| format!("No session bus: {}", e))?;
+    let reply = connection
+        .request_name(BUS_NAME, false, true, true)
+        .map_err(|e| format!("Failed to request {}: {}", BUS_NAME, e))?;
+    if reply != RequestNameReply::PrimaryOwner && reply != RequestNameReply::AlreadyOwner {
+        return Err(format!("{} is owned by another process", BUS_NAME));
+    }
+
+    let mut cr = Crossroads::new();
+    let root = cr.register(ROOT_IFACE, register_root);
+    let player_iface = cr.register(PLAYER_IFACE, register_player);
+    cr.insert(OBJECT_PATH, &[root, player_iface], ());
+    connection.start_receive(
+        MatchRule::new_method_call(),
+        Box::new(move |message, connection| {
+            let _ = cr.handle_message(message, connection);
+            true
+        }),
+    );
+    println!("📻 MPRIS service registered as {}", BUS_NAME);
+
+    loop {
+        connection
+            .process(PROCESS_INTERVAL)
+            .map_err(|e| format!("Lost session bus connection: {}", e))?;
+
+        loop {
+            match events.try_recv() {
+                Ok(PlayerEvent::State(state)) => {
+                    send_changes(&connection, &last, &state);
+                    last = state;
+                }
+                Ok(PlayerEvent::Seeked(position_ms)) => send_seeked(&connection, position_ms),
+                // Missed some updates, catch up from the current state
+                Err(TryRecvError::Lagged(_)) => {
+                    let state = player::get_player_state();
+                    send_changes(&connection, &last, &state);
+                    last = state;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Serve MPRIS on its own thread until the app exits. Without a session bus,
+/// or if the name is taken, the kiosk just runs without it.
+pub fn start_mpris_service() {
+    thread::spawn(|| {
+        if let Err(e) = run_mpris_service() {
+            eprintln!("❌ MPRIS service unavailable: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::PlayerCommand;
+    use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::time::Instant;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// A session bus of our own, killed when the test ends
+    struct PrivateBus(Child);
+
+    impl PrivateBus {
+        fn start() -> Self {
+            let mut child = Command::new("dbus-daemon")
+                .args(["--session", "--print-address", "--nofork"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("dbus-daemon not found");
+            let mut address = String::new();
+            BufReader::new(child.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            std::env::set_var("DBUS_SESSION_BUS_ADDRESS", address.trim());
+            Self(child)
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn wait_for_name(connection: &Connection) {
+        let bus = connection.with_proxy("org.freedesktop.DBus", "/org/freedesktop/DBus", TIMEOUT);
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let (owned,): (bool,) = bus
+                .method_call("org.freedesktop.DBus", "NameHasOwner", (BUS_NAME,))
+                .unwrap();
+            if owned {
+                return;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "{} was never registered",
+                BUS_NAME
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn serves_player_state_and_controls_on_a_private_bus() {
+        let _bus = PrivateBus::start();
+        let commands = player::fake_engine(PlayerState {
+            status: PlaybackStatus::Playing,
+            path: Some("/music/Some Song.flac".to_string()),
+            position_ms: 10_000,
+            duration_ms: 180_000,
+            queue: vec!["/music/Some Song.flac".to_string()],
+            index: Some(0),
+            ..Default::default()
+        });
+        start_mpris_service();
+
+        let connection = Connection::new_session().unwrap();
+        wait_for_name(&connection);
+        let proxy = connection.with_proxy(BUS_NAME, OBJECT_PATH, TIMEOUT);
+
+        let status: String = proxy.get(PLAYER_IFACE, "PlaybackStatus").unwrap();
+        assert_eq!(status, "Playing");
+        let metadata: PropMap = proxy.get(PLAYER_IFACE, "Metadata").unwrap();
+        assert_eq!(
+            dbus::arg::prop_cast::<String>(&metadata, "xesam:title").map(String::as_str),
+            Some("Some Song")
+        );
+        assert_eq!(
+            dbus::arg::prop_cast::<i64>(&metadata, "mpris:length"),
+            Some(&180_000_000)
+        );
+
+        let () = proxy.method_call(PLAYER_IFACE, "PlayPause", ()).unwrap();
+        assert_eq!(commands.recv_timeout(TIMEOUT), Ok(PlayerCommand::Pause));
+
+        let () = proxy
+            .method_call(PLAYER_IFACE, "Seek", (5_000_000i64,))
+            .unwrap();
+        assert_eq!(
+            commands.recv_timeout(TIMEOUT),
+            Ok(PlayerCommand::Seek(15_000))
+        );
+    }
+}
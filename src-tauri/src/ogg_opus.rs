@@ -0,0 +1,279 @@
+//! Ogg Opus encoding for voice recordings
+//!
+//! Transcodes a finished 16-bit WAV into an Ogg Opus file (RFC 7845) tuned for
+//! speech. Opus only runs at fixed rates, so input is resampled to 48 kHz with
+//! a linear resampler; more than two channels are downmixed to mono.
+
+use audiopus::{coder::Encoder, Application, Bitrate, Channels, SampleRate, Signal};
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::decode::Resampler;
+use crate::wav::WavReader;
+
+const OPUS_RATE: u32 = 48000;
+
+/// 20 ms frames
+const FRAME_SAMPLES: usize = 960;
+
+/// Recommended maximum packet size
+const MAX_PACKET: usize = 4000;
+
+/// Speech bitrates (bits/s); plenty for voice, ~1/40th of 44.1 kHz PCM
+const MONO_BITRATE: i32 = 24_000;
+const STEREO_BITRATE: i32 = 32_000;
+
+/// Audio per Ogg page (1 s), bounds how far a seek has to decode
+const PAGE_SAMPLES: u64 = OPUS_RATE as u64;
+
+/// Bytes searched from the end of the file for the last page
+const LAST_PAGE_SEARCH: u64 = 64 * 1024;
+
+/// Frames read from the WAV per iteration
+const READ_FRAMES: usize = 4096;
+
+const VENDOR: &str = "honeybee";
+
+fn opus_error(e: audiopus::Error) -> io::Error {
+    io::Error::other(format!("Opus encoder error: {}", e))
+}
+
+/// Converts interleaved i16 WAV frames into the channel layout Opus encodes
+fn convert_frames(input: &[i16], in_channels: usize, out_channels: usize, out: &mut Vec<f32>) {
+    out.clear();
+    if in_channels == out_channels {
+        out.extend(input.iter().map(|&s| s as f32 / 32768.0));
+    } else {
+        // Downmix to mono
+        for frame in input.chunks_exact(in_channels) {
+            let sum: f32 = frame.iter().map(|&s| s as f32 / 32768.0).sum();
+            out.push(sum / in_channels as f32);
+        }
+    }
+}
+
+fn opus_head(channels: u8, pre_skip: u16, input_rate: u32) -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1); // version
+    head.push(channels);
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&input_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // mapping family: mono / stereo
+    head
+}
+
+fn opus_tags() -> Vec<u8> {
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(VENDOR.len() as u32).to_le_bytes());
+    tags.extend_from_slice(VENDOR.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+    tags
+}
+
+/// Queues encoded packets so the final one can be flagged end-of-stream
+struct OggOpusWriter<W: Write> {
+    packets: PacketWriter<W>,
+    serial: u32,
+    held: Option<(Vec<u8>, u64)>,
+    /// Granule position the current page started at
+    page_start: u64,
+}
+
+impl<W: Write> OggOpusWriter<W> {
+    fn push(&mut self, packet: Vec<u8>, granule: u64) -> io::Result<()> {
+        if let Some((prev, prev_granule)) = self.held.replace((packet, granule)) {
+            let end_info = if prev_granule - self.page_start >= PAGE_SAMPLES {
+                self.page_start = prev_granule;
+                PacketWriteEndInfo::EndPage
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            self.packets
+                .write_packet(prev.into_boxed_slice(), self.serial, end_info, prev_granule)?;
+        }
+        Ok(())
+    }
+
+    /// Write the held packet as the end of the stream with the final granule position
+    fn finish(mut self, final_granule: u64) -> io::Result<W> {
+        let (last, _) = self.held.take().unwrap_or_default();
+        self.packets.write_packet(
+            last.into_boxed_slice(),
+            self.serial,
+            PacketWriteEndInfo::EndStream,
+            final_granule,
+        )?;
+        Ok(self.packets.into_inner())
+    }
+}
+
+/// Encode a 16-bit PCM WAV file as Ogg Opus
+pub fn encode_wav(wav_path: &Path, opus_path: &Path) -> io::Result<()> {
+    let mut reader = WavReader::open(wav_path)?;
+    let info = reader.info().clone();
+    let in_channels = info.channels as usize;
+    if in_channels == 0 || info.sample_rate == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid WAV format"));
+    }
+    let out_channels = if in_channels == 2 { 2 } else { 1 };
+
+    let (opus_channels, bitrate) = if out_channels == 2 {
+        (Channels::Stereo, STEREO_BITRATE)
+    } else {
+        (Channels::Mono, MONO_BITRATE)
+    };
+    let mut encoder =
+        Encoder::new(SampleRate::Hz48000, opus_channels, Application::Voip).map_err(opus_error)?;
+    encoder
+        .set_bitrate(Bitrate::BitsPerSecond(bitrate))
+        .map_err(opus_error)?;
+    encoder.set_signal(Signal::Voice).map_err(opus_error)?;
+    let pre_skip = encoder.lookahead().map_err(opus_error)? as u64;
+
+    // Serial only has to be unique among streams in the file
+    let serial = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(1);
+
+    let file = BufWriter::new(File::create(opus_path)?);
+    let mut packets = PacketWriter::new(file);
+    // Both header packets go on their own pages
+    packets.write_packet(
+        opus_head(out_channels as u8, pre_skip as u16, info.sample_rate).into_boxed_slice(),
+        serial,
+        PacketWriteEndInfo::EndPage,
+        0,
+    )?;
+    packets.write_packet(opus_tags().into_boxed_slice(), serial, PacketWriteEndInfo::EndPage, 0)?;
+    let mut writer = OggOpusWriter {
+        packets,
+        serial,
+        held: None,
+        page_start: 0,
+    };
+
+    let mut resampler = Resampler::new(info.sample_rate, OPUS_RATE, out_channels);
+    let frame_len = FRAME_SAMPLES * out_channels;
+    let mut wav_frames = Vec::with_capacity(READ_FRAMES * in_channels);
+    let mut converted = Vec::with_capacity(READ_FRAMES * out_channels);
+    let mut pcm: Vec<f32> = Vec::new();
+    let mut packet = vec![0u8; MAX_PACKET];
+    let mut input_samples = 0u64; // per channel, at 48 kHz
+    let mut encoded_samples = 0u64;
+
+    let mut encode_ready = |pcm: &mut Vec<f32>, writer: &mut OggOpusWriter<_>| -> io::Result<()> {
+        let mut offset = 0;
+        while pcm.len() - offset >= frame_len {
+            let len = encoder
+                .encode_float(&pcm[offset..offset + frame_len], &mut packet)
+                .map_err(opus_error)?;
+            encoded_samples += FRAME_SAMPLES as u64;
+            writer.push(packet[..len].to_vec(), encoded_samples)?;
+            offset += frame_len;
+        }
+        pcm.drain(..offset);
+        Ok(())
+    };
+
+    loop {
+        let frames = reader.read_frames(&mut wav_frames, READ_FRAMES)?;
+        if frames == 0 {
+            break;
+        }
+        convert_frames(&wav_frames, in_channels, out_channels, &mut converted);
+        let before = pcm.len();
+        resampler.process(&converted, &mut pcm);
+        input_samples += ((pcm.len() - before) / out_channels) as u64;
+        encode_ready(&mut pcm, &mut writer)?;
+    }
+
+    let before = pcm.len();
+    resampler.flush(&mut pcm);
+    input_samples += ((pcm.len() - before) / out_channels) as u64;
+
+    // Pad with silence so the encoder's lookahead is flushed, then to a whole frame
+    pcm.resize(pcm.len() + pre_skip as usize * out_channels, 0.0);
+    let remainder = pcm.len() % frame_len;
+    if remainder != 0 {
+        pcm.resize(pcm.len() + frame_len - remainder, 0.0);
+    }
+    encode_ready(&mut pcm, &mut writer)?;
+
+    // The final granule position trims the padding on decode
+    let mut file = writer.finish(pre_skip + input_samples)?;
+    file.flush()?;
+    file.get_ref().sync_all()?;
+    Ok(())
+}
+
+/// Header fields of an Ogg Opus file
+#[derive(Debug, Clone)]
+pub struct OpusInfo {
+    pub channels: u16,
+    /// Samples (at 48 kHz) to drop from the start of the decoded stream
+    pub pre_skip: u16,
+    /// Rate of the original input (decoders always output 48 kHz)
+    pub input_sample_rate: u32,
+    pub duration_ms: u64,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Read OpusHead from the first page and the duration from the last page's granule position
+pub fn read_info(path: &Path) -> io::Result<OpusInfo> {
+    let mut file = File::open(path)?;
+
+    // First page holds only OpusHead: 27-byte page header, segment table, packet
+    let mut first = [0u8; 27 + 255 + 19];
+    let n = file.read(&mut first)?;
+    if n < 27 || &first[0..4] != b"OggS" {
+        return Err(invalid("Not an Ogg file"));
+    }
+    let head = 27 + first[26] as usize;
+    if n < head + 19 || &first[head..head + 8] != b"OpusHead" {
+        return Err(invalid("Not an Opus stream"));
+    }
+    let channels = first[head + 9] as u16;
+    let pre_skip = u16::from_le_bytes([first[head + 10], first[head + 11]]);
+    let input_sample_rate = u32::from_le_bytes([
+        first[head + 12],
+        first[head + 13],
+        first[head + 14],
+        first[head + 15],
+    ]);
+
+    // The granule position of the last page is the total sample count at 48 kHz
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(LAST_PAGE_SEARCH);
+    file.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::with_capacity((len - start) as usize);
+    file.read_to_end(&mut tail)?;
+    let granule = tail
+        .windows(4)
+        .rposition(|w| w == b"OggS")
+        .filter(|&pos| pos + 14 <= tail.len())
+        .map(|pos| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&tail[pos + 6..pos + 14]);
+            u64::from_le_bytes(bytes)
+        })
+        .unwrap_or(0);
+
+    Ok(OpusInfo {
+        channels,
+        pre_skip,
+        input_sample_rate,
+        duration_ms: granule.saturating_sub(pre_skip as u64) * 1000 / OPUS_RATE as u64,
+    })
+}
@@ -0,0 +1,1069 @@
+//! Audio Playback
+//!
+//! Native playback engine: files are decoded in Rust and played through cpal
+//! on the default output device, which is the sink `set_volume` controls
+//! (ALSA's default PCM is routed to the PipeWire/PulseAudio default sink).
+//! Nothing is loaded into the webview, and playback lives on its own thread so
+//! it keeps going while the user navigates the kiosk.
+//!
+//! Per track a decoder thread resamples to the device rate and fills a ring
+//! buffer that the output callback drains. The engine thread owns the queue,
+//! takes commands over a channel, and emits `player-state` on every change and
+//! `player-position` while playing. State changes and seeks are also broadcast
+//! to Rust subscribers (the MPRIS service).
+//!
+//! The queue, play order, position and shuffle / repeat modes are saved to
+//! ~/.config/honeybee/player.json, so the queue survives a restart and resume
+//! picks up where playback left off.
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, Sample, SampleFormat, SizedSample,
+};
+use parking_lot::Mutex;
+use ringbuf::{
+    traits::{Consumer, Observer, Producer, Split},
+    HeapCons, HeapProd, HeapRb,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+use crate::decode::{AudioDecoder, Resampler};
+use crate::fsutil::write_atomic;
+
+/// How often `player-position` is sent while playing
+const POSITION_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Decoded audio buffered ahead of the output
+const RING_BUFFER_MS: usize = 500;
+
+/// Decoder thread back-off while the ring buffer is full
+const FILL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// "Previous" restarts the track instead once this far in
+const RESTART_THRESHOLD_MS: u64 = 3000;
+
+/// How often the position is saved while playing
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+lazy_static::lazy_static! {
+    static ref PLAYER_COMMANDS: Mutex<Option<mpsc::Sender<PlayerCommand>>> = Mutex::new(None);
+    static ref PLAYER_STATE: Mutex<PlayerState> = Mutex::new(PlayerState::default());
+    static ref PLAYER_EVENTS: broadcast::Sender<PlayerEvent> = broadcast::channel(16).0;
+}
+
+/// Playback gain (f32 bits), applied on top of the system volume
+static PLAYER_GAIN: AtomicU32 = AtomicU32::new(1.0f32.to_bits());
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackStatus {
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+/// What happens when a track ends
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    /// Stop after the last track
+    #[default]
+    Off,
+    /// Start the queue over
+    All,
+    /// Play the current track again
+    One,
+}
+
+/// Snapshot of the player, sent with `player-state`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlayerState {
+    pub status: PlaybackStatus,
+    /// Current track, kept after the queue finishes
+    pub path: Option<String>,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    /// In the order tracks were added, shuffling doesn't reorder it
+    pub queue: Vec<String>,
+    pub index: Option<usize>,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+/// Sent with `player-position`
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerPosition {
+    pub path: String,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// Broadcast to Rust subscribers
+#[derive(Debug, Clone)]
+pub enum PlayerEvent {
+    State(PlayerState),
+    /// Jumped to a position (ms) within the current track
+    Seeked(u64),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum PlayerCommand {
+    Play { queue: Vec<PathBuf>, index: usize },
+    Enqueue(Vec<PathBuf>),
+    Pause,
+    Resume,
+    Stop,
+    Seek(u64),
+    Next,
+    Previous,
+    /// Jump to a queue entry
+    PlayIndex(usize),
+    Remove(usize),
+    SetShuffle(bool),
+    SetRepeat(RepeatMode),
+    /// Reopen the stream on the current default output
+    ReopenOutput,
+}
+
+fn send(command: PlayerCommand) -> Result<(), String> {
+    PLAYER_COMMANDS
+        .lock()
+        .as_ref()
+        .ok_or("Player is not running")?
+        .send(command)
+        .map_err(|_| "Player is not running".to_string())
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+// ============================================================================
+// PLAYBACK (one track)
+// ============================================================================
+
+/// State shared between the engine, the decoder thread and the output callback
+#[derive(Default)]
+struct PlaybackShared {
+    paused: AtomicBool,
+    stop: AtomicBool,
+    /// Track position at the last seek
+    base_ms: AtomicU64,
+    /// Output frames played since the last seek
+    played_frames: AtomicU64,
+    /// Pending seek for the decoder thread
+    seek_to: Mutex<Option<u64>>,
+    /// Decoder reached the end and everything is in the ring buffer
+    decode_done: AtomicBool,
+    /// Ring buffer ran dry after the end, the track is over
+    drained: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+/// Output callback side: drains the ring buffer into the device
+struct PlaybackSink {
+    /// Locked by the decoder thread only to flush on seek
+    consumer: Arc<Mutex<HeapCons<f32>>>,
+    shared: Arc<PlaybackShared>,
+    channels: usize,
+    /// Gain applied to the last frame, ramped towards `PLAYER_GAIN` per buffer
+    gain: f32,
+}
+
+impl PlaybackSink {
+    fn fill<T>(&mut self, data: &mut [T])
+    where
+        T: Sample + FromSample<f32>,
+    {
+        let mut written = 0;
+        let target = gain();
+        let step = (target - self.gain) * self.channels as f32 / data.len().max(1) as f32;
+        // try_lock: never block the audio thread, a seek in progress plays silence
+        if !self.shared.paused.load(Ordering::Relaxed) {
+            if let Some(mut consumer) = self.consumer.try_lock() {
+                for (out, sample) in data.iter_mut().zip(consumer.pop_iter()) {
+                    if written % self.channels == 0 {
+                        self.gain += step;
+                    }
+                    *out = T::from_sample(sample * self.gain);
+                    written += 1;
+                }
+                if consumer.is_empty() && self.shared.decode_done.load(Ordering::Acquire) {
+                    self.shared.drained.store(true, Ordering::Release);
+                }
+            }
+        }
+
+        for out in &mut data[written..] {
+            *out = T::EQUILIBRIUM;
+        }
+        self.gain = target;
+        self.shared
+            .played_frames
+            .fetch_add((written / self.channels) as u64, Ordering::Relaxed);
+    }
+}
+
+fn build_playback_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut sink: PlaybackSink,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let shared = sink.shared.clone();
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| sink.fill(data),
+            move |err| {
+                eprintln!("Playback stream error: {}", err);
+                if let cpal::StreamError::DeviceNotAvailable = err {
+                    *shared.error.lock() = Some("Audio output device disconnected".to_string());
+                }
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to build stream: {}", e))
+}
+
+/// Maps interleaved frames onto the output's channel count
+pub(crate) fn map_channels(
+    input: &[f32],
+    in_channels: usize,
+    out_channels: usize,
+    out: &mut Vec<f32>,
+) {
+    out.clear();
+    if in_channels == out_channels {
+        out.extend_from_slice(input);
+        return;
+    }
+    for frame in input.chunks_exact(in_channels) {
+        if out_channels == 1 {
+            out.push(frame.iter().sum::<f32>() / in_channels as f32);
+        } else if in_channels == 1 {
+            out.extend(std::iter::repeat_n(frame[0], out_channels));
+        } else {
+            out.extend((0..out_channels).map(|c| frame.get(c).copied().unwrap_or(0.0)));
+        }
+    }
+}
+
+/// Decoder thread: decodes, converts to the output format and keeps the ring
+/// buffer full until stopped
+fn spawn_decoder(
+    mut decoder: AudioDecoder,
+    mut producer: HeapProd<f32>,
+    consumer: Arc<Mutex<HeapCons<f32>>>,
+    shared: Arc<PlaybackShared>,
+    output_rate: u32,
+    output_channels: usize,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let in_channels = decoder.channels().max(1) as usize;
+        let input_rate = decoder.sample_rate();
+        let mut resampler = Resampler::new(input_rate, output_rate, output_channels);
+        let mut block = Vec::new();
+        let mut mapped = Vec::new();
+        let mut pending = Vec::new();
+        let mut offset = 0;
+        let mut at_end = false;
+
+        while !shared.stop.load(Ordering::Relaxed) {
+            let seek = shared.seek_to.lock().take();
+            if let Some(position_ms) = seek {
+                // Drop what was buffered before the seek
+                {
+                    let mut consumer = consumer.lock();
+                    shared.decode_done.store(false, Ordering::Release);
+                    shared.drained.store(false, Ordering::Release);
+                    consumer.clear();
+                    shared.base_ms.store(position_ms, Ordering::Relaxed);
+                    shared.played_frames.store(0, Ordering::Relaxed);
+                }
+                if let Err(e) = decoder.seek(position_ms) {
+                    eprintln!("Playback seek failed: {}", e);
+                }
+                resampler = Resampler::new(input_rate, output_rate, output_channels);
+                pending.clear();
+                offset = 0;
+                at_end = false;
+            }
+
+            if offset < pending.len() {
+                offset += producer.push_slice(&pending[offset..]);
+                if offset < pending.len() {
+                    thread::sleep(FILL_INTERVAL);
+                }
+                continue;
+            }
+            if at_end {
+                // Wait around for a seek back
+                shared.decode_done.store(true, Ordering::Release);
+                thread::sleep(FILL_INTERVAL);
+                continue;
+            }
+
+            pending.clear();
+            offset = 0;
+            match decoder.next_block(&mut block) {
+                Ok(true) => {
+                    map_channels(&block, in_channels, output_channels, &mut mapped);
+                    resampler.process(&mapped, &mut pending);
+                }
+                Ok(false) => {
+                    resampler.flush(&mut pending);
+                    at_end = true;
+                }
+                Err(e) => {
+                    *shared.error.lock() = Some(e);
+                    at_end = true;
+                }
+            }
+        }
+    })
+}
+
+/// One track playing on the output device, stopped on drop
+struct Playback {
+    path: PathBuf,
+    duration_ms: u64,
+    output_rate: u32,
+    shared: Arc<PlaybackShared>,
+    stream: Option<cpal::Stream>,
+    decoder_thread: Option<JoinHandle<()>>,
+}
+
+impl Playback {
+    fn start(path: &Path) -> Result<Self, String> {
+        let decoder = AudioDecoder::open(path)?;
+        let duration_ms = decoder
+            .total_frames()
+            .map(|frames| frames * 1000 / decoder.sample_rate().max(1) as u64)
+            .unwrap_or(0);
+
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or("No audio output device found")?;
+        let config = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to get output config: {}", e))?;
+        let output_rate = config.sample_rate().0;
+        let output_channels = config.channels().max(1) as usize;
+
+        let capacity = output_rate as usize * output_channels * RING_BUFFER_MS / 1000;
+        let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+        let consumer = Arc::new(Mutex::new(consumer));
+        let shared = Arc::new(PlaybackShared::default());
+        let sink = PlaybackSink {
+            consumer: consumer.clone(),
+            shared: shared.clone(),
+            channels: output_channels,
+            gain: gain(),
+        };
+
+        let sample_format = config.sample_format();
+        let stream_config: cpal::StreamConfig = config.into();
+        let stream = match sample_format {
+            SampleFormat::I8 => build_playback_stream::<i8>(&device, &stream_config, sink),
+            SampleFormat::I16 => build_playback_stream::<i16>(&device, &stream_config, sink),
+            SampleFormat::I32 => build_playback_stream::<i32>(&device, &stream_config, sink),
+            SampleFormat::I64 => build_playback_stream::<i64>(&device, &stream_config, sink),
+            SampleFormat::U8 => build_playback_stream::<u8>(&device, &stream_config, sink),
+            SampleFormat::U16 => build_playback_stream::<u16>(&device, &stream_config, sink),
+            SampleFormat::U32 => build_playback_stream::<u32>(&device, &stream_config, sink),
+            SampleFormat::U64 => build_playback_stream::<u64>(&device, &stream_config, sink),
+            SampleFormat::F32 => build_playback_stream::<f32>(&device, &stream_config, sink),
+            SampleFormat::F64 => build_playback_stream::<f64>(&device, &stream_config, sink),
+            other => Err(format!("Unsupported sample format: {:?}", other)),
+        }?;
+
+        let decoder_thread = spawn_decoder(
+            decoder,
+            producer,
+            consumer,
+            shared.clone(),
+            output_rate,
+            output_channels,
+        );
+        // From here on Drop stops the decoder thread
+        let playback = Self {
+            path: path.to_path_buf(),
+            duration_ms,
+            output_rate,
+            shared,
+            stream: Some(stream),
+            decoder_thread: Some(decoder_thread),
+        };
+        if let Some(stream) = &playback.stream {
+            stream
+                .play()
+                .map_err(|e| format!("Failed to start stream: {}", e))?;
+        }
+        Ok(playback)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.shared.paused.load(Ordering::Relaxed)
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.shared.paused.store(paused, Ordering::Relaxed);
+        // The callback plays silence while paused, releasing the device is a bonus
+        if let Some(stream) = &self.stream {
+            let result = if paused { stream.pause() } else { stream.play() };
+            if let Err(e) = result {
+                eprintln!("Failed to {} playback stream: {}", if paused { "pause" } else { "resume" }, e);
+            }
+        }
+    }
+
+    fn position_ms(&self) -> u64 {
+        let played = self.shared.played_frames.load(Ordering::Relaxed);
+        let position = self.shared.base_ms.load(Ordering::Relaxed)
+            + played * 1000 / self.output_rate.max(1) as u64;
+        if self.duration_ms > 0 {
+            position.min(self.duration_ms)
+        } else {
+            position
+        }
+    }
+
+    fn seek(&self, position_ms: u64) {
+        let position_ms = if self.duration_ms > 0 {
+            position_ms.min(self.duration_ms)
+        } else {
+            position_ms
+        };
+        // Report the new position right away, the decoder thread catches up
+        self.shared.base_ms.store(position_ms, Ordering::Relaxed);
+        self.shared.played_frames.store(0, Ordering::Relaxed);
+        self.shared.drained.store(false, Ordering::Release);
+        *self.shared.seek_to.lock() = Some(position_ms);
+    }
+
+    fn finished(&self) -> bool {
+        self.shared.drained.load(Ordering::Acquire)
+    }
+
+    fn take_error(&self) -> Option<String> {
+        self.shared.error.lock().take()
+    }
+}
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        self.stream.take();
+        if let Some(handle) = self.decoder_thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// ============================================================================
+// ENGINE (queue)
+// ============================================================================
+
+/// Queue saved across restarts (~/.config/honeybee/player.json)
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedQueue {
+    queue: Vec<String>,
+    order: Vec<usize>,
+    cursor: Option<usize>,
+    position_ms: u64,
+    shuffle: bool,
+    repeat: RepeatMode,
+}
+
+fn saved_queue_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/player.json"))
+        .unwrap_or_else(|| PathBuf::from("player.json"))
+}
+
+fn load_saved_queue() -> SavedQueue {
+    fs::read_to_string(saved_queue_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_queue(saved: &SavedQueue) -> Result<(), String> {
+    let path = saved_queue_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string(saved).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to save play queue: {}", e))
+}
+
+/// Fisher-Yates with a time-seeded xorshift, plenty for a play order
+fn shuffle(items: &mut [usize]) {
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+        | 1;
+    for i in (1..items.len()).rev() {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        items.swap(i, (seed % (i as u64 + 1)) as usize);
+    }
+}
+
+struct Engine {
+    app: AppHandle,
+    queue: Vec<PathBuf>,
+    /// Play order over `queue`, shuffled or 0..n
+    order: Vec<usize>,
+    /// Position of the current track in `order`
+    cursor: Option<usize>,
+    shuffle: bool,
+    repeat: RepeatMode,
+    playback: Option<Playback>,
+    /// Where the restored track picks up on the first resume
+    resume_position: Option<u64>,
+    last_position: Instant,
+    last_save: Instant,
+}
+
+impl Engine {
+    fn new(app: AppHandle) -> Self {
+        Self {
+            app,
+            queue: Vec::new(),
+            order: Vec::new(),
+            cursor: None,
+            shuffle: false,
+            repeat: RepeatMode::Off,
+            playback: None,
+            resume_position: None,
+            last_position: Instant::now(),
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Pick up the queue from the last run, stopped
+    fn restore(&mut self) {
+        let saved = load_saved_queue();
+        self.queue = saved.queue.into_iter().map(PathBuf::from).collect();
+        self.shuffle = saved.shuffle;
+        self.repeat = saved.repeat;
+
+        let mut sorted = saved.order.clone();
+        sorted.sort_unstable();
+        if sorted.iter().copied().eq(0..self.queue.len()) {
+            self.order = saved.order;
+            self.cursor = saved.cursor.filter(|&c| c < self.order.len());
+        } else {
+            self.order = (0..self.queue.len()).collect();
+            self.cursor = None;
+        }
+        if self.cursor.is_some() && saved.position_ms > 0 {
+            self.resume_position = Some(saved.position_ms);
+        }
+    }
+
+    fn save(&mut self) {
+        self.last_save = Instant::now();
+        let saved = SavedQueue {
+            queue: self.queue.iter().map(|p| path_string(p)).collect(),
+            order: self.order.clone(),
+            cursor: self.cursor,
+            position_ms: self.position_ms(),
+            shuffle: self.shuffle,
+            repeat: self.repeat,
+        };
+        if let Err(e) = save_queue(&saved) {
+            eprintln!("{}", e);
+        }
+    }
+
+    /// Queue index of the current track
+    fn index(&self) -> Option<usize> {
+        self.cursor.and_then(|c| self.order.get(c).copied())
+    }
+
+    fn position_ms(&self) -> u64 {
+        match &self.playback {
+            Some(playback) => playback.position_ms(),
+            None => self.resume_position.unwrap_or(0),
+        }
+    }
+
+    /// Rebuild the play order, keeping queue entry `current` as the current track
+    fn reorder(&mut self, current: Option<usize>) {
+        if self.shuffle {
+            let mut order: Vec<usize> = (0..self.queue.len())
+                .filter(|&i| Some(i) != current)
+                .collect();
+            shuffle(&mut order);
+            if let Some(current) = current {
+                order.insert(0, current);
+            }
+            self.cursor = current.map(|_| 0);
+            self.order = order;
+        } else {
+            self.order = (0..self.queue.len()).collect();
+            self.cursor = current;
+        }
+    }
+
+    fn state(&self) -> PlayerState {
+        let status = match &self.playback {
+            None => PlaybackStatus::Stopped,
+            Some(p) if p.is_paused() => PlaybackStatus::Paused,
+            Some(_) => PlaybackStatus::Playing,
+        };
+        PlayerState {
+            status,
+            path: self
+                .index()
+                .and_then(|i| self.queue.get(i))
+                .map(|p| path_string(p)),
+            position_ms: self.position_ms(),
+            duration_ms: self.playback.as_ref().map_or(0, |p| p.duration_ms),
+            queue: self.queue.iter().map(|p| path_string(p)).collect(),
+            index: self.index(),
+            shuffle: self.shuffle,
+            repeat: self.repeat,
+        }
+    }
+
+    fn emit_state(&mut self) {
+        let state = self.state();
+        *PLAYER_STATE.lock() = state.clone();
+        // No subscribers is fine
+        let _ = PLAYER_EVENTS.send(PlayerEvent::State(state.clone()));
+        let _ = self.app.emit("player-state", state);
+        self.save();
+    }
+
+    fn emit_seeked(&self, position_ms: u64) {
+        let _ = PLAYER_EVENTS.send(PlayerEvent::Seeked(position_ms));
+    }
+
+    fn emit_error(&self, message: String) {
+        eprintln!("Playback error: {}", message);
+        let _ = self.app.emit("player-error", message);
+    }
+
+    /// Start the first playable track from `cursor` on in play order
+    fn start_at(&mut self, cursor: usize) {
+        self.playback = None;
+        self.resume_position = None;
+        for c in cursor..self.order.len() {
+            self.cursor = Some(c);
+            let path = self.queue[self.order[c]].clone();
+            match Playback::start(&path) {
+                Ok(playback) => {
+                    println!("🎵 Playing {}", path.display());
+                    self.playback = Some(playback);
+                    break;
+                }
+                Err(e) => self.emit_error(format!("{}: {}", path.display(), e)),
+            }
+        }
+        self.last_position = Instant::now();
+        self.emit_state();
+    }
+
+    /// Position in play order after the current track, wrapping with repeat all
+    fn next_cursor(&mut self) -> Option<usize> {
+        let next = self.cursor.map_or(0, |c| c + 1);
+        if next < self.order.len() {
+            return Some(next);
+        }
+        if self.repeat == RepeatMode::All && !self.queue.is_empty() {
+            // New round, new order
+            if self.shuffle {
+                self.reorder(None);
+            }
+            return Some(0);
+        }
+        None
+    }
+
+    /// Current track ended
+    fn track_finished(&mut self) {
+        if self.repeat == RepeatMode::One {
+            if let Some(cursor) = self.cursor {
+                return self.start_at(cursor);
+            }
+        }
+        match self.next_cursor() {
+            Some(next) => self.start_at(next),
+            None => {
+                // End of the queue
+                self.playback = None;
+                self.emit_state();
+            }
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        if index >= self.queue.len() {
+            return;
+        }
+        let current = self.index() == Some(index);
+        let was_playing = current && self.playback.is_some();
+        let removed_at = self.order.iter().position(|&i| i == index);
+
+        self.queue.remove(index);
+        self.order.retain(|&i| i != index);
+        for i in &mut self.order {
+            if *i > index {
+                *i -= 1;
+            }
+        }
+        if let (Some(cursor), Some(removed_at)) = (self.cursor, removed_at) {
+            if removed_at < cursor {
+                self.cursor = Some(cursor - 1);
+            }
+        }
+
+        if current {
+            // The next track slides into the current slot
+            self.playback = None;
+            self.resume_position = None;
+            match self.cursor {
+                Some(cursor) if cursor < self.order.len() && was_playing => {
+                    return self.start_at(cursor);
+                }
+                Some(cursor) if cursor >= self.order.len() => {
+                    self.cursor = self.order.len().checked_sub(1);
+                }
+                _ => {}
+            }
+        }
+        self.emit_state();
+    }
+
+    fn handle(&mut self, command: PlayerCommand) {
+        match command {
+            PlayerCommand::Play { queue, index } => {
+                let index = index.min(queue.len().saturating_sub(1));
+                self.queue = queue;
+                self.reorder(Some(index));
+                if let Some(cursor) = self.cursor {
+                    self.start_at(cursor);
+                }
+            }
+            PlayerCommand::Enqueue(paths) => {
+                let start = self.queue.len();
+                self.queue.extend(paths);
+                self.order.extend(start..self.queue.len());
+                self.emit_state();
+            }
+            PlayerCommand::Pause => {
+                if let Some(playback) = &self.playback {
+                    playback.set_paused(true);
+                    self.emit_state();
+                }
+            }
+            PlayerCommand::Resume => match (&self.playback, self.cursor) {
+                (Some(playback), _) => {
+                    playback.set_paused(false);
+                    self.emit_state();
+                }
+                // Stopped: start the current track, where a restored queue left off
+                (None, Some(cursor)) => {
+                    let position = self.resume_position.take();
+                    self.start_at(cursor);
+                    if let (Some(playback), Some(position)) = (&self.playback, position) {
+                        playback.seek(position);
+                        self.emit_state();
+                    }
+                }
+                (None, None) if !self.queue.is_empty() => self.start_at(0),
+                (None, None) => {}
+            },
+            PlayerCommand::Stop => {
+                if self.playback.take().is_some() {
+                    self.emit_state();
+                }
+            }
+            PlayerCommand::Seek(position_ms) => {
+                if let Some(playback) = &self.playback {
+                    playback.seek(position_ms);
+                    self.emit_state();
+                    self.emit_seeked(position_ms);
+                }
+            }
+            PlayerCommand::Next => {
+                if self.cursor.is_some() {
+                    if let Some(next) = self.next_cursor() {
+                        self.start_at(next);
+                    }
+                }
+            }
+            PlayerCommand::Previous => {
+                let Some(cursor) = self.cursor else {
+                    return;
+                };
+                let restart = self.position_ms() >= RESTART_THRESHOLD_MS;
+                let previous = match cursor {
+                    0 if self.repeat == RepeatMode::All => self.order.len().checked_sub(1),
+                    0 => None,
+                    c => Some(c - 1),
+                };
+                match (&self.playback, previous) {
+                    (Some(playback), _) if restart => {
+                        playback.seek(0);
+                        self.emit_state();
+                        self.emit_seeked(0);
+                    }
+                    (_, Some(previous)) => self.start_at(previous),
+                    (Some(playback), None) => {
+                        playback.seek(0);
+                        self.emit_state();
+                        self.emit_seeked(0);
+                    }
+                    (None, None) => self.start_at(cursor),
+                }
+            }
+            PlayerCommand::PlayIndex(index) => {
+                if let Some(cursor) = self.order.iter().position(|&i| i == index) {
+                    self.start_at(cursor);
+                }
+            }
+            PlayerCommand::Remove(index) => self.remove(index),
+            PlayerCommand::SetShuffle(shuffle) => {
+                if self.shuffle != shuffle {
+                    self.shuffle = shuffle;
+                    self.reorder(self.index());
+                    self.emit_state();
+                }
+            }
+            PlayerCommand::SetRepeat(repeat) => {
+                self.repeat = repeat;
+                self.emit_state();
+            }
+            PlayerCommand::ReopenOutput => {
+                let Some(playback) = self.playback.take() else {
+                    return;
+                };
+                let position = playback.position_ms();
+                let paused = playback.is_paused();
+                let path = playback.path.clone();
+                // Release the old device before opening the new one
+                drop(playback);
+                match Playback::start(&path) {
+                    Ok(playback) => {
+                        playback.set_paused(paused);
+                        playback.seek(position);
+                        self.playback = Some(playback);
+                    }
+                    Err(e) => self.emit_error(format!("{}: {}", path.display(), e)),
+                }
+                self.emit_state();
+            }
+        }
+    }
+
+    /// Check on the current track between commands
+    fn tick(&mut self) {
+        let Some(playback) = &self.playback else {
+            return;
+        };
+
+        if let Some(error) = playback.take_error() {
+            let message = format!("{}: {}", playback.path.display(), error);
+            self.emit_error(message);
+            // Don't loop on a broken track with repeat one
+            return match self.next_cursor() {
+                Some(next) => self.start_at(next),
+                None => {
+                    self.playback = None;
+                    self.emit_state();
+                }
+            };
+        }
+        if playback.finished() {
+            return self.track_finished();
+        }
+
+        if !playback.is_paused() && self.last_position.elapsed() >= POSITION_INTERVAL {
+            self.last_position = Instant::now();
+            let position = PlayerPosition {
+                path: path_string(&playback.path),
+                position_ms: playback.position_ms(),
+                duration_ms: playback.duration_ms,
+            };
+            PLAYER_STATE.lock().position_ms = position.position_ms;
+            let _ = self.app.emit("player-position", position);
+
+            if self.last_save.elapsed() >= SAVE_INTERVAL {
+                self.save();
+            }
+        }
+    }
+}
+
+/// Start the playback engine thread
+pub fn start_player(app: AppHandle) {
+    let (tx, rx) = mpsc::channel();
+    *PLAYER_COMMANDS.lock() = Some(tx);
+
+    thread::spawn(move || {
+        let mut engine = Engine::new(app);
+        engine.restore();
+        engine.emit_state();
+
+        loop {
+            match rx.recv_timeout(POSITION_INTERVAL) {
+                Ok(command) => engine.handle(command),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            engine.tick();
+        }
+    });
+}
+
+fn playable_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    Ok(path)
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+/// Replace the queue and start playing `paths[index]`
+#[tauri::command]
+pub fn player_play(paths: Vec<String>, index: Option<usize>) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("Nothing to play".to_string());
+    }
+    let queue = paths
+        .iter()
+        .map(|p| playable_path(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    send(PlayerCommand::Play {
+        queue,
+        index: index.unwrap_or(0),
+    })
+}
+
+/// Add tracks to the end of the queue
+#[tauri::command]
+pub fn player_enqueue(paths: Vec<String>) -> Result<(), String> {
+    let paths = paths
+        .iter()
+        .map(|p| playable_path(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    send(PlayerCommand::Enqueue(paths))
+}
+
+#[tauri::command]
+pub fn player_pause() -> Result<(), String> {
+    send(PlayerCommand::Pause)
+}
+
+/// Resume, or restart the current track if stopped
+#[tauri::command]
+pub fn player_resume() -> Result<(), String> {
+    send(PlayerCommand::Resume)
+}
+
+#[tauri::command]
+pub fn player_stop() -> Result<(), String> {
+    send(PlayerCommand::Stop)
+}
+
+#[tauri::command]
+pub fn player_seek(position_ms: u64) -> Result<(), String> {
+    send(PlayerCommand::Seek(position_ms))
+}
+
+#[tauri::command]
+pub fn player_next() -> Result<(), String> {
+    send(PlayerCommand::Next)
+}
+
+/// Previous track, or back to the start if a few seconds in
+#[tauri::command]
+pub fn player_previous() -> Result<(), String> {
+    send(PlayerCommand::Previous)
+}
+
+/// Play the queue entry at `index`
+#[tauri::command]
+pub fn player_play_index(index: usize) -> Result<(), String> {
+    send(PlayerCommand::PlayIndex(index))
+}
+
+/// Remove the queue entry at `index`
+#[tauri::command]
+pub fn player_remove(index: usize) -> Result<(), String> {
+    send(PlayerCommand::Remove(index))
+}
+
+#[tauri::command]
+pub fn player_set_shuffle(shuffle: bool) -> Result<(), String> {
+    send(PlayerCommand::SetShuffle(shuffle))
+}
+
+#[tauri::command]
+pub fn player_set_repeat(repeat: RepeatMode) -> Result<(), String> {
+    send(PlayerCommand::SetRepeat(repeat))
+}
+
+#[tauri::command]
+pub fn get_player_state() -> PlayerState {
+    PLAYER_STATE.lock().clone()
+}
+
+/// Scale playback (0.0 - 1.0) without touching the system volume, e.g. to
+/// duck music. Ramped over one output buffer to avoid clicks.
+pub fn set_gain(gain: f32) {
+    PLAYER_GAIN.store(gain.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+}
+
+pub fn gain() -> f32 {
+    f32::from_bits(PLAYER_GAIN.load(Ordering::Relaxed))
+}
+
+/// Continue the current track on the (new) default output device
+pub fn reopen_output() -> Result<(), String> {
+    send(PlayerCommand::ReopenOutput)
+}
+
+/// Receive state changes and seeks from the engine
+pub fn subscribe() -> broadcast::Receiver<PlayerEvent> {
+    PLAYER_EVENTS.subscribe()
+}
+
+/// Stand in for the engine thread: commands go to the returned receiver and
+/// the player reports `state`
+#[cfg(test)]
+pub(crate) fn fake_engine(state: PlayerState) -> mpsc::Receiver<PlayerCommand> {
+    let (tx, rx) = mpsc::channel();
+    *PLAYER_COMMANDS.lock() = Some(tx);
+    *PLAYER_STATE.lock() = state;
+    rx
+}
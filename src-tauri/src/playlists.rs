@@ -0,0 +1,223 @@
+//! Playlists
+//!
+//! One extended M3U file per playlist in ~/.config/honeybee/playlists, so they
+//! can be edited by hand too. Entries are written as absolute paths with an
+//! `#EXTINF` line when the track is in the music library; hand-written relative
+//! paths are resolved against the playlist's folder.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::fsutil::write_atomic;
+use crate::library;
+
+const PLAYLIST_EXTENSION: &str = "m3u";
+
+const MAX_NAME_LEN: usize = 100;
+
+lazy_static::lazy_static! {
+    // Serializes read-modify-write cycles on playlist files
+    static ref PLAYLIST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaylistInfo {
+    pub name: String,
+    pub track_count: usize,
+    pub modified: u64, // unix timestamp
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Playlist {
+    pub name: String,
+    pub tracks: Vec<String>,
+}
+
+fn playlists_dir() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/playlists"))
+        .unwrap_or_else(|| PathBuf::from("playlists"))
+}
+
+/// Playlist names become file names
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Name is longer than {} characters", MAX_NAME_LEN));
+    }
+    if name.starts_with('.') || name.contains(['/', '\\', '\0']) {
+        return Err("Name contains invalid characters".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn playlist_path(name: &str) -> Result<PathBuf, String> {
+    let name = validate_name(name)?;
+    Ok(playlists_dir().join(format!("{}.{}", name, PLAYLIST_EXTENSION)))
+}
+
+fn existing_playlist_path(name: &str) -> Result<PathBuf, String> {
+    let path = playlist_path(name)?;
+    if !path.is_file() {
+        return Err(format!("Playlist not found: {}", name));
+    }
+    Ok(path)
+}
+
+fn read_m3u(path: &Path) -> Result<Vec<String>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read playlist: {}", e))?;
+    let dir = path.parent().unwrap_or(Path::new("/"));
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let entry = Path::new(line.strip_prefix("file://").unwrap_or(line));
+            dir.join(entry).to_string_lossy().to_string()
+        })
+        .collect())
+}
+
+fn write_m3u(path: &Path, tracks: &[String]) -> Result<(), String> {
+    let mut content = String::from("#EXTM3U\n");
+    for track in tracks {
+        if let Some(info) = library::find_track(track) {
+            let title = match &info.artist {
+                Some(artist) => format!("{} - {}", artist, info.title),
+                None => info.title.clone(),
+            };
+            content.push_str(&format!("#EXTINF:{},{}\n", info.duration_ms / 1000, title));
+        }
+        content.push_str(track);
+        content.push('\n');
+    }
+
+    fs::create_dir_all(playlists_dir())
+        .map_err(|e| format!("Failed to create playlists dir: {}", e))?;
+    write_atomic(path, content).map_err(|e| format!("Failed to save playlist: {}", e))
+}
+
+/// Tracks must be absolute paths to existing files
+fn validate_tracks(tracks: &[String]) -> Result<(), String> {
+    for track in tracks {
+        let path = Path::new(track);
+        if !path.is_absolute() || !path.is_file() {
+            return Err(format!("File not found: {}", track));
+        }
+    }
+    Ok(())
+}
+
+/// List playlists by name
+#[tauri::command]
+pub fn list_playlists() -> Result<Vec<PlaylistInfo>, String> {
+    let dir = playlists_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read playlists: {}", e))?;
+    let mut playlists = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|e| e != PLAYLIST_EXTENSION) {
+            continue;
+        }
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let modified = entry
+            .metadata()
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        playlists.push(PlaylistInfo {
+            name,
+            track_count: read_m3u(&path).map(|t| t.len()).unwrap_or(0),
+            modified,
+        });
+    }
+
+    playlists.sort_by_key(|p| p.name.to_lowercase());
+    Ok(playlists)
+}
+
+#[tauri::command]
+pub fn get_playlist(name: String) -> Result<Playlist, String> {
+    let path = existing_playlist_path(&name)?;
+    Ok(Playlist {
+        name: validate_name(&name)?,
+        tracks: read_m3u(&path)?,
+    })
+}
+
+/// Create a playlist, optionally with tracks
+#[tauri::command]
+pub fn create_playlist(name: String, tracks: Option<Vec<String>>) -> Result<Playlist, String> {
+    let _guard = PLAYLIST_LOCK.lock();
+    let path = playlist_path(&name)?;
+    if path.exists() {
+        return Err(format!("A playlist named \"{}\" already exists", name.trim()));
+    }
+    let tracks = tracks.unwrap_or_default();
+    validate_tracks(&tracks)?;
+    write_m3u(&path, &tracks)?;
+
+    Ok(Playlist {
+        name: validate_name(&name)?,
+        tracks,
+    })
+}
+
+/// Replace a playlist's tracks (reorder, add or remove entries)
+#[tauri::command]
+pub fn save_playlist(name: String, tracks: Vec<String>) -> Result<(), String> {
+    let _guard = PLAYLIST_LOCK.lock();
+    let path = existing_playlist_path(&name)?;
+    validate_tracks(&tracks)?;
+    write_m3u(&path, &tracks)
+}
+
+/// Append tracks to a playlist
+#[tauri::command]
+pub fn add_to_playlist(name: String, tracks: Vec<String>) -> Result<(), String> {
+    let _guard = PLAYLIST_LOCK.lock();
+    let path = existing_playlist_path(&name)?;
+    validate_tracks(&tracks)?;
+    let mut all = read_m3u(&path)?;
+    all.extend(tracks);
+    write_m3u(&path, &all)
+}
+
+#[tauri::command]
+pub fn rename_playlist(name: String, new_name: String) -> Result<(), String> {
+    let _guard = PLAYLIST_LOCK.lock();
+    let path = existing_playlist_path(&name)?;
+    let new_path = playlist_path(&new_name)?;
+    if new_path == path {
+        return Ok(());
+    }
+    if new_path.exists() {
+        return Err(format!("A playlist named \"{}\" already exists", new_name.trim()));
+    }
+    fs::rename(&path, &new_path).map_err(|e| format!("Failed to rename playlist: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_playlist(name: String) -> Result<(), String> {
+    let _guard = PLAYLIST_LOCK.lock();
+    let path = existing_playlist_path(&name)?;
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete playlist: {}", e))
+}
@@ -0,0 +1,1027 @@
+//! PulseAudio Native Protocol
+//!
+//! A small client for the protocol spoken by PulseAudio and by pipewire-pulse
+//! on PipeWire systems, so volume changes go over one open socket instead of
+//! spawning `wpctl` / `pactl` each time, and without linking libpulse. Only
+//! the control commands are implemented, no streams.
+//!
+//! Packets are a 20 byte header (length, channel, two offset words, flags, all
+//! big endian) and a "tagstruct" payload where every value is preceded by a
+//! type tag. Requests start with the command and a tag the reply echoes.
+
+use std::{
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    time::Duration,
+};
+
+/// Highest protocol version understood here. Later versions add sink port
+/// fields this parser doesn't read.
+const PROTOCOL_VERSION: u32 = 32;
+
+/// The version word also carries SHM flags in its upper bits
+const VERSION_MASK: u32 = 0xffff;
+
+const COMMAND_ERROR: u32 = 0;
+const COMMAND_REPLY: u32 = 2;
+const COMMAND_AUTH: u32 = 8;
+const COMMAND_SET_CLIENT_NAME: u32 = 9;
+const COMMAND_GET_SERVER_INFO: u32 = 20;
+const COMMAND_GET_SINK_INFO: u32 = 21;
+const COMMAND_GET_SINK_INFO_LIST: u32 = 22;
+const COMMAND_GET_SOURCE_INFO: u32 = 23;
+const COMMAND_SUBSCRIBE: u32 = 35;
+const COMMAND_SET_SINK_VOLUME: u32 = 36;
+const COMMAND_SET_SOURCE_VOLUME: u32 = 38;
+const COMMAND_SET_SINK_MUTE: u32 = 39;
+const COMMAND_SET_SOURCE_MUTE: u32 = 40;
+const COMMAND_SET_DEFAULT_SINK: u32 = 44;
+const COMMAND_SUBSCRIBE_EVENT: u32 = 66;
+const COMMAND_SET_SINK_PORT: u32 = 96;
+
+/// Packets on this channel are commands, others carry audio
+const CONTROL_CHANNEL: u32 = u32::MAX;
+
+const INVALID_INDEX: u32 = u32::MAX;
+
+const HEADER_LEN: usize = 20;
+
+const MAX_PACKET_LEN: usize = 4 * 1024 * 1024;
+
+const COOKIE_LEN: usize = 256;
+
+/// A hung server must not hang the UI
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 100% volume
+pub const VOLUME_NORM: u32 = 0x10000;
+
+/// Subscribe to sinks (volume, mute, added, removed)
+pub const SUBSCRIBE_SINK: u32 = 0x0001;
+
+/// Subscribe to sources, i.e. microphones (volume, mute, added, removed)
+pub const SUBSCRIBE_SOURCE: u32 = 0x0002;
+
+/// Subscribe to server changes (e.g. a new default sink)
+pub const SUBSCRIBE_SERVER: u32 = 0x0080;
+
+/// Sink name the server resolves to the default output
+pub const DEFAULT_SINK: &str = "@DEFAULT_SINK@";
+
+/// Source name the server resolves to the default input
+pub const DEFAULT_SOURCE: &str = "@DEFAULT_SOURCE@";
+
+const TAG_STRING: u8 = b't';
+const TAG_STRING_NULL: u8 = b'N';
+const TAG_U32: u8 = b'L';
+const TAG_U8: u8 = b'B';
+const TAG_USEC: u8 = b'U';
+const TAG_SAMPLE_SPEC: u8 = b'a';
+const TAG_ARBITRARY: u8 = b'x';
+const TAG_BOOLEAN_TRUE: u8 = b'1';
+const TAG_BOOLEAN_FALSE: u8 = b'0';
+const TAG_CHANNEL_MAP: u8 = b'm';
+const TAG_CVOLUME: u8 = b'v';
+const TAG_PROPLIST: u8 = b'P';
+const TAG_VOLUME: u8 = b'V';
+const TAG_FORMAT_INFO: u8 = b'f';
+
+/// An output as the server describes it
+#[derive(Debug, Clone)]
+pub struct SinkInfo {
+    pub name: String,
+    pub description: String,
+    /// Per channel, `VOLUME_NORM` is 100%
+    pub volume: Vec<u32>,
+    pub mute: bool,
+    pub ports: Vec<PortInfo>,
+    pub active_port: Option<String>,
+}
+
+/// Where a sink's sound comes out, e.g. speakers or the headphone jack
+#[derive(Debug, Clone)]
+pub struct PortInfo {
+    pub name: String,
+    pub description: String,
+    /// Whether something is plugged in, when the hardware can tell
+    pub available: Option<bool>,
+}
+
+#[derive(Default)]
+struct TagWriter(Vec<u8>);
+
+impl TagWriter {
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.push(TAG_U32);
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn string(&mut self, value: Option<&str>) -> &mut Self {
+        match value {
+            Some(s) => {
+                self.0.push(TAG_STRING);
+                self.0.extend_from_slice(s.as_bytes());
+                self.0.push(0);
+            }
+            None => self.0.push(TAG_STRING_NULL),
+        }
+        self
+    }
+
+    fn bool(&mut self, value: bool) -> &mut Self {
+        self.0.push(if value {
+            TAG_BOOLEAN_TRUE
+        } else {
+            TAG_BOOLEAN_FALSE
+        });
+        self
+    }
+
+    fn arbitrary(&mut self, data: &[u8]) -> &mut Self {
+        self.0.push(TAG_ARBITRARY);
+        self.0.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        self.0.extend_from_slice(data);
+        self
+    }
+
+    fn cvolume(&mut self, volume: &[u32]) -> &mut Self {
+        self.0.push(TAG_CVOLUME);
+        self.0.push(volume.len() as u8);
+        for channel in volume {
+            self.0.extend_from_slice(&channel.to_be_bytes());
+        }
+        self
+    }
+
+    /// String properties, stored with their NUL terminator
+    fn proplist(&mut self, properties: &[(&str, &str)]) -> &mut Self {
+        self.0.push(TAG_PROPLIST);
+        for (key, value) in properties {
+            let mut data = value.as_bytes().to_vec();
+            data.push(0);
+            self.string(Some(key));
+            self.u32(data.len() as u32);
+            self.arbitrary(&data);
+        }
+        self.string(None)
+    }
+}
+
+struct TagReader {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl TagReader {
+    fn bytes(&mut self, len: usize) -> Result<&[u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("Truncated PulseAudio packet")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn tag(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        let tag = self.tag()?;
+        if tag != expected {
+            return Err(format!(
+                "Unexpected PulseAudio value '{}', expected '{}'",
+                tag as char, expected as char
+            ));
+        }
+        Ok(())
+    }
+
+    fn raw_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.expect(TAG_U32)?;
+        self.raw_u32()
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        self.expect(TAG_U8)?;
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn usec(&mut self) -> Result<(), String> {
+        self.expect(TAG_USEC)?;
+        self.bytes(8).map(|_| ())
+    }
+
+    fn volume(&mut self) -> Result<u32, String> {
+        self.expect(TAG_VOLUME)?;
+        self.raw_u32()
+    }
+
+    fn bool(&mut self) -> Result<bool, String> {
+        match self.tag()? {
+            TAG_BOOLEAN_TRUE => Ok(true),
+            TAG_BOOLEAN_FALSE => Ok(false),
+            tag => Err(format!(
+                "Unexpected PulseAudio value '{}', expected a boolean",
+                tag as char
+            )),
+        }
+    }
+
+    fn string(&mut self) -> Result<Option<String>, String> {
+        match self.tag()? {
+            TAG_STRING_NULL => Ok(None),
+            TAG_STRING => {
+                let rest = &self.data[self.pos..];
+                let len = rest
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or("Unterminated PulseAudio string")?;
+                let s = String::from_utf8_lossy(&rest[..len]).to_string();
+                self.pos += len + 1;
+                Ok(Some(s))
+            }
+            tag => Err(format!(
+                "Unexpected PulseAudio value '{}', expected a string",
+                tag as char
+            )),
+        }
+    }
+
+    fn arbitrary(&mut self) -> Result<&[u8], String> {
+        self.expect(TAG_ARBITRARY)?;
+        let len = self.raw_u32()? as usize;
+        self.bytes(len)
+    }
+
+    fn sample_spec(&mut self) -> Result<(), String> {
+        self.expect(TAG_SAMPLE_SPEC)?;
+        // Format, channels, rate
+        self.bytes(6).map(|_| ())
+    }
+
+    fn channel_map(&mut self) -> Result<(), String> {
+        self.expect(TAG_CHANNEL_MAP)?;
+        let channels = self.bytes(1)?[0] as usize;
+        self.bytes(channels).map(|_| ())
+    }
+
+    fn cvolume(&mut self) -> Result<Vec<u32>, String> {
+        self.expect(TAG_CVOLUME)?;
+        let channels = self.bytes(1)?[0];
+        (0..channels).map(|_| self.raw_u32()).collect()
+    }
+
+    fn proplist(&mut self) -> Result<(), String> {
+        self.expect(TAG_PROPLIST)?;
+        while self.string()?.is_some() {
+            self.u32()?;
+            self.arbitrary()?;
+        }
+        Ok(())
+    }
+
+    fn format_info(&mut self) -> Result<(), String> {
+        self.expect(TAG_FORMAT_INFO)?;
+        self.u8()?;
+        self.proplist()
+    }
+}
+
+fn error_name(code: u32) -> &'static str {
+    match code {
+        1 => "access denied",
+        2 => "unknown command",
+        3 => "invalid argument",
+        4 => "entity exists",
+        5 => "no such entity",
+        6 => "connection refused",
+        7 => "protocol error",
+        8 => "timeout",
+        9 => "no authentication key",
+        10 => "internal error",
+        11 => "connection terminated",
+        12 => "entity killed",
+        13 => "invalid server",
+        17 => "not supported",
+        _ => "unknown error",
+    }
+}
+
+fn socket_path() -> Option<PathBuf> {
+    if let Some(server) = std::env::var_os("PULSE_SERVER") {
+        let server = server.to_string_lossy().to_string();
+        let path = server.strip_prefix("unix:").unwrap_or(&server);
+        return path.starts_with('/').then(|| PathBuf::from(path));
+    }
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("/run/user/{}", unsafe { libc::getuid() })));
+    Some(runtime_dir.join("pulse/native"))
+}
+
+/// PulseAudio checks this unless the socket allows anonymous access,
+/// pipewire-pulse ignores it
+fn read_cookie() -> Vec<u8> {
+    let mut candidates: Vec<PathBuf> = std::env::var_os("PULSE_COOKIE")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    if let Some(config) = dirs::config_dir() {
+        candidates.push(config.join("pulse/cookie"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".pulse-cookie"));
+    }
+    candidates
+        .iter()
+        .filter_map(|path| std::fs::read(path).ok())
+        .find(|cookie| cookie.len() == COOKIE_LEN)
+        .unwrap_or_else(|| vec![0; COOKIE_LEN])
+}
+
+fn read_sink(reply: &mut TagReader, version: u32) -> Result<SinkInfo, String> {
+    reply.u32()?; // index
+    let name = reply.string()?.unwrap_or_default();
+    let description = reply.string()?.unwrap_or_default();
+    reply.sample_spec()?;
+    reply.channel_map()?;
+    reply.u32()?; // owner module
+    let volume = reply.cvolume()?;
+    let mute = reply.bool()?;
+    reply.u32()?; // monitor source
+    reply.string()?; // monitor source name
+    reply.usec()?; // latency
+    reply.string()?; // driver
+    reply.u32()?; // flags
+    if version >= 13 {
+        reply.proplist()?;
+        reply.usec()?; // configured latency
+    }
+    if version >= 15 {
+        reply.volume()?; // base volume
+        reply.u32()?; // state
+        reply.u32()?; // volume steps
+        reply.u32()?; // card
+    }
+    let mut ports = Vec::new();
+    let mut active_port = None;
+    if version >= 16 {
+        let count = reply.u32()?;
+        for _ in 0..count {
+            let name = reply.string()?.unwrap_or_default();
+            let description = reply.string()?.unwrap_or_default();
+            reply.u32()?; // priority
+                          // Unknown, no, yes
+            let available = match version >= 24 {
+                true => match reply.u32()? {
+                    1 => Some(false),
+                    2 => Some(true),
+                    _ => None,
+                },
+                false => None,
+            };
+            ports.push(PortInfo {
+                name,
+                description,
+                available,
+            });
+        }
+        active_port = reply.string()?;
+    }
+    if version >= 21 {
+        let formats = reply.u8()?;
+        for _ in 0..formats {
+            reply.format_info()?;
+        }
+    }
+
+    Ok(SinkInfo {
+        name,
+        description,
+        volume,
+        mute,
+        ports,
+        active_port,
+    })
+}
+
+/// An input as the server describes it, only what the mic controls need
+#[derive(Debug, Clone)]
+pub struct SourceInfo {
+    /// Per channel, `VOLUME_NORM` is 100%
+    pub volume: Vec<u32>,
+    pub mute: bool,
+}
+
+/// Sources start like sinks, the rest of the reply is skipped
+fn read_source(reply: &mut TagReader) -> Result<SourceInfo, String> {
+    reply.u32()?; // index
+    reply.string()?; // name
+    reply.string()?; // description
+    reply.sample_spec()?;
+    reply.channel_map()?;
+    reply.u32()?; // owner module
+    let volume = reply.cvolume()?;
+    let mute = reply.bool()?;
+
+    Ok(SourceInfo { volume, mute })
+}
+
+/// An open, authenticated connection to the sound server
+pub struct Connection {
+    stream: UnixStream,
+    version: u32,
+    next_tag: u32,
+    server_name: String,
+    /// The socket failed, the connection has to be replaced
+    broken: bool,
+}
+
+impl Connection {
+    pub fn connect() -> Result<Self, String> {
+        let path = socket_path().ok_or("No PulseAudio socket configured")?;
+        let stream = UnixStream::connect(&path)
+            .map_err(|e| format!("Failed to connect to {}: {}", path.display(), e))?;
+        stream
+            .set_read_timeout(Some(TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+            .map_err(|e| e.to_string())?;
+
+        let mut connection = Connection {
+            stream,
+            version: PROTOCOL_VERSION,
+            next_tag: 0,
+            server_name: String::new(),
+            broken: false,
+        };
+
+        let cookie = read_cookie();
+        let mut reply = connection.request(COMMAND_AUTH, |args| {
+            args.u32(PROTOCOL_VERSION).arbitrary(&cookie);
+        })?;
+        let server_version = reply.u32()? & VERSION_MASK;
+        if server_version < 13 {
+            return Err(format!("PulseAudio protocol {} is too old", server_version));
+        }
+        connection.version = server_version.min(PROTOCOL_VERSION);
+
+        connection.request(COMMAND_SET_CLIENT_NAME, |args| {
+            args.proplist(&[
+                ("application.name", "Honeybee"),
+                ("application.id", "honeybee"),
+            ]);
+        })?;
+
+        let mut info = connection.request(COMMAND_GET_SERVER_INFO, |_| {})?;
+        connection.server_name = info.string()?.unwrap_or_default();
+        Ok(connection)
+    }
+
+    /// The server's package name, e.g. "PulseAudio (on PipeWire 1.0.5)"
+    pub fn server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    fn send(&mut self, payload: &[u8]) -> Result<(), String> {
+        let mut packet = Vec::with_capacity(HEADER_LEN + payload.len());
+        for word in [payload.len() as u32, CONTROL_CHANNEL, 0, 0, 0] {
+            packet.extend_from_slice(&word.to_be_bytes());
+        }
+        packet.extend_from_slice(payload);
+        self.stream.write_all(&packet).map_err(|e| {
+            self.broken = true;
+            format!("PulseAudio write failed: {}", e)
+        })
+    }
+
+    /// Next command packet, skipping audio data
+    fn receive(&mut self) -> Result<TagReader, String> {
+        loop {
+            let mut header = [0u8; HEADER_LEN];
+            self.read_exact(&mut header)?;
+            let word = |i: usize| {
+                u32::from_be_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]])
+            };
+            let len = word(0) as usize;
+            if len > MAX_PACKET_LEN {
+                self.broken = true;
+                return Err(format!("PulseAudio packet of {} bytes is too large", len));
+            }
+            let mut data = vec![0u8; len];
+            self.read_exact(&mut data)?;
+            if word(4) == CONTROL_CHANNEL {
+                return Ok(TagReader { data, pos: 0 });
+            }
+        }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), String> {
+        self.stream.read_exact(buf).map_err(|e| {
+            self.broken = true;
+            format!("PulseAudio read failed: {}", e)
+        })
+    }
+
+    /// Send a command and wait for its reply, positioned after the tag
+    fn request(
+        &mut self,
+        command: u32,
+        args: impl FnOnce(&mut TagWriter),
+    ) -> Result<TagReader, String> {
+        let tag = self.next_tag;
+        self.next_tag = self.next_tag.wrapping_add(1);
+
+        let mut packet = TagWriter::default();
+        packet.u32(command).u32(tag);
+        args(&mut packet);
+        self.send(&packet.0)?;
+
+        loop {
+            let mut reply = self.receive()?;
+            let result = (|| {
+                let kind = reply.u32()?;
+                let reply_tag = reply.u32()?;
+                Ok::<_, String>((kind, reply_tag))
+            })();
+            let (kind, reply_tag) = result.inspect_err(|_| self.broken = true)?;
+            // Events the server sends on its own
+            if reply_tag != tag {
+                continue;
+            }
+            return match kind {
+                COMMAND_REPLY => Ok(reply),
+                COMMAND_ERROR => {
+                    let code = reply.u32()?;
+                    Err(format!("PulseAudio: {}", error_name(code)))
+                }
+                other => {
+                    self.broken = true;
+                    Err(format!("Unexpected PulseAudio reply {}", other))
+                }
+            };
+        }
+    }
+
+    pub fn sink_info(&mut self, name: &str) -> Result<SinkInfo, String> {
+        let mut reply = self.request(COMMAND_GET_SINK_INFO, |args| {
+            args.u32(INVALID_INDEX).string(Some(name));
+        })?;
+        let version = self.version;
+        read_sink(&mut reply, version).inspect_err(|_| self.broken = true)
+    }
+
+    /// Set every channel, `volume` must have the sink's channel count
+    pub fn set_sink_volume(&mut self, name: &str, volume: &[u32]) -> Result<(), String> {
+        self.request(COMMAND_SET_SINK_VOLUME, |args| {
+            args.u32(INVALID_INDEX).string(Some(name)).cvolume(volume);
+        })
+        .map(|_| ())
+    }
+
+    pub fn set_sink_mute(&mut self, name: &str, mute: bool) -> Result<(), String> {
+        self.request(COMMAND_SET_SINK_MUTE, |args| {
+            args.u32(INVALID_INDEX).string(Some(name)).bool(mute);
+        })
+        .map(|_| ())
+    }
+
+    pub fn source_info(&mut self, name: &str) -> Result<SourceInfo, String> {
+        let mut reply = self.request(COMMAND_GET_SOURCE_INFO, |args| {
+            args.u32(INVALID_INDEX).string(Some(name));
+        })?;
+        read_source(&mut reply).inspect_err(|_| self.broken = true)
+    }
+
+    /// Set every channel, `volume` must have the source's channel count
+    pub fn set_source_volume(&mut self, name: &str, volume: &[u32]) -> Result<(), String> {
+        self.request(COMMAND_SET_SOURCE_VOLUME, |args| {
+            args.u32(INVALID_INDEX).string(Some(name)).cvolume(volume);
+        })
+        .map(|_| ())
+    }
+
+    pub fn set_source_mute(&mut self, name: &str, mute: bool) -> Result<(), String> {
+        self.request(COMMAND_SET_SOURCE_MUTE, |args| {
+            args.u32(INVALID_INDEX).string(Some(name)).bool(mute);
+        })
+        .map(|_| ())
+    }
+
+    pub fn sinks(&mut self) -> Result<Vec<SinkInfo>, String> {
+        let mut reply = self.request(COMMAND_GET_SINK_INFO_LIST, |_| {})?;
+        let version = self.version;
+        let mut sinks = Vec::new();
+        while !reply.is_empty() {
+            sinks.push(read_sink(&mut reply, version).inspect_err(|_| self.broken = true)?);
+        }
+        Ok(sinks)
+    }
+
+    pub fn default_sink_name(&mut self) -> Result<Option<String>, String> {
+        let mut info = self.request(COMMAND_GET_SERVER_INFO, |_| {})?;
+        // Package name, version, user, host
+        for _ in 0..4 {
+            info.string()?;
+        }
+        info.sample_spec()?;
+        info.string()
+    }
+
+    /// New streams play on the default sink, and the server moves the ones
+    /// that were following the previous default
+    pub fn set_default_sink(&mut self, name: &str) -> Result<(), String> {
+        self.request(COMMAND_SET_DEFAULT_SINK, |args| {
+            args.string(Some(name));
+        })
+        .map(|_| ())
+    }
+
+    pub fn set_sink_port(&mut self, name: &str, port: &str) -> Result<(), String> {
+        self.request(COMMAND_SET_SINK_PORT, |args| {
+            args.u32(INVALID_INDEX)
+                .string(Some(name))
+                .string(Some(port));
+        })
+        .map(|_| ())
+    }
+
+    /// Have the server report changes to the objects in `mask`, see
+    /// `next_event`. Use a connection of its own for this, since waiting
+    /// for events blocks.
+    pub fn subscribe(&mut self, mask: u32) -> Result<(), String> {
+        self.request(COMMAND_SUBSCRIBE, |args| {
+            args.u32(mask);
+        })?;
+        // Events may be hours apart
+        self.stream
+            .set_read_timeout(None)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Block until something subscribed to changed
+    pub fn next_event(&mut self) -> Result<(), String> {
+        loop {
+            let mut packet = self.receive()?;
+            if packet.u32().inspect_err(|_| self.broken = true)? == COMMAND_SUBSCRIBE_EVENT {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tagstruct bytes written out by hand, independent of `TagWriter`
+    #[derive(Default)]
+    struct Fixture(Vec<u8>);
+
+    impl Fixture {
+        fn raw(mut self, bytes: &[u8]) -> Self {
+            self.0.extend_from_slice(bytes);
+            self
+        }
+
+        fn u32(self, value: u32) -> Self {
+            self.raw(b"L").raw(&value.to_be_bytes())
+        }
+
+        fn u8(self, value: u8) -> Self {
+            self.raw(&[b'B', value])
+        }
+
+        fn string(self, value: &str) -> Self {
+            self.raw(b"t").raw(value.as_bytes()).raw(&[0])
+        }
+
+        fn null(self) -> Self {
+            self.raw(b"N")
+        }
+
+        fn bool(self, value: bool) -> Self {
+            self.raw(if value { b"1" } else { b"0" })
+        }
+
+        fn usec(self) -> Self {
+            self.raw(b"U").raw(&20_000u64.to_be_bytes())
+        }
+
+        /// s16le stereo at 48 kHz
+        fn sample_spec(self) -> Self {
+            self.raw(&[b'a', 3, 2]).raw(&48_000u32.to_be_bytes())
+        }
+
+        /// Front left, front right
+        fn channel_map(self) -> Self {
+            self.raw(&[b'm', 2, 1, 2])
+        }
+
+        fn cvolume(self, volume: &[u32]) -> Self {
+            volume
+                .iter()
+                .fold(self.raw(&[b'v', volume.len() as u8]), |f, v| {
+                    f.raw(&v.to_be_bytes())
+                })
+        }
+
+        fn volume(self, value: u32) -> Self {
+            self.raw(b"V").raw(&value.to_be_bytes())
+        }
+
+        fn proplist(self, properties: &[(&str, &str)]) -> Self {
+            properties
+                .iter()
+                .fold(self.raw(b"P"), |f, (key, value)| {
+                    let len = value.len() as u32 + 1;
+                    f.string(key)
+                        .u32(len)
+                        .raw(b"x")
+                        .raw(&len.to_be_bytes())
+                        .raw(value.as_bytes())
+                        .raw(&[0])
+                })
+                .null()
+        }
+
+        fn reader(self) -> TagReader {
+            TagReader {
+                data: self.0,
+                pos: 0,
+            }
+        }
+    }
+
+    /// Everything of a sink up to the ports, in protocol version 15 and later
+    fn sink_head(name: &str, volume: &[u32], mute: bool) -> Fixture {
+        Fixture::default()
+            .u32(0)
+            .string(name)
+            .string("Built-in Audio Analog Stereo")
+            .sample_spec()
+            .channel_map()
+            .u32(7)
+            .cvolume(volume)
+            .bool(mute)
+            .u32(1)
+            .string(&format!("{}.monitor", name))
+            .usec()
+            .string("module-alsa-card.c")
+            .u32(0x1ff)
+            .proplist(&[("device.class", "sound"), ("alsa.card", "0")])
+            .usec()
+            .volume(VOLUME_NORM)
+            .u32(0)
+            .u32(65537)
+            .u32(3)
+    }
+
+    fn sink_v32() -> Fixture {
+        sink_head("alsa_output.analog-stereo", &[0x8000, 0x6000], false)
+            .u32(3)
+            .string("analog-output-speaker")
+            .string("Speakers")
+            .u32(10_000)
+            .u32(0)
+            .string("analog-output-headphones")
+            .string("Headphones")
+            .u32(9_900)
+            .u32(1)
+            .string("hdmi-output-0")
+            .string("HDMI")
+            .u32(5_900)
+            .u32(2)
+            .string("analog-output-speaker")
+            .u8(2)
+            .raw(b"f")
+            .u8(1)
+            .proplist(&[])
+            .raw(b"f")
+            .u8(2)
+            .proplist(&[("format.rate", "48000")])
+    }
+
+    /// A 20 byte header and the payload, on the control channel
+    fn packet(channel: u32, payload: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::new();
+        for word in [payload.len() as u32, channel, 0, 0, 0] {
+            packet.extend_from_slice(&word.to_be_bytes());
+        }
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    /// A connection whose server already queued `packets`, the other end is
+    /// kept open so requests can be written
+    fn connection(packets: &[Vec<u8>]) -> (Connection, UnixStream) {
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        for packet in packets {
+            server.write_all(packet).unwrap();
+        }
+        let connection = Connection {
+            stream,
+            version: PROTOCOL_VERSION,
+            next_tag: 0,
+            server_name: String::new(),
+            broken: false,
+        };
+        (connection, server)
+    }
+
+    #[test]
+    fn reads_v32_sink_with_ports_and_formats() {
+        let mut reply = sink_v32().reader();
+        let sink = read_sink(&mut reply, 32).unwrap();
+        assert!(reply.is_empty());
+
+        assert_eq!(sink.name, "alsa_output.analog-stereo");
+        assert_eq!(sink.description, "Built-in Audio Analog Stereo");
+        assert_eq!(sink.volume, vec![0x8000, 0x6000]);
+        assert!(!sink.mute);
+        let ports: Vec<_> = sink
+            .ports
+            .iter()
+            .map(|p| (p.name.as_str(), p.description.as_str(), p.available))
+            .collect();
+        assert_eq!(
+            ports,
+            vec![
+                ("analog-output-speaker", "Speakers", None),
+                ("analog-output-headphones", "Headphones", Some(false)),
+                ("hdmi-output-0", "HDMI", Some(true)),
+            ]
+        );
+        assert_eq!(sink.active_port.as_deref(), Some("analog-output-speaker"));
+    }
+
+    #[test]
+    fn reads_v15_sink_without_ports() {
+        let mut reply = sink_head("null", &[VOLUME_NORM], true).reader();
+        let sink = read_sink(&mut reply, 15).unwrap();
+        assert!(reply.is_empty());
+
+        assert_eq!(sink.name, "null");
+        assert_eq!(sink.volume, vec![VOLUME_NORM]);
+        assert!(sink.mute);
+        assert!(sink.ports.is_empty());
+        assert_eq!(sink.active_port, None);
+    }
+
+    #[test]
+    fn reads_source_volume_and_mute() {
+        let mut reply = sink_head("alsa_input.analog-stereo", &[0x4000, 0x4000], true).reader();
+        let source = read_source(&mut reply).unwrap();
+        assert_eq!(source.volume, vec![0x4000, 0x4000]);
+        assert!(source.mute);
+    }
+
+    #[test]
+    fn lists_sinks_until_the_reply_ends() {
+        let reply = Fixture::default()
+            .u32(COMMAND_REPLY)
+            .u32(0)
+            .raw(&sink_v32().0)
+            .raw(&sink_v32().0);
+        let (mut connection, _server) = connection(&[packet(CONTROL_CHANNEL, &reply.0)]);
+
+        let sinks = connection.sinks().unwrap();
+        assert_eq!(sinks.len(), 2);
+        assert_eq!(sinks[1].ports.len(), 3);
+        assert!(!connection.is_broken());
+    }
+
+    #[test]
+    fn reads_default_sink_from_server_info() {
+        let reply = Fixture::default()
+            .u32(COMMAND_REPLY)
+            .u32(0)
+            .string("PulseAudio (on PipeWire 1.0.5)")
+            .string("15.0.0")
+            .string("user")
+            .string("host")
+            .sample_spec()
+            .string("alsa_output.analog-stereo")
+            .string("alsa_input.analog-stereo")
+            .u32(0x1234)
+            .channel_map();
+        let (mut connection, _server) = connection(&[packet(CONTROL_CHANNEL, &reply.0)]);
+
+        assert_eq!(
+            connection.default_sink_name().unwrap().as_deref(),
+            Some("alsa_output.analog-stereo")
+        );
+    }
+
+    #[test]
+    fn skips_events_and_audio_before_the_reply() {
+        let event = Fixture::default()
+            .u32(COMMAND_SUBSCRIBE_EVENT)
+            .u32(INVALID_INDEX)
+            .u32(0x10)
+            .u32(0);
+        let reply = Fixture::default().u32(COMMAND_REPLY).u32(0);
+        let (mut connection, _server) = connection(&[
+            packet(CONTROL_CHANNEL, &event.0),
+            packet(0, &[0; 64]),
+            packet(CONTROL_CHANNEL, &reply.0),
+        ]);
+
+        connection.set_sink_mute(DEFAULT_SINK, true).unwrap();
+        assert!(!connection.is_broken());
+    }
+
+    #[test]
+    fn error_reply_is_an_error() {
+        let reply = Fixture::default().u32(COMMAND_ERROR).u32(0).u32(5);
+        let (mut connection, _server) = connection(&[packet(CONTROL_CHANNEL, &reply.0)]);
+
+        assert_eq!(
+            connection.sink_info("nope").unwrap_err(),
+            "PulseAudio: no such entity"
+        );
+        // The connection is still fine for the next request
+        assert!(!connection.is_broken());
+    }
+
+    #[test]
+    fn truncated_sink_breaks_the_connection() {
+        let sink = sink_v32().0;
+        let reply = Fixture::default()
+            .u32(COMMAND_REPLY)
+            .u32(0)
+            .raw(&sink[..sink.len() / 2]);
+        let (mut connection, _server) = connection(&[packet(CONTROL_CHANNEL, &reply.0)]);
+
+        assert!(connection.sink_info(DEFAULT_SINK).is_err());
+        assert!(connection.is_broken());
+
+        let mut reply = Fixture::default().raw(&[b'L', 0, 0]).reader();
+        assert_eq!(reply.u32().unwrap_err(), "Truncated PulseAudio packet");
+        let mut reply = Fixture::default().raw(b"tno end").reader();
+        assert_eq!(
+            reply.string().unwrap_err(),
+            "Unterminated PulseAudio string"
+        );
+        let mut reply = Fixture::default().raw(b"x").raw(&[0, 0, 1, 0, 1]).reader();
+        assert_eq!(
+            reply.arbitrary().unwrap_err(),
+            "Truncated PulseAudio packet"
+        );
+        let mut reply = Fixture::default().raw(&[b'v', 2, 0, 1, 0, 0]).reader();
+        assert_eq!(reply.cvolume().unwrap_err(), "Truncated PulseAudio packet");
+    }
+
+    #[test]
+    fn cvolume_and_proplist_round_trip() {
+        let mut writer = TagWriter::default();
+        writer
+            .cvolume(&[0, VOLUME_NORM, u32::MAX])
+            .proplist(&[("application.name", "Honeybee"), ("empty", "")])
+            .u32(42);
+        let mut reader = TagReader {
+            data: writer.0.clone(),
+            pos: 0,
+        };
+        assert_eq!(reader.cvolume().unwrap(), vec![0, VOLUME_NORM, u32::MAX]);
+        reader.proplist().unwrap();
+        assert_eq!(reader.u32().unwrap(), 42);
+        assert!(reader.is_empty());
+
+        // Entry by entry, the values keep their NUL terminator
+        let mut reader = TagReader {
+            data: writer.0,
+            pos: 0,
+        };
+        reader.cvolume().unwrap();
+        reader.expect(TAG_PROPLIST).unwrap();
+        assert_eq!(
+            reader.string().unwrap().as_deref(),
+            Some("application.name")
+        );
+        assert_eq!(reader.u32().unwrap(), 9);
+        assert_eq!(reader.arbitrary().unwrap(), b"Honeybee\0");
+        assert_eq!(reader.string().unwrap().as_deref(), Some("empty"));
+        assert_eq!(reader.u32().unwrap(), 1);
+        assert_eq!(reader.arbitrary().unwrap(), b"\0");
+        assert_eq!(reader.string().unwrap(), None);
+    }
+}
@@ -1,33 +1,75 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, Sample, SampleFormat, SizedSample,
+};
 use chrono::Local;
+use ringbuf::{
+    traits::{Consumer, Observer, Producer, Split},
+    HeapCons, HeapProd, HeapRb,
+};
 use std::{
     fs,
-    io::{Cursor, Write},
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
-    thread,
+    thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 use parking_lot::Mutex;
 use tauri::{AppHandle, Emitter};
 
+use crate::fsutil::write_atomic;
+use crate::levels::{self, LevelMeter};
+use crate::mic::{self, MicLease, MicOwner};
+use crate::storage::{self, MediaKind};
+use crate::wav::{self, WavWriter};
+use crate::{flac, ogg_opus, waveform};
+
 const RECORDINGS_DIR: &str = "honeybee-recordings";
 
+/// Suffix for recordings still being written or transcoded (dropped on finalize)
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// Seconds of audio the ring buffer can hold if the writer stalls (slow eMMC)
+const RING_BUFFER_SECONDS: usize = 4;
+
+/// How often the writer patches the WAV header, bounding what a crash can lose
+const HEADER_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Level events go out every tick (~20 Hz), status events every STATUS_EVERY ticks
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+const STATUS_EVERY: u32 = 4;
+/// Free disk space is re-checked every 5 s while recording
+const STORAGE_CHECK_EVERY: u32 = 100;
+
+/// Window used to find speech when trimming silence
+const TRIM_WINDOW_MS: u64 = 20;
+
+/// How long stop_recording waits for the file to be finalized (and transcoded)
+const STOP_TIMEOUT: Duration = Duration::from_secs(120);
+
 static RECORDING: AtomicBool = AtomicBool::new(false);
 static STOP_RECORDING: AtomicBool = AtomicBool::new(false);
+// While paused the stream stays open but samples are dropped in the callback
+static PAUSED: AtomicBool = AtomicBool::new(false);
+// Frames kept so far (excludes paused time), drives duration_ms
+static CAPTURED_FRAMES: AtomicU64 = AtomicU64::new(0);
+static CAPTURE_RATE: AtomicU64 = AtomicU64::new(44100);
+// RMS / peak of the incoming audio, fed from the cpal callback
+static RECORDING_METER: LevelMeter = LevelMeter::new();
 
 lazy_static::lazy_static! {
-    static ref RECORDING_SAMPLES: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
-    static ref SAMPLE_RATE: Arc<Mutex<u32>> = Arc::new(Mutex::new(44100));
-    static ref CHANNELS: Arc<Mutex<u16>> = Arc::new(Mutex::new(1));
+    // Result of the last finished recording, picked up by stop_recording
+    static ref LAST_SAVED: Mutex<Option<RecordingSaved>> = Mutex::new(None);
 }
 
 #[derive(Clone, serde::Serialize)]
 pub struct RecordingStatus {
     pub recording: bool,
-    pub duration_ms: u64,
+    pub paused: bool,
+    pub duration_ms: u64, // excludes paused time
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -37,6 +79,32 @@ pub struct RecordingSaved {
     pub duration_ms: u64,
     pub success: bool,
     pub error: Option<String>,
+    pub stop_reason: StopReason,
+    pub trimmed_ms: u64, // silence removed from the start and end
+}
+
+/// Why a recording ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// stop_recording was called
+    User,
+    /// Voice activity detection saw no speech for the configured timeout
+    Silence,
+    /// The device or the disk failed
+    Error,
+    /// Free disk space dropped below the critical threshold
+    StorageFull,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct InputDevice {
+    pub id: String, // pass to start_recording
+    pub name: String,
+    pub is_default: bool,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -45,26 +113,341 @@ pub struct RecordingInfo {
     pub path: String,
     pub size: u64,
     pub modified: u64,
+    pub format: RecordingFormat,
+    // From the file header, 0 when it can't be read
+    pub duration_ms: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    #[serde(flatten)]
+    pub metadata: RecordingMetadata,
+}
+
+/// User annotations, stored in a hidden sidecar next to the recording
+/// (`.REC_....wav.meta.json`). WAV files also get them as a LIST/INFO chunk.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RecordingMetadata {
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+}
+
+impl RecordingMetadata {
+    /// Trim values, drop empty ones and duplicate tags
+    fn normalized(self) -> Self {
+        let clean = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.tags {
+            let tag = tag.trim().to_string();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Self {
+            title: clean(self.title),
+            tags,
+            notes: clean(self.notes),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Output format of a recording. Audio is always captured to WAV first and
+/// transcoded once the recording is finalized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    /// 16-bit PCM, largest but no processing
+    #[default]
+    Wav,
+    /// Lossless, roughly half the size of WAV
+    Flac,
+    /// Lossy speech codec in an Ogg container, a few % of the WAV size
+    Opus,
+}
+
+impl RecordingFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            RecordingFormat::Wav => "wav",
+            RecordingFormat::Flac => "flac",
+            RecordingFormat::Opus => "opus",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            RecordingFormat::Wav => "audio/wav",
+            RecordingFormat::Flac => "audio/flac",
+            RecordingFormat::Opus => "audio/ogg",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "wav" => Some(RecordingFormat::Wav),
+            "flac" => Some(RecordingFormat::Flac),
+            "opus" | "ogg" => Some(RecordingFormat::Opus),
+            _ => None,
+        }
+    }
+}
+
+/// Voice activity detection: auto-stop on silence and silence trimming
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct VadSettings {
+    /// Stop the recording after `silence_timeout_secs` without speech
+    pub auto_stop: bool,
+    pub silence_timeout_secs: f32,
+    /// Cut leading and trailing silence when the recording is saved
+    pub trim_silence: bool,
+    /// Audio with an RMS level below this (dBFS) counts as silence
+    pub silence_threshold_db: f32,
+    /// Silence kept before and after the speech when trimming
+    pub trim_padding_ms: u64,
+}
+
+impl Default for VadSettings {
+    fn default() -> Self {
+        Self {
+            auto_stop: false,
+            silence_timeout_secs: 5.0,
+            trim_silence: false,
+            silence_threshold_db: -45.0,
+            trim_padding_ms: 300,
+        }
+    }
+}
+
+/// Recorder settings (~/.config/honeybee/recorder.json)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RecorderSettings {
+    /// Format used when start_recording doesn't specify one
+    pub format: RecordingFormat,
+    pub vad: VadSettings,
+}
+
+fn settings_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee/recorder.json"))
+        .unwrap_or_else(|| PathBuf::from("recorder.json"))
+}
+
+fn load_settings() -> RecorderSettings {
+    fs::read_to_string(settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// ~/Music/honeybee-recordings (falls back to ~/Music when XDG is not set up)
+pub(crate) fn recordings_dir() -> Result<PathBuf, String> {
+    let music_dir = dirs::audio_dir()
+        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
+        .ok_or("Failed to get Music directory")?;
+    Ok(music_dir.join(RECORDINGS_DIR))
+}
+
+/// Whether a file in the recordings directory is a recording
+pub(crate) fn is_recording_file(path: &Path) -> bool {
+    RecordingFormat::from_path(path).is_some()
+}
+
+/// Hidden file next to a recording holding data about it
+pub(crate) fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}", name, suffix))
+}
+
+/// Sidecars that follow their recording on rename / delete
+const SIDECAR_SUFFIXES: &[&str] = &["meta.json", waveform::CACHE_SUFFIX];
+
+fn metadata_path(path: &Path) -> PathBuf {
+    sidecar_path(path, "meta.json")
+}
+
+fn load_metadata(path: &Path) -> RecordingMetadata {
+    fs::read_to_string(metadata_path(path))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Remove a recording from disk
+pub(crate) fn remove_recording_file(path: &Path) -> Result<(), String> {
+    fs::remove_file(path).map_err(|e| format!("Failed to delete recording: {}", e))?;
+    for suffix in SIDECAR_SUFFIXES {
+        let _ = fs::remove_file(sidecar_path(path, suffix));
+    }
+    Ok(())
+}
+
+/// Resolve a path passed from the frontend, refusing anything outside the recordings directory
+pub(crate) fn recording_path(path: &str) -> Result<PathBuf, String> {
+    let rec_dir = recordings_dir()?;
+    let target = PathBuf::from(path);
+    let escapes = target
+        .components()
+        .any(|c| matches!(c, std::path::Component::ParentDir));
+    if escapes || !target.starts_with(&rec_dir) {
+        return Err("Path is outside the recordings directory".to_string());
+    }
+    if !is_recording_file(&target) || !target.is_file() {
+        return Err("Recording not found".to_string());
+    }
+    Ok(target)
+}
+
+/// Format details from the file header: (duration_ms, sample_rate, channels)
+fn read_header(path: &Path, format: RecordingFormat) -> Option<(u64, u32, u16)> {
+    match format {
+        RecordingFormat::Wav => wav::read_info(path)
+            .ok()
+            .map(|i| (i.duration_ms(), i.sample_rate, i.channels)),
+        RecordingFormat::Flac => flac::read_streaminfo(path)
+            .ok()
+            .map(|i| (i.duration_ms(), i.sample_rate, i.channels)),
+        // Opus always decodes at 48 kHz
+        RecordingFormat::Opus => ogg_opus::read_info(path)
+            .ok()
+            .map(|i| (i.duration_ms, 48000, i.channels)),
+    }
+}
+
+fn recording_info(path: &Path) -> Option<RecordingInfo> {
+    let format = RecordingFormat::from_path(path)?;
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (duration_ms, sample_rate, channels) = read_header(path, format).unwrap_or_default();
+
+    Some(RecordingInfo {
+        filename: path.file_name()?.to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified,
+        format,
+        duration_ms,
+        sample_rate,
+        channels,
+        metadata: load_metadata(path),
+    })
+}
+
+/// List available audio input devices
+#[tauri::command]
+pub async fn list_input_devices() -> Result<Vec<InputDevice>, String> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to enumerate input devices: {}", e))?;
+
+    let mut result = Vec::new();
+    for device in devices {
+        let Ok(name) = device.name() else {
+            continue;
+        };
+        // Devices without a usable default config can't be recorded from
+        let Ok(config) = device.default_input_config() else {
+            continue;
+        };
+        result.push(InputDevice {
+            id: name.clone(),
+            is_default: default_name.as_deref() == Some(name.as_str()),
+            name,
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+            sample_format: format!("{:?}", config.sample_format()).to_lowercase(),
+        });
+    }
+
+    Ok(result)
+}
+
+/// Find an input device by id (its name), or the default device
+fn find_input_device(device_id: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match device_id {
+        Some(id) => host
+            .input_devices()
+            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?
+            .find(|d| d.name().map(|n| n == id).unwrap_or(false))
+            .ok_or_else(|| format!("Input device not found: {}", id)),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| "No input device found".to_string()),
+    }
+}
+
+/// Get the recorder settings
+#[tauri::command]
+pub fn get_recorder_settings() -> RecorderSettings {
+    load_settings()
+}
+
+/// Update the recorder settings
+#[tauri::command]
+pub fn set_recorder_settings(settings: RecorderSettings) -> Result<(), String> {
+    let path = settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    write_atomic(&path, json).map_err(|e| format!("Failed to save recorder settings: {}", e))
 }
 
-/// Start recording audio
+/// Start recording audio (from `device_id`, or the default input device).
+/// `format` and `auto_stop` override the recorder settings for this recording.
 #[tauri::command]
-pub async fn start_recording(app: AppHandle) -> Result<String, String> {
+pub async fn start_recording(
+    app: AppHandle,
+    device_id: Option<String>,
+    format: Option<RecordingFormat>,
+    auto_stop: Option<bool>,
+) -> Result<String, String> {
     if RECORDING.load(Ordering::SeqCst) {
         return Ok("Already recording".to_string());
     }
 
+    // Refuse to start when the disk is critically full or the quota is used up
+    storage::check_capture_allowed(&app, MediaKind::Recordings)?;
+
+    // Fails while the voice agent is in a session; pauses its wakeword detection otherwise
+    let mic_app = app.clone();
+    let mic =
+        tauri::async_runtime::spawn_blocking(move || mic::acquire(&mic_app, MicOwner::Recorder))
+            .await
+            .map_err(|e| format!("Failed to acquire microphone: {}", e))??;
+
     STOP_RECORDING.store(false, Ordering::SeqCst);
+    PAUSED.store(false, Ordering::SeqCst);
+    CAPTURED_FRAMES.store(0, Ordering::SeqCst);
+    RECORDING_METER.reset();
+    *LAST_SAVED.lock() = None;
 
-    // Clear previous samples
-    {
-        let mut samples = RECORDING_SAMPLES.lock();
-        samples.clear();
+    let mut settings = load_settings();
+    if let Some(format) = format {
+        settings.format = format;
+    }
+    if let Some(auto_stop) = auto_stop {
+        settings.vad.auto_stop = auto_stop;
     }
 
     let app_handle = app.clone();
     thread::spawn(move || {
-        run_recording(app_handle);
+        run_recording(app_handle, device_id, settings, mic);
     });
 
     Ok("Recording started".to_string())
@@ -72,76 +455,69 @@ pub async fn start_recording(app: AppHandle) -> Result<String, String> {
 
 /// Stop recording and save
 #[tauri::command]
-pub async fn stop_recording(app: AppHandle) -> Result<RecordingSaved, String> {
+pub async fn stop_recording() -> Result<RecordingSaved, String> {
     if !RECORDING.load(Ordering::SeqCst) {
         return Err("Not recording".to_string());
     }
 
     STOP_RECORDING.store(true, Ordering::SeqCst);
 
-    // Wait for recording thread to finish
-    let mut attempts = 0;
-    while RECORDING.load(Ordering::SeqCst) && attempts < 100 {
-        thread::sleep(Duration::from_millis(50));
-        attempts += 1;
+    // Wait for recording thread to finalize the file (transcoding can take a while)
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    while RECORDING.load(Ordering::SeqCst) && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(50)).await;
     }
 
-    // Get recorded samples
-    let samples = {
-        let guard = RECORDING_SAMPLES.lock();
-        guard.clone()
-    };
-    let rate = *SAMPLE_RATE.lock();
-    let ch = *CHANNELS.lock();
+    let result = LAST_SAVED
+        .lock()
+        .take()
+        .ok_or("Recording failed to stop in time")?;
 
-    if samples.is_empty() {
-        let result = RecordingSaved {
-            path: String::new(),
-            filename: String::new(),
-            duration_ms: 0,
-            success: false,
-            error: Some("No audio data recorded".to_string()),
-        };
-        let _ = app.emit("recording-saved", result.clone());
-        return Ok(result);
+    // Saving failed outright (audio was captured but nothing is on disk)
+    if !result.success && result.path.is_empty() && result.duration_ms > 0 {
+        return Err(result.error.unwrap_or_default());
     }
+    Ok(result)
+}
 
-    let duration_ms = (samples.len() as u64 * 1000) / (rate as u64 * ch as u64);
+/// Pause recording (the input stream stays open, samples are discarded)
+#[tauri::command]
+pub async fn pause_recording(app: AppHandle) -> Result<RecordingStatus, String> {
+    set_paused(&app, true)
+}
 
-    // Save as WAV
-    match save_wav(&samples, rate, ch) {
-        Ok((path, filename)) => {
-            let result = RecordingSaved {
-                path,
-                filename,
-                duration_ms,
-                success: true,
-                error: None,
-            };
-            let _ = app.emit("recording-saved", result.clone());
-            Ok(result)
-        }
-        Err(e) => {
-            let result = RecordingSaved {
-                path: String::new(),
-                filename: String::new(),
-                duration_ms,
-                success: false,
-                error: Some(e.clone()),
-            };
-            let _ = app.emit("recording-saved", result.clone());
-            Err(e)
-        }
+/// Resume a paused recording
+#[tauri::command]
+pub async fn resume_recording(app: AppHandle) -> Result<RecordingStatus, String> {
+    set_paused(&app, false)
+}
+
+fn set_paused(app: &AppHandle, paused: bool) -> Result<RecordingStatus, String> {
+    if !RECORDING.load(Ordering::SeqCst) {
+        return Err("Not recording".to_string());
+    }
+
+    PAUSED.store(paused, Ordering::SeqCst);
+
+    // Emit right away so the UI doesn't wait for the next tick
+    let status = current_status();
+    let _ = app.emit("recording-status", status.clone());
+    Ok(status)
+}
+
+fn current_status() -> RecordingStatus {
+    let rate = CAPTURE_RATE.load(Ordering::Relaxed).max(1);
+    RecordingStatus {
+        recording: RECORDING.load(Ordering::SeqCst),
+        paused: PAUSED.load(Ordering::SeqCst),
+        duration_ms: CAPTURED_FRAMES.load(Ordering::Relaxed) * 1000 / rate,
     }
 }
 
 /// List all recordings
 #[tauri::command]
 pub async fn list_recordings() -> Result<Vec<RecordingInfo>, String> {
-    let music_dir = dirs::audio_dir()
-        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
-        .ok_or("Failed to get Music directory")?;
-    let rec_dir = music_dir.join(RECORDINGS_DIR);
+    let rec_dir = recordings_dir()?;
 
     if !rec_dir.exists() {
         return Ok(Vec::new());
@@ -154,23 +530,9 @@ pub async fn list_recordings() -> Result<Vec<RecordingInfo>, String> {
 
     for entry in entries.flatten() {
         let path = entry.path();
-        if let Some(ext) = path.extension() {
-            if ext.to_string_lossy().to_lowercase() == "wav" {
-                if let Ok(metadata) = entry.metadata() {
-                    let modified = metadata
-                        .modified()
-                        .ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0);
-
-                    recordings.push(RecordingInfo {
-                        filename: entry.file_name().to_string_lossy().to_string(),
-                        path: path.to_string_lossy().to_string(),
-                        size: metadata.len(),
-                        modified,
-                    });
-                }
+        if is_recording_file(&path) {
+            if let Some(info) = recording_info(&path) {
+                recordings.push(info);
             }
         }
     }
@@ -183,26 +545,93 @@ pub async fn list_recordings() -> Result<Vec<RecordingInfo>, String> {
 #[tauri::command]
 pub async fn read_audio_file(path: String) -> Result<String, String> {
     use base64::Engine;
+    let format = RecordingFormat::from_path(Path::new(&path))
+        .ok_or("Unsupported audio format")?;
     let data = fs::read(&path).map_err(|e| format!("Failed to read audio: {}", e))?;
     let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
-    Ok(format!("data:audio/wav;base64,{}", b64))
+    Ok(format!("data:{};base64,{}", format.mime_type(), b64))
 }
 
 /// Delete a recording
 #[tauri::command]
 pub async fn delete_recording(path: String) -> Result<bool, String> {
-    let music_dir = dirs::audio_dir()
-        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
-        .ok_or("Failed to get Music directory")?;
-    let rec_dir = music_dir.join(RECORDINGS_DIR);
-    let target = std::path::Path::new(&path);
+    let target = recording_path(&path)?;
+    remove_recording_file(&target)?;
+    Ok(true)
+}
+
+/// Rename a recording. The extension is kept, `name` is the new file stem.
+#[tauri::command]
+pub async fn rename_recording(path: String, name: String) -> Result<RecordingInfo, String> {
+    let source = recording_path(&path)?;
+    let ext = source
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
 
-    if !target.starts_with(&rec_dir) {
-        return Err("Cannot delete files outside recordings directory".to_string());
+    // Accept the name with or without the current extension
+    let name = name.trim();
+    let stem = name
+        .strip_suffix(&format!(".{}", ext))
+        .unwrap_or(name)
+        .trim();
+    if stem.is_empty() || stem.starts_with('.') || stem.contains(['/', '\\', '\0']) {
+        return Err("Invalid recording name".to_string());
     }
 
-    fs::remove_file(&path).map_err(|e| format!("Failed to delete recording: {}", e))?;
-    Ok(true)
+    let target = source.with_file_name(format!("{}.{}", stem, ext));
+    if target == source {
+        return recording_info(&source).ok_or("Recording not found".to_string());
+    }
+    if target.exists() {
+        return Err(format!("A recording named {} already exists", stem));
+    }
+
+    fs::rename(&source, &target).map_err(|e| format!("Failed to rename recording: {}", e))?;
+    for suffix in SIDECAR_SUFFIXES {
+        let sidecar = sidecar_path(&source, suffix);
+        if sidecar.exists() {
+            let _ = fs::rename(&sidecar, sidecar_path(&target, suffix));
+        }
+    }
+    storage::rename_favorite(&source.to_string_lossy(), &target.to_string_lossy());
+
+    recording_info(&target).ok_or("Recording not found".to_string())
+}
+
+/// Set the title, tags and notes of a recording (empty values clear them)
+#[tauri::command]
+pub async fn set_recording_metadata(
+    path: String,
+    metadata: RecordingMetadata,
+) -> Result<RecordingMetadata, String> {
+    let target = recording_path(&path)?;
+    let metadata = metadata.normalized();
+
+    let sidecar = metadata_path(&target);
+    if metadata.is_empty() {
+        if sidecar.exists() {
+            fs::remove_file(&sidecar).map_err(|e| format!("Failed to clear metadata: {}", e))?;
+        }
+    } else {
+        let json = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+        write_atomic(&sidecar, json).map_err(|e| format!("Failed to save metadata: {}", e))?;
+    }
+
+    // Keep the annotations inside WAV files too, so they survive copying off the device
+    if RecordingFormat::from_path(&target) == Some(RecordingFormat::Wav) {
+        let tags = metadata.tags.join("; ");
+        let entries: [(&[u8; 4], &str); 3] = [
+            (b"INAM", metadata.title.as_deref().unwrap_or_default()),
+            (b"IKEY", &tags),
+            (b"ICMT", metadata.notes.as_deref().unwrap_or_default()),
+        ];
+        if let Err(e) = wav::write_info_chunk(&target, &entries) {
+            eprintln!("Failed to write WAV INFO chunk: {}", e);
+        }
+    }
+
+    Ok(metadata)
 }
 
 /// Check if currently recording
@@ -211,133 +640,440 @@ pub async fn is_recording() -> Result<bool, String> {
     Ok(RECORDING.load(Ordering::SeqCst))
 }
 
-fn run_recording(app: AppHandle) {
-    RECORDING.store(true, Ordering::SeqCst);
+/// Finish any recordings interrupted by a crash or power loss.
+/// The WAV header is repaired from the file length and the `.part` suffix dropped.
+/// Half-written FLAC / Opus files are discarded, the WAV they came from is kept.
+pub fn recover_partial_recordings() {
+    let Ok(rec_dir) = recordings_dir() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&rec_dir) else {
+        return;
+    };
 
-    let host = cpal::default_host();
-    let device = match host.default_input_device() {
-        Some(d) => d,
-        None => {
-            let _ = app.emit("recording-error", "No input device found");
-            RECORDING.store(false, Ordering::SeqCst);
-            return;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(final_name) = name.strip_suffix(PARTIAL_SUFFIX) else {
+            continue;
+        };
+
+        let recovered = wav::read_channels(&path).and_then(|ch| wav::repair_header(&path, ch));
+        match recovered {
+            Ok(0) | Err(_) => {
+                println!("🎙️ Discarding empty partial recording {}", name);
+                let _ = fs::remove_file(&path);
+            }
+            Ok(bytes) => {
+                let target = rec_dir.join(final_name);
+                match fs::rename(&path, &target) {
+                    Ok(_) => println!("🎙️ Recovered partial recording {} ({} bytes)", final_name, bytes),
+                    Err(e) => eprintln!("Failed to recover {}: {}", name, e),
+                }
+            }
+        }
+    }
+}
+
+fn finish_recording(app: &AppHandle, result: RecordingSaved) {
+    let _ = app.emit("recording-saved", result.clone());
+    *LAST_SAVED.lock() = Some(result);
+    RECORDING.store(false, Ordering::SeqCst);
+    STOP_RECORDING.store(false, Ordering::SeqCst);
+    PAUSED.store(false, Ordering::SeqCst);
+}
+
+fn fail_recording(app: &AppHandle, error: String) {
+    let _ = app.emit("recording-error", error.clone());
+    finish_recording(
+        app,
+        RecordingSaved {
+            path: String::new(),
+            filename: String::new(),
+            duration_ms: 0,
+            success: false,
+            error: Some(error),
+            stop_reason: StopReason::Error,
+            trimmed_ms: 0,
+        },
+    );
+}
+
+/// Transcode a finalized WAV into `format` next to it and remove the WAV.
+/// Returns the path of the new file.
+fn transcode(wav_path: &Path, format: RecordingFormat) -> Result<PathBuf, String> {
+    let target = wav_path.with_extension(format.extension());
+    let partial = PathBuf::from(format!("{}{}", target.display(), PARTIAL_SUFFIX));
+
+    let encoded = match format {
+        RecordingFormat::Wav => return Ok(wav_path.to_path_buf()),
+        RecordingFormat::Flac => flac::encode_wav(wav_path, &partial),
+        RecordingFormat::Opus => ogg_opus::encode_wav(wav_path, &partial),
+    };
+    if let Err(e) = encoded.and_then(|_| fs::rename(&partial, &target)) {
+        let _ = fs::remove_file(&partial);
+        return Err(format!("Failed to encode {}: {}", format.extension().to_uppercase(), e));
+    }
+
+    if let Err(e) = fs::remove_file(wav_path) {
+        eprintln!("Failed to remove WAV after transcoding: {}", e);
+    }
+    Ok(target)
+}
+
+/// Trim leading and trailing silence from a finalized WAV in place.
+/// Returns the milliseconds removed. A recording that is silent throughout is left alone.
+fn trim_silence(path: &Path, vad: &VadSettings) -> std::io::Result<u64> {
+    let mut reader = wav::WavReader::open(path)?;
+    let info = reader.info().clone();
+    let rate = info.sample_rate.max(1) as u64;
+    let window = (rate * TRIM_WINDOW_MS / 1000).max(1);
+    let threshold = 10f64.powf(vad.silence_threshold_db as f64 / 20.0) * 32768.0;
+
+    // First and last window loud enough to be speech
+    let mut voiced: Option<(u64, u64)> = None;
+    let mut buf = Vec::new();
+    let mut index = 0u64;
+    while reader.read_frames(&mut buf, window as usize)? > 0 {
+        let sum_sq: f64 = buf.iter().map(|&s| s as f64 * s as f64).sum();
+        if (sum_sq / buf.len() as f64).sqrt() >= threshold {
+            voiced = Some((voiced.map_or(index, |(first, _)| first), index));
         }
+        index += 1;
+    }
+    drop(reader);
+
+    let Some((first, last)) = voiced else {
+        return Ok(0);
+    };
+    let total = info.frames();
+    let padding = rate * vad.trim_padding_ms / 1000;
+    let start = (first * window).saturating_sub(padding);
+    let end = ((last + 1) * window + padding).min(total);
+    if start == 0 && end == total {
+        return Ok(0);
+    }
+
+    wav::crop(path, start, end)?;
+    Ok((total - (end - start)) * 1000 / rate)
+}
+
+/// `mic` is held until the input stream is closed (dropped on every early return)
+fn run_recording(
+    app: AppHandle,
+    device_id: Option<String>,
+    settings: RecorderSettings,
+    mic: MicLease,
+) {
+    RECORDING.store(true, Ordering::SeqCst);
+
+    let device = match find_input_device(device_id.as_deref()) {
+        Ok(d) => d,
+        Err(e) => return fail_recording(&app, e),
     };
 
     let config = match device.default_input_config() {
         Ok(c) => c,
-        Err(e) => {
-            let _ = app.emit("recording-error", format!("Failed to get input config: {}", e));
-            RECORDING.store(false, Ordering::SeqCst);
-            return;
-        }
+        Err(e) => return fail_recording(&app, format!("Failed to get input config: {}", e)),
     };
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels();
+    CAPTURE_RATE.store(sample_rate as u64, Ordering::Relaxed);
 
-    // Store config for WAV saving
-    {
-        *SAMPLE_RATE.lock() = config.sample_rate().0;
-        *CHANNELS.lock() = config.channels();
+    // Open the output file up front, samples are streamed into it
+    let rec_dir = match recordings_dir() {
+        Ok(d) => d,
+        Err(e) => return fail_recording(&app, e),
+    };
+    if let Err(e) = fs::create_dir_all(&rec_dir) {
+        return fail_recording(&app, format!("Failed to create recordings directory: {}", e));
     }
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let filename = format!("REC_{}.wav", timestamp);
+    let final_path = rec_dir.join(&filename);
+    let partial_path = rec_dir.join(format!("{}{}", filename, PARTIAL_SUFFIX));
 
-    let samples = RECORDING_SAMPLES.clone();
-    let start_time = Instant::now();
-    let app_tick = app.clone();
+    let writer = match WavWriter::create(&partial_path, sample_rate, channels) {
+        Ok(w) => w,
+        Err(e) => return fail_recording(&app, format!("Failed to create WAV file: {}", e)),
+    };
 
-    let stream = match device.build_input_stream(
-        &config.into(),
-        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let mut guard = samples.lock();
-            guard.extend_from_slice(data);
-        },
-        move |err| {
-            eprintln!("Recording stream error: {}", err);
-        },
-        None,
-    ) {
+    // Lock-free ring buffer between the cpal callback and the writer thread
+    let capacity = sample_rate as usize * channels as usize * RING_BUFFER_SECONDS;
+    let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+    let dropped = Arc::new(AtomicU64::new(0));
+    let sink = CaptureSink {
+        producer,
+        dropped: dropped.clone(),
+        channels: channels as usize,
+        scratch: Vec::new(),
+    };
+
+    let sample_format = config.sample_format();
+    let stream_config: cpal::StreamConfig = config.into();
+    let stream = match sample_format {
+        SampleFormat::I8 => build_capture_stream::<i8>(&device, &stream_config, sink),
+        SampleFormat::I16 => build_capture_stream::<i16>(&device, &stream_config, sink),
+        SampleFormat::I32 => build_capture_stream::<i32>(&device, &stream_config, sink),
+        SampleFormat::I64 => build_capture_stream::<i64>(&device, &stream_config, sink),
+        SampleFormat::U8 => build_capture_stream::<u8>(&device, &stream_config, sink),
+        SampleFormat::U16 => build_capture_stream::<u16>(&device, &stream_config, sink),
+        SampleFormat::U32 => build_capture_stream::<u32>(&device, &stream_config, sink),
+        SampleFormat::U64 => build_capture_stream::<u64>(&device, &stream_config, sink),
+        SampleFormat::F32 => build_capture_stream::<f32>(&device, &stream_config, sink),
+        SampleFormat::F64 => build_capture_stream::<f64>(&device, &stream_config, sink),
+        other => Err(format!("Unsupported sample format: {:?}", other)),
+    };
+    let stream = match stream {
         Ok(s) => s,
         Err(e) => {
-            let _ = app.emit("recording-error", format!("Failed to build stream: {}", e));
-            RECORDING.store(false, Ordering::SeqCst);
-            return;
+            let _ = fs::remove_file(&partial_path);
+            return fail_recording(&app, e);
         }
     };
 
+    let input_done = Arc::new(AtomicBool::new(false));
+    let writer_handle = spawn_writer(consumer, writer, input_done.clone());
+
     if let Err(e) = stream.play() {
-        let _ = app.emit("recording-error", format!("Failed to start stream: {}", e));
-        RECORDING.store(false, Ordering::SeqCst);
-        return;
+        drop(stream);
+        input_done.store(true, Ordering::SeqCst);
+        let _ = writer_handle.join();
+        let _ = fs::remove_file(&partial_path);
+        return fail_recording(&app, format!("Failed to start stream: {}", e));
     }
 
-    // Send duration updates
+    // Send level and duration updates, watch for silence
+    let vad = &settings.vad;
+    let silence_timeout = Duration::from_secs_f32(vad.silence_timeout_secs.clamp(0.5, 3600.0));
+    let mut silent_for = Duration::ZERO;
+    let mut stop_reason = StopReason::User;
+    let mut write_error = None;
+    let mut tick: u32 = 0;
     loop {
         if STOP_RECORDING.load(Ordering::SeqCst) {
             break;
         }
-        let elapsed = start_time.elapsed().as_millis() as u64;
-        let _ = app_tick.emit("recording-status", RecordingStatus {
-            recording: true,
-            duration_ms: elapsed,
-        });
-        thread::sleep(Duration::from_millis(200));
+        if writer_handle.is_finished() {
+            // Writer only exits early on an I/O error (e.g. disk full)
+            write_error = Some("Recording stopped: failed to write audio to disk".to_string());
+            stop_reason = StopReason::Error;
+            break;
+        }
+        let level = levels::publish(&app, &RECORDING_METER, "recorder");
+        if vad.auto_stop {
+            // Time spent paused is not silence
+            if PAUSED.load(Ordering::SeqCst) || level.rms_db >= vad.silence_threshold_db {
+                silent_for = Duration::ZERO;
+            } else {
+                silent_for += TICK_INTERVAL;
+                if silent_for >= silence_timeout {
+                    println!("🎙️ Auto-stopping recording after {:.1}s of silence", silent_for.as_secs_f32());
+                    stop_reason = StopReason::Silence;
+                    break;
+                }
+            }
+        }
+        if tick.is_multiple_of(STORAGE_CHECK_EVERY) {
+            if let Err(e) = storage::check_capture_space(&app, MediaKind::Recordings) {
+                eprintln!("🎙️ {}", e);
+                write_error = Some(e);
+                stop_reason = StopReason::StorageFull;
+                break;
+            }
+        }
+        if tick.is_multiple_of(STATUS_EVERY) {
+            let _ = app.emit("recording-status", current_status());
+        }
+        tick = tick.wrapping_add(1);
+        thread::sleep(TICK_INTERVAL);
     }
 
+    // Stop the callback, then let the writer drain what's left and finalize
     drop(stream);
-    RECORDING.store(false, Ordering::SeqCst);
-    STOP_RECORDING.store(false, Ordering::SeqCst);
+    drop(mic);
+    input_done.store(true, Ordering::SeqCst);
+
+    let dropped = dropped.load(Ordering::Relaxed);
+    if dropped > 0 {
+        eprintln!("⚠️ Recorder ring buffer overflowed, dropped {} samples", dropped);
+    }
+
+    // Keep whatever made it to disk if the writer failed part way
+    let recover = |path: &Path| {
+        wav::repair_header(path, channels)
+            .map(|bytes| bytes / (channels as u64 * 2) * 1000 / sample_rate.max(1) as u64)
+            .unwrap_or(0)
+    };
+    let mut duration_ms = match writer_handle.join() {
+        Ok(Ok(ms)) => ms,
+        Ok(Err(e)) => {
+            eprintln!("Recording writer error: {}", e);
+            write_error.get_or_insert_with(|| format!("Failed to write WAV file: {}", e));
+            recover(&partial_path)
+        }
+        Err(_) => {
+            write_error.get_or_insert_with(|| "Recording writer thread panicked".to_string());
+            recover(&partial_path)
+        }
+    };
+
+    if let Some(e) = &write_error {
+        let _ = app.emit("recording-error", e.clone());
+    }
+
+    if duration_ms == 0 {
+        let _ = fs::remove_file(&partial_path);
+        return finish_recording(
+            &app,
+            RecordingSaved {
+                path: String::new(),
+                filename: String::new(),
+                duration_ms: 0,
+                success: false,
+                error: Some("No audio data recorded".to_string()),
+                stop_reason,
+                trimmed_ms: 0,
+            },
+        );
+    }
+
+    let mut trimmed_ms = 0;
+    if vad.trim_silence {
+        match trim_silence(&partial_path, vad) {
+            Ok(ms) => {
+                trimmed_ms = ms;
+                duration_ms = duration_ms.saturating_sub(ms);
+            }
+            Err(e) => eprintln!("Failed to trim silence: {}", e),
+        }
+    }
+
+    let result = match fs::rename(&partial_path, &final_path) {
+        Ok(_) => {
+            let success = write_error.is_none();
+            // The WAV is complete on disk from here, a failed transcode keeps it
+            let saved_path = match transcode(&final_path, settings.format) {
+                Ok(path) => path,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    write_error.get_or_insert(format!("{}, kept the WAV recording", e));
+                    final_path
+                }
+            };
+            RecordingSaved {
+                filename: saved_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or(filename),
+                path: saved_path.to_string_lossy().to_string(),
+                duration_ms,
+                success,
+                error: write_error,
+                stop_reason,
+                trimmed_ms,
+            }
+        }
+        Err(e) => RecordingSaved {
+            path: String::new(),
+            filename: String::new(),
+            duration_ms,
+            success: false,
+            error: Some(format!("Failed to write WAV file: {}", e)),
+            stop_reason,
+            trimmed_ms,
+        },
+    };
+    let saved = result.success.then(|| PathBuf::from(&result.path));
+    finish_recording(&app, result);
+
+    // Have the waveform ready before the recording is first opened
+    if let Some(path) = saved {
+        waveform::warm_cache(&path);
+    }
 }
 
-fn save_wav(samples: &[f32], sample_rate: u32, channels: u16) -> Result<(String, String), String> {
-    let music_dir = dirs::audio_dir()
-        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
-        .ok_or("Failed to get Music directory")?;
-    let rec_dir = music_dir.join(RECORDINGS_DIR);
+/// Audio callback side of the recorder: converts to f32 and feeds the ring buffer
+struct CaptureSink {
+    producer: HeapProd<f32>,
+    dropped: Arc<AtomicU64>,
+    channels: usize,
+    scratch: Vec<f32>,
+}
 
-    if !rec_dir.exists() {
-        fs::create_dir_all(&rec_dir)
-            .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+impl CaptureSink {
+    fn push<T>(&mut self, data: &[T])
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        if PAUSED.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // Reuse the scratch buffer, it only grows on the first callbacks
+        self.scratch.clear();
+        self.scratch.extend(data.iter().map(|s| s.to_sample::<f32>()));
+        RECORDING_METER.process(&self.scratch);
+
+        CAPTURED_FRAMES.fetch_add((data.len() / self.channels) as u64, Ordering::Relaxed);
+        let pushed = self.producer.push_slice(&self.scratch);
+        if pushed < self.scratch.len() {
+            self.dropped
+                .fetch_add((self.scratch.len() - pushed) as u64, Ordering::Relaxed);
+        }
     }
+}
 
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-    let filename = format!("REC_{}.wav", timestamp);
-    let filepath = rec_dir.join(&filename);
-
-    // Write WAV manually
-    let num_samples = samples.len() as u32;
-    let bits_per_sample: u16 = 16;
-    let byte_rate = sample_rate * channels as u32 * (bits_per_sample as u32 / 8);
-    let block_align = channels * (bits_per_sample / 8);
-    let data_size = num_samples * (bits_per_sample as u32 / 8);
-
-    let mut buf = Cursor::new(Vec::new());
-
-    // RIFF header
-    buf.write_all(b"RIFF").map_err(|e| e.to_string())?;
-    buf.write_all(&(36 + data_size).to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(b"WAVE").map_err(|e| e.to_string())?;
-
-    // fmt chunk
-    buf.write_all(b"fmt ").map_err(|e| e.to_string())?;
-    buf.write_all(&16u32.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&1u16.to_le_bytes()).map_err(|e| e.to_string())?; // PCM
-    buf.write_all(&channels.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&sample_rate.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&byte_rate.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&block_align.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&bits_per_sample.to_le_bytes()).map_err(|e| e.to_string())?;
-
-    // data chunk
-    buf.write_all(b"data").map_err(|e| e.to_string())?;
-    buf.write_all(&data_size.to_le_bytes()).map_err(|e| e.to_string())?;
-
-    // Convert f32 samples to i16
-    for &sample in samples {
-        let clamped = sample.clamp(-1.0, 1.0);
-        let val = (clamped * 32767.0) as i16;
-        buf.write_all(&val.to_le_bytes()).map_err(|e| e.to_string())?;
-    }
-
-    fs::write(&filepath, buf.into_inner())
-        .map_err(|e| format!("Failed to write WAV file: {}", e))?;
-
-    Ok((filepath.to_string_lossy().to_string(), filename))
+fn build_capture_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut sink: CaptureSink,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| sink.push(data),
+            move |err| {
+                eprintln!("Recording stream error: {}", err);
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to build stream: {}", e))
+}
+
+/// Writer thread: drains the ring buffer into the WAV file until input is done.
+/// Returns the recorded duration in milliseconds.
+fn spawn_writer(
+    mut consumer: HeapCons<f32>,
+    mut writer: WavWriter,
+    input_done: Arc<AtomicBool>,
+) -> JoinHandle<std::io::Result<u64>> {
+    thread::spawn(move || {
+        let mut buf = vec![0f32; 8192];
+        let mut last_header_update = Instant::now();
+
+        loop {
+            let n = consumer.pop_slice(&mut buf);
+            if n > 0 {
+                writer.write_samples(&buf[..n])?;
+                if last_header_update.elapsed() >= HEADER_UPDATE_INTERVAL {
+                    writer.update_header()?;
+                    last_header_update = Instant::now();
+                }
+                continue;
+            }
+
+            if input_done.load(Ordering::SeqCst) && consumer.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        writer.finalize()
+    })
 }
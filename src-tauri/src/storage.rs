@@ -0,0 +1,552 @@
+//! Storage Manager Module
+//!
+//! Keeps camera.rs / recorder.rs from filling up the disk (16 GB eMMC kiosks):
+//! - Tracks usage per media type (photos, recordings) and free disk space
+//! - Enforces configurable per-type quotas
+//! - Emits `storage-warning` events when quotas or free space run low
+//! - Optionally prunes the oldest non-favorite items
+//! - Refuses new captures when the disk is critically full, and stops running
+//!   recordings when it gets there
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::fsutil::write_atomic;
+use crate::{gallery, recorder};
+
+/// How often the background monitor re-checks usage
+const MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
+const MB: u64 = 1024 * 1024;
+
+lazy_static::lazy_static! {
+    // Last level reported by the monitor, so warnings are only emitted on change
+    static ref LAST_LEVEL: Mutex<StorageLevel> = Mutex::new(StorageLevel::Ok);
+    // Serializes config / favorites file updates
+    static ref CONFIG_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Media types managed by the storage manager
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Photos,
+    Recordings,
+}
+
+impl MediaKind {
+    fn dir(self) -> Result<PathBuf, String> {
+        match self {
+            MediaKind::Photos => gallery::camera_dir(),
+            MediaKind::Recordings => recorder::recordings_dir(),
+        }
+    }
+
+    fn is_media_file(self, path: &Path) -> bool {
+        match self {
+            MediaKind::Photos => gallery::is_image_file(path),
+            MediaKind::Recordings => recorder::is_recording_file(path),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            MediaKind::Photos => "Photos",
+            MediaKind::Recordings => "Recordings",
+        }
+    }
+}
+
+/// Overall storage health
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+/// Storage configuration (~/.config/honeybee/storage.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Max space for photos in MB (None = unlimited)
+    pub photo_quota_mb: Option<u64>,
+    /// Max space for recordings in MB (None = unlimited)
+    pub recording_quota_mb: Option<u64>,
+    /// Warn when a media type reaches this percentage of its quota
+    pub warn_percent: u8,
+    /// Warn when free disk space drops below this (MB)
+    pub low_free_mb: u64,
+    /// Refuse captures when free disk space drops below this (MB)
+    pub critical_free_mb: u64,
+    /// Delete the oldest non-favorite items instead of refusing captures
+    pub auto_prune: bool,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            photo_quota_mb: None,
+            recording_quota_mb: None,
+            warn_percent: 90,
+            low_free_mb: 1024,
+            critical_free_mb: 200,
+            auto_prune: false,
+        }
+    }
+}
+
+impl StorageConfig {
+    fn quota_bytes(&self, kind: MediaKind) -> Option<u64> {
+        match kind {
+            MediaKind::Photos => self.photo_quota_mb,
+            MediaKind::Recordings => self.recording_quota_mb,
+        }
+        .map(|mb| mb * MB)
+    }
+}
+
+/// Usage of a single media type
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaUsage {
+    pub kind: MediaKind,
+    pub bytes: u64,
+    pub files: u32,
+    pub quota_bytes: Option<u64>,
+    /// Percentage of quota used (None if unlimited)
+    pub percent_used: Option<f32>,
+}
+
+/// Full storage report
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    pub photos: MediaUsage,
+    pub recordings: MediaUsage,
+    pub disk_total_bytes: u64,
+    pub disk_free_bytes: u64,
+    pub level: StorageLevel,
+}
+
+impl StorageUsage {
+    fn media(&self, kind: MediaKind) -> &MediaUsage {
+        match kind {
+            MediaKind::Photos => &self.photos,
+            MediaKind::Recordings => &self.recordings,
+        }
+    }
+}
+
+/// `storage-warning` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageWarning {
+    pub level: StorageLevel,
+    pub message: String,
+    pub usage: StorageUsage,
+    /// Files removed by auto-prune (if any)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pruned: Vec<String>,
+}
+
+fn config_dir() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".config/honeybee"))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn config_path() -> PathBuf {
+    config_dir().join("storage.json")
+}
+
+fn favorites_path() -> PathBuf {
+    config_dir().join("favorites.json")
+}
+
+pub fn load_config() -> StorageConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// No file means no favorites. A file that can't be read is an error, so a
+/// damaged one never reads as "nothing is protected".
+fn load_favorites() -> Result<HashSet<String>, String> {
+    let path = favorites_path();
+    let json = match fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_favorites(favorites: &HashSet<String>) -> Result<(), String> {
+    fs::create_dir_all(config_dir()).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    let json = serde_json::to_string_pretty(favorites).map_err(|e| e.to_string())?;
+    write_atomic(&favorites_path(), json).map_err(|e| format!("Failed to save favorites: {}", e))
+}
+
+/// Update a favorite's path after a file was renamed
+pub fn rename_favorite(old_path: &str, new_path: &str) {
+    let _guard = CONFIG_LOCK.lock();
+    let mut favorites = match load_favorites() {
+        Ok(favorites) => favorites,
+        Err(e) => {
+            eprintln!("Favorite not moved to {}: {}", new_path, e);
+            return;
+        }
+    };
+    if favorites.remove(old_path) {
+        favorites.insert(new_path.to_string());
+        if let Err(e) = save_favorites(&favorites) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+/// (total, available) bytes on the filesystem containing `path`
+fn disk_space(path: &Path) -> Option<(u64, u64)> {
+    // statvfs needs an existing path, walk up until we find one
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let c_path = CString::new(existing.as_os_str().as_bytes()).ok()?;
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    let frsize = stat.f_frsize as u64;
+    Some((stat.f_blocks as u64 * frsize, stat.f_bavail as u64 * frsize))
+}
+
+/// Media files of a kind, oldest first: (path, size, modified)
+fn media_files(kind: MediaKind) -> Vec<(PathBuf, u64, u64)> {
+    let Ok(dir) = kind.dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(PathBuf, u64, u64)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            Some((path, metadata.len(), modified))
+        })
+        .collect();
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+    files
+}
+
+fn media_usage(kind: MediaKind, config: &StorageConfig) -> MediaUsage {
+    // Count every file in the directory (sidecars and indexes take space too)
+    let files = media_files(kind);
+    let bytes: u64 = files.iter().map(|(_, size, _)| size).sum();
+    let count = files
+        .iter()
+        .filter(|(path, _, _)| kind.is_media_file(path))
+        .count() as u32;
+    let quota_bytes = config.quota_bytes(kind);
+
+    MediaUsage {
+        kind,
+        bytes,
+        files: count,
+        quota_bytes,
+        percent_used: quota_bytes
+            .filter(|q| *q > 0)
+            .map(|q| (bytes as f64 / q as f64 * 100.0) as f32),
+    }
+}
+
+fn compute_usage(config: &StorageConfig) -> StorageUsage {
+    let photos = media_usage(MediaKind::Photos, config);
+    let recordings = media_usage(MediaKind::Recordings, config);
+
+    let probe = MediaKind::Photos
+        .dir()
+        .or_else(|_| MediaKind::Recordings.dir())
+        .unwrap_or_else(|_| PathBuf::from("/"));
+    let (disk_total_bytes, disk_free_bytes) = disk_space(&probe).unwrap_or((0, u64::MAX));
+
+    let mut level = StorageLevel::Ok;
+    if disk_free_bytes < config.critical_free_mb * MB {
+        level = StorageLevel::Critical;
+    } else if disk_free_bytes < config.low_free_mb * MB {
+        level = StorageLevel::Warning;
+    }
+    for usage in [&photos, &recordings] {
+        if usage.percent_used.unwrap_or(0.0) >= config.warn_percent as f32 {
+            level = level.max(StorageLevel::Warning);
+        }
+    }
+
+    StorageUsage {
+        photos,
+        recordings,
+        disk_total_bytes,
+        disk_free_bytes,
+        level,
+    }
+}
+
+fn delete_media_file(kind: MediaKind, path: &Path) -> Result<(), String> {
+    match kind {
+        MediaKind::Photos => {
+            fs::remove_file(path).map_err(|e| format!("Failed to delete image: {}", e))?;
+            if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+                gallery::remove_from_index(dir, &[name.to_string_lossy().to_string()]);
+            }
+            Ok(())
+        }
+        MediaKind::Recordings => recorder::remove_recording_file(path),
+    }
+}
+
+/// Delete the oldest non-favorite items of `kind` until usage is below the
+/// quota warning level and free space is above the low-space mark.
+/// Returns the deleted paths.
+fn prune(kind: MediaKind, config: &StorageConfig) -> Vec<String> {
+    let favorites = match load_favorites() {
+        Ok(favorites) => favorites,
+        Err(e) => {
+            eprintln!("Not pruning {:?}, favorites unknown: {}", kind, e);
+            return Vec::new();
+        }
+    };
+    let quota = config.quota_bytes(kind);
+    let quota_target = quota.map(|q| q * config.warn_percent.min(100) as u64 / 100);
+
+    let files = media_files(kind);
+    let mut used: u64 = files.iter().map(|(_, size, _)| size).sum();
+    let mut free = kind
+        .dir()
+        .ok()
+        .and_then(|d| disk_space(&d))
+        .map(|(_, free)| free)
+        .unwrap_or(u64::MAX);
+    let free_target = config.low_free_mb * MB;
+
+    let mut pruned = Vec::new();
+    for (path, size, _) in files {
+        let over_quota = quota_target.map(|t| used > t).unwrap_or(false);
+        let low_space = free < free_target;
+        if !over_quota && !low_space {
+            break;
+        }
+
+        let path_str = path.to_string_lossy().to_string();
+        if !kind.is_media_file(&path) || favorites.contains(&path_str) {
+            continue;
+        }
+
+        match delete_media_file(kind, &path) {
+            Ok(_) => {
+                println!("🧹 Pruned {}", path_str);
+                used = used.saturating_sub(size);
+                free = free.saturating_add(size);
+                pruned.push(path_str);
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    pruned
+}
+
+fn emit_warning(app: &AppHandle, level: StorageLevel, message: String, pruned: Vec<String>) {
+    let usage = compute_usage(&load_config());
+    let warning = StorageWarning {
+        level,
+        message,
+        usage,
+        pruned,
+    };
+    if let Err(e) = app.emit("storage-warning", warning) {
+        eprintln!("Failed to emit storage warning: {}", e);
+    }
+}
+
+/// Called by capture commands before writing anything.
+/// Prunes if enabled, emits warnings, and refuses when there is no room.
+pub fn check_capture_allowed(app: &AppHandle, kind: MediaKind) -> Result<(), String> {
+    let config = load_config();
+    let mut usage = compute_usage(&config);
+    let mut pruned = Vec::new();
+
+    let over_quota = usage.media(kind).percent_used.unwrap_or(0.0) >= 100.0;
+
+    if config.auto_prune && (over_quota || usage.level != StorageLevel::Ok) {
+        pruned = prune(kind, &config);
+        usage = compute_usage(&config);
+    }
+
+    let media = usage.media(kind);
+    let error = if usage.disk_free_bytes < config.critical_free_mb * MB {
+        Some(format!(
+            "Storage critically full: {} MB free (minimum {} MB). Delete some photos or recordings.",
+            usage.disk_free_bytes / MB,
+            config.critical_free_mb
+        ))
+    } else if media.percent_used.unwrap_or(0.0) >= 100.0 {
+        Some(format!(
+            "{} storage quota reached ({} MB of {} MB). Delete some items or raise the quota.",
+            kind.label(),
+            media.bytes / MB,
+            media.quota_bytes.unwrap_or(0) / MB
+        ))
+    } else {
+        None
+    };
+
+    match error {
+        Some(message) => {
+            emit_warning(app, StorageLevel::Critical, message.clone(), pruned);
+            Err(message)
+        }
+        None => {
+            if !pruned.is_empty() {
+                emit_warning(
+                    app,
+                    usage.level,
+                    format!("Removed {} old {} to free space", pruned.len(), kind.label().to_lowercase()),
+                    pruned,
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Called periodically while a capture is writing. Only checks free space
+/// (no directory scan, no pruning under a file being written), emits a
+/// warning and returns the error to stop with when the disk is critically full.
+pub fn check_capture_space(app: &AppHandle, kind: MediaKind) -> Result<(), String> {
+    let config = load_config();
+    let Ok(dir) = kind.dir() else {
+        return Ok(());
+    };
+    let (_, disk_free_bytes) = disk_space(&dir).unwrap_or((0, u64::MAX));
+    if disk_free_bytes >= config.critical_free_mb * MB {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Storage critically full: {} MB free (minimum {} MB). {} capture stopped.",
+        disk_free_bytes / MB,
+        config.critical_free_mb,
+        kind.label()
+    );
+    emit_warning(app, StorageLevel::Critical, message.clone(), Vec::new());
+    Err(message)
+}
+
+/// Get current storage usage per media type
+#[tauri::command]
+pub async fn get_storage_usage() -> Result<StorageUsage, String> {
+    Ok(compute_usage(&load_config()))
+}
+
+/// Get the storage configuration
+#[tauri::command]
+pub fn get_storage_config() -> StorageConfig {
+    load_config()
+}
+
+/// Update the storage configuration
+#[tauri::command]
+pub fn set_storage_config(config: StorageConfig) -> Result<(), String> {
+    let _guard = CONFIG_LOCK.lock();
+    fs::create_dir_all(config_dir()).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    write_atomic(&config_path(), json).map_err(|e| format!("Failed to save storage config: {}", e))
+}
+
+/// Mark or unmark a photo / recording as favorite (favorites are never pruned)
+#[tauri::command]
+pub fn set_media_favorite(path: String, favorite: bool) -> Result<(), String> {
+    let _guard = CONFIG_LOCK.lock();
+    let mut favorites = load_favorites()?;
+    // Drop favorites whose files are gone
+    favorites.retain(|p| Path::new(p).exists());
+    if favorite {
+        favorites.insert(path);
+    } else {
+        favorites.remove(&path);
+    }
+    save_favorites(&favorites)
+}
+
+/// List favorite photo / recording paths
+#[tauri::command]
+pub fn list_media_favorites() -> Vec<String> {
+    let mut favorites: Vec<String> = load_favorites()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            HashSet::new()
+        })
+        .into_iter()
+        .collect();
+    favorites.sort();
+    favorites
+}
+
+/// Start the background storage monitor
+/// Re-checks usage periodically, prunes if enabled and emits `storage-warning` on level changes
+pub fn start_storage_monitor(app_handle: AppHandle) {
+    thread::spawn(move || loop {
+        let config = load_config();
+        let mut usage = compute_usage(&config);
+        let mut pruned = Vec::new();
+
+        if config.auto_prune && usage.level != StorageLevel::Ok {
+            for kind in [MediaKind::Photos, MediaKind::Recordings] {
+                pruned.extend(prune(kind, &config));
+            }
+            usage = compute_usage(&config);
+        }
+
+        let changed = {
+            let mut last = LAST_LEVEL.lock();
+            let changed = *last != usage.level;
+            *last = usage.level;
+            changed
+        };
+
+        if (changed && usage.level != StorageLevel::Ok) || !pruned.is_empty() {
+            let message = match usage.level {
+                StorageLevel::Critical => format!(
+                    "Storage critically full: {} MB free",
+                    usage.disk_free_bytes / MB
+                ),
+                StorageLevel::Warning => "Storage running low".to_string(),
+                StorageLevel::Ok => format!("Removed {} old items to free space", pruned.len()),
+            };
+            println!("💾 {}", message);
+            emit_warning(&app_handle, usage.level, message, pruned);
+        }
+
+        thread::sleep(MONITOR_INTERVAL);
+    });
+}
@@ -1,22 +1,123 @@
-// System controls: brightness and volume management
-// 
-// Brightness: Uses KDE's DBus interface (for development)
-// In production (GNOME), this will be swapped to the appropriate interface
+// System controls: volume management and what the device can control
 //
-// Volume: Uses PipeWire (wpctl) -> PulseAudio (pactl) -> ALSA (amixer) fallback chain
-// Only controls speaker OUTPUT volume, never touches microphone/input
+// Brightness lives in brightness.rs (KDE / GNOME / sysfs backlight / DDC/CI backends)
+//
+// Volume: talks to PulseAudio / pipewire-pulse natively over one kept-open
+// connection (pulse.rs). Without a reachable server it falls back to
+// PipeWire (wpctl) -> PulseAudio (pactl) -> ALSA (amixer) subprocesses.
+// The volume commands only control the speaker OUTPUT, the microphone INPUT
+// has commands of its own (gain, privacy mute)
+// Changes from elsewhere are emitted as `volume-changed` / `mic-input-changed`
+//
+// Outputs: sinks of the sound server (wpctl or pactl without a native
+// connection), or ALSA cards through ~/.asoundrc
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::brightness::{self, BrightnessBackendKind};
+use crate::fsutil::write_atomic;
+use crate::player;
+use crate::pulse;
+use crate::voice_agent_ipc::{send_voice_agent_command, VoiceAgentCommand};
+
+/// How long to wait before trying the native connection again after it failed
+const PULSE_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the volume is read when the server can't report changes
+const VOLUME_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
-use std::process::Command;
+/// Highest microphone gain, boosting quiet rooms
+const MAX_INPUT_LEVEL: u8 = 150;
+
+lazy_static::lazy_static! {
+    /// Detected once, the sound server doesn't change while running
+    static ref AUDIO_BACKEND: AudioBackend = detect_audio_backend();
+    static ref PULSE: Mutex<PulseState> = Mutex::new(PulseState::default());
+    /// Level to return to when unmuting
+    static ref PRE_MUTE_LEVEL: Mutex<Option<u8>> = Mutex::new(None);
+    /// Mic mute state the voice agent was last told about
+    static ref LAST_MIC_MUTED: Mutex<Option<bool>> = Mutex::new(None);
+}
+
+#[derive(Default)]
+struct PulseState {
+    connection: Option<pulse::Connection>,
+    last_attempt: Option<Instant>,
+}
 
 /// Audio backend detection
-#[derive(Debug, Clone, Copy)]
-enum AudioBackend {
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioBackend {
     PipeWire,
     PulseAudio,
     Alsa,
 }
 
+/// Run `f` on the native connection, connecting first if needed. None when
+/// there's no server to talk to, so the caller falls back to subprocesses.
+fn with_pulse<T>(
+    f: impl FnOnce(&mut pulse::Connection) -> Result<T, String>,
+) -> Option<Result<T, String>> {
+    let mut state = PULSE.lock();
+    if state.connection.is_none() {
+        if state
+            .last_attempt
+            .is_some_and(|at| at.elapsed() < PULSE_RETRY_INTERVAL)
+        {
+            return None;
+        }
+        state.last_attempt = Some(Instant::now());
+        match pulse::Connection::connect() {
+            Ok(connection) => {
+                println!("🔊 Connected to {}", connection.server_name());
+                state.connection = Some(connection);
+            }
+            Err(e) => {
+                eprintln!(
+                    "Audio: no native connection, using command line tools: {}",
+                    e
+                );
+                return None;
+            }
+        }
+    }
+
+    let connection = state.connection.as_mut()?;
+    let result = f(connection);
+    if connection.is_broken() {
+        // Server restarted or went away: reconnect on the next call
+        eprintln!(
+            "Audio: native connection lost: {}",
+            result.err().unwrap_or_default()
+        );
+        state.connection = None;
+        state.last_attempt = None;
+        return None;
+    }
+    Some(result)
+}
+
 fn detect_audio_backend() -> AudioBackend {
+    // A server we can talk to natively says what it is
+    if let Some(Ok(server)) = with_pulse(|pulse| Ok(pulse.server_name().to_string())) {
+        return if server.contains("PipeWire") {
+            AudioBackend::PipeWire
+        } else {
+            AudioBackend::PulseAudio
+        };
+    }
+
     // Check for PipeWire first (modern systems)
     if Command::new("wpctl")
         .arg("--version")
@@ -41,140 +142,163 @@ fn detect_audio_backend() -> AudioBackend {
     AudioBackend::Alsa
 }
 
-// ============================================================================
-// BRIGHTNESS CONTROL (KDE DBus)
-// ============================================================================
-
-/// Get maximum brightness value from KDE
-fn get_brightness_max_kde() -> Result<i32, String> {
-    let output = Command::new("busctl")
-        .args([
-            "--user",
-            "call",
-            "org.kde.Solid.PowerManagement",
-            "/org/kde/Solid/PowerManagement/Actions/BrightnessControl",
-            "org.kde.Solid.PowerManagement.Actions.BrightnessControl",
-            "brightnessMax",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to call brightnessMax: {}", e))?;
+/// Which backends the system controls use, so the UI can hide what doesn't work
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemCapabilities {
+    pub brightness: bool,
+    pub brightness_backend: Option<BrightnessBackendKind>,
+    /// Why brightness can't be controlled
+    pub brightness_error: Option<String>,
+    pub audio_backend: AudioBackend,
+    /// Volume goes over the native connection instead of subprocesses
+    pub audio_native: bool,
+}
 
-    if !output.status.success() {
-        return Err(format!(
-            "brightnessMax failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+/// Detecting backends runs a few subprocesses (and DDC/CI probes), so this is
+/// async to stay off the main thread
+#[tauri::command]
+pub async fn get_system_capabilities() -> SystemCapabilities {
+    let brightness = brightness::active_backend();
+    SystemCapabilities {
+        brightness: brightness.is_ok(),
+        brightness_backend: brightness.as_ref().ok().copied(),
+        brightness_error: brightness.err(),
+        audio_backend: *AUDIO_BACKEND,
+        audio_native: with_pulse(|_| Ok(())).is_some(),
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    // Output format: "i 19200" (type indicator followed by value)
-    let value_str = stdout
-        .split_whitespace()
-        .nth(1)
-        .ok_or("Invalid brightnessMax response")?;
+// ============================================================================
+// VOLUME CONTROL (PipeWire / PulseAudio / ALSA)
+// ============================================================================
 
-    value_str
-        .parse::<i32>()
-        .map_err(|_| "Failed to parse brightness max value".to_string())
+/// Volume of the speaker output or microphone input, and whether it's muted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct VolumeInfo {
+    /// Percentage (0-100, input gain up to 150), kept while muted
+    pub level: u8,
+    pub muted: bool,
 }
 
-/// Get current brightness value from KDE
-fn get_brightness_current_kde() -> Result<i32, String> {
-    let output = Command::new("busctl")
-        .args([
-            "--user",
-            "call",
-            "org.kde.Solid.PowerManagement",
-            "/org/kde/Solid/PowerManagement/Actions/BrightnessControl",
-            "org.kde.Solid.PowerManagement.Actions.BrightnessControl",
-            "brightness",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to call brightness: {}", e))?;
+/// Which end of the default audio path to control. The output is what the
+/// volume commands change, the input only changes through the microphone
+/// commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Output,
+    Input,
+}
 
-    if !output.status.success() {
-        return Err(format!(
-            "brightness failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+impl Direction {
+    fn max_level(self) -> u8 {
+        match self {
+            Direction::Output => 100,
+            Direction::Input => MAX_INPUT_LEVEL,
+        }
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    // Output format: "i 9600" (type indicator followed by value)
-    let value_str = stdout
-        .split_whitespace()
-        .nth(1)
-        .ok_or("Invalid brightness response")?;
+    fn pulse_name(self) -> &'static str {
+        match self {
+            Direction::Output => pulse::DEFAULT_SINK,
+            Direction::Input => pulse::DEFAULT_SOURCE,
+        }
+    }
 
-    value_str
-        .parse::<i32>()
-        .map_err(|_| "Failed to parse brightness value".to_string())
-}
+    /// SINK = output only, SOURCE = input only
+    fn wpctl_node(self) -> &'static str {
+        match self {
+            Direction::Output => "@DEFAULT_AUDIO_SINK@",
+            Direction::Input => "@DEFAULT_AUDIO_SOURCE@",
+        }
+    }
 
-/// Get current display brightness as percentage (0-100)
-#[tauri::command]
-pub fn get_brightness() -> Result<u8, String> {
-    let max = get_brightness_max_kde()?;
-    let current = get_brightness_current_kde()?;
+    fn pactl_kind(self) -> &'static str {
+        match self {
+            Direction::Output => "sink",
+            Direction::Input => "source",
+        }
+    }
+
+    fn pactl_target(self) -> &'static str {
+        match self {
+            Direction::Output => "@DEFAULT_SINK@",
+            Direction::Input => "@DEFAULT_SOURCE@",
+        }
+    }
 
-    if max <= 0 {
-        return Err("Invalid max brightness value".to_string());
+    fn amixer_control(self) -> &'static str {
+        match self {
+            Direction::Output => "Master",
+            Direction::Input => "Capture",
+        }
     }
 
-    let percentage = ((current as f64 / max as f64) * 100.0).round() as u8;
-    Ok(percentage.clamp(0, 100))
+    /// The switch is "mute" / "unmute" for playback, "nocap" / "cap" for capture
+    fn amixer_switch(self, muted: bool) -> &'static str {
+        match (self, muted) {
+            (Direction::Output, true) => "mute",
+            (Direction::Output, false) => "unmute",
+            (Direction::Input, true) => "nocap",
+            (Direction::Input, false) => "cap",
+        }
+    }
 }
 
-/// Set display brightness (percentage 0-100)
+/// Get current speaker volume as percentage (0-100)
 #[tauri::command]
-pub fn set_brightness(level: u8) -> Result<(), String> {
-    // Clamp to safe range (never fully black, minimum 5%)
-    let safe_level = level.clamp(5, 100);
-
-    let max = get_brightness_max_kde()?;
-    let target_value = ((safe_level as f64 / 100.0) * max as f64).round() as i32;
+pub fn get_volume() -> Result<u8, String> {
+    get_volume_info().map(|info| info.level)
+}
 
-    let output = Command::new("busctl")
-        .args([
-            "--user",
-            "call",
-            "org.kde.Solid.PowerManagement",
-            "/org/kde/Solid/PowerManagement/Actions/BrightnessControl",
-            "org.kde.Solid.PowerManagement.Actions.BrightnessControl",
-            "setBrightness",
-            "i",
-            &target_value.to_string(),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to set brightness: {}", e))?;
+/// Get current speaker volume and mute state
+#[tauri::command]
+pub fn get_volume_info() -> Result<VolumeInfo, String> {
+    read_volume(Direction::Output)
+}
 
-    if !output.status.success() {
-        return Err(format!(
-            "setBrightness failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+fn read_volume(direction: Direction) -> Result<VolumeInfo, String> {
+    if let Some(result) = with_pulse(|pulse| read_volume_native(pulse, direction)) {
+        return result;
     }
 
-    Ok(())
+    match *AUDIO_BACKEND {
+        AudioBackend::PipeWire => read_volume_pipewire(direction),
+        AudioBackend::PulseAudio => read_volume_pulseaudio(direction),
+        AudioBackend::Alsa => read_volume_alsa(direction),
+    }
 }
 
-// ============================================================================
-// VOLUME CONTROL (PipeWire / PulseAudio / ALSA)
-// ============================================================================
-
-/// Get current speaker volume as percentage (0-100)
-#[tauri::command]
-pub fn get_volume() -> Result<u8, String> {
-    match detect_audio_backend() {
-        AudioBackend::PipeWire => get_volume_pipewire(),
-        AudioBackend::PulseAudio => get_volume_pulseaudio(),
-        AudioBackend::Alsa => get_volume_alsa(),
+/// Channel volumes and mute state of the default sink or source
+fn native_volume(
+    pulse: &mut pulse::Connection,
+    direction: Direction,
+) -> Result<(Vec<u32>, bool), String> {
+    let name = direction.pulse_name();
+    match direction {
+        Direction::Output => pulse.sink_info(name).map(|sink| (sink.volume, sink.mute)),
+        Direction::Input => pulse
+            .source_info(name)
+            .map(|source| (source.volume, source.mute)),
     }
 }
 
-fn get_volume_pipewire() -> Result<u8, String> {
+/// Loudest channel, like the desktop mixers show it
+fn read_volume_native(
+    pulse: &mut pulse::Connection,
+    direction: Direction,
+) -> Result<VolumeInfo, String> {
+    let (volume, muted) = native_volume(pulse, direction)?;
+    let loudest = volume.iter().copied().max().unwrap_or(0) as u64;
+    let percent = (loudest * 100 + pulse::VOLUME_NORM as u64 / 2) / pulse::VOLUME_NORM as u64;
+    Ok(VolumeInfo {
+        level: percent.min(direction.max_level() as u64) as u8,
+        muted,
+    })
+}
+
+fn read_volume_pipewire(direction: Direction) -> Result<VolumeInfo, String> {
     let output = Command::new("wpctl")
-        .args(["get-volume", "@DEFAULT_AUDIO_SINK@"])
+        .args(["get-volume", direction.wpctl_node()])
         .output()
         .map_err(|e| format!("wpctl error: {}", e))?;
 
@@ -187,53 +311,82 @@ fn get_volume_pipewire() -> Result<u8, String> {
     // 0.50 = 50%
     for word in stdout.split_whitespace() {
         if let Ok(vol) = word.parse::<f64>() {
-            return Ok((vol * 100.0).round() as u8);
+            return Ok(VolumeInfo {
+                level: ((vol * 100.0).round() as u8).min(direction.max_level()),
+                muted: stdout.contains("[MUTED]"),
+            });
         }
     }
 
     Err("Failed to parse wpctl volume".to_string())
 }
 
-fn get_volume_pulseaudio() -> Result<u8, String> {
+fn read_volume_pulseaudio(direction: Direction) -> Result<VolumeInfo, String> {
+    let kind = direction.pactl_kind();
     let output = Command::new("pactl")
-        .args(["get-sink-volume", "@DEFAULT_SINK@"])
+        .args([&format!("get-{}-volume", kind), direction.pactl_target()])
+        .env("LC_ALL", "C")
         .output()
         .map_err(|e| format!("pactl error: {}", e))?;
 
     if !output.status.success() {
-        return Err("pactl get-sink-volume failed".to_string());
+        return Err(format!("pactl get-{}-volume failed", kind));
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     // Output contains percentage like "50%"
-    for word in stdout.split_whitespace() {
-        if word.ends_with('%') {
-            if let Ok(vol) = word.trim_end_matches('%').parse::<u8>() {
-                return Ok(vol.min(100));
-            }
-        }
+    let level = stdout
+        .split_whitespace()
+        .filter_map(|word| word.strip_suffix('%'))
+        .find_map(|word| word.parse::<u8>().ok())
+        .ok_or("Failed to parse pactl volume")?;
+
+    let output = Command::new("pactl")
+        .args([&format!("get-{}-mute", kind), direction.pactl_target()])
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("pactl error: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("pactl get-{}-mute failed", kind));
     }
 
-    Err("Failed to parse pactl volume".to_string())
+    // Output format: "Mute: yes" or "Mute: no", translated outside the C locale
+    Ok(VolumeInfo {
+        level: level.min(direction.max_level()),
+        muted: String::from_utf8_lossy(&output.stdout).contains("yes"),
+    })
 }
 
-fn get_volume_alsa() -> Result<u8, String> {
+fn read_volume_alsa(direction: Direction) -> Result<VolumeInfo, String> {
+    let control = direction.amixer_control();
     let output = Command::new("amixer")
-        .args(["get", "Master"])
+        .args(["get", control])
         .output()
         .map_err(|e| format!("amixer error: {}", e))?;
 
     if !output.status.success() {
-        return Err("amixer get Master failed".to_string());
+        return Err(format!("amixer get {} failed", control));
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    // Output contains "[50%]"
+    // Output contains "[50%] [-20.00dB] [on]", the switch is "[off]" when muted
     for line in stdout.lines() {
         if let Some(start) = line.find('[') {
             if let Some(end) = line[start..].find('%') {
                 if let Ok(vol) = line[start + 1..start + end].parse::<u8>() {
-                    return Ok(vol.min(100));
+                    // Output controls without a switch are muted by zeroing them
+                    let muted = if line.contains("[off]") || line.contains("[on]") {
+                        line.contains("[off]")
+                    } else {
+                        direction == Direction::Output
+                            && vol == 0
+                            && PRE_MUTE_LEVEL.lock().is_some()
+                    };
+                    return Ok(VolumeInfo {
+                        level: vol.min(direction.max_level()),
+                        muted,
+                    });
                 }
             }
         }
@@ -246,23 +399,53 @@ fn get_volume_alsa() -> Result<u8, String> {
 /// NOTE: This only affects OUTPUT volume, never touches microphone/input
 #[tauri::command]
 pub fn set_volume(level: u8) -> Result<(), String> {
-    let safe_level = level.clamp(0, 100);
+    write_volume(Direction::Output, level.min(100))
+}
+
+fn write_volume(direction: Direction, level: u8) -> Result<(), String> {
+    if let Some(result) = with_pulse(|pulse| write_volume_native(pulse, direction, level)) {
+        return result;
+    }
 
-    match detect_audio_backend() {
-        AudioBackend::PipeWire => set_volume_pipewire(safe_level),
-        AudioBackend::PulseAudio => set_volume_pulseaudio(safe_level),
-        AudioBackend::Alsa => set_volume_alsa(safe_level),
+    match *AUDIO_BACKEND {
+        AudioBackend::PipeWire => write_volume_pipewire(direction, level),
+        AudioBackend::PulseAudio => write_volume_pulseaudio(direction, level),
+        AudioBackend::Alsa => write_volume_alsa(direction, level),
     }
 }
 
-fn set_volume_pipewire(level: u8) -> Result<(), String> {
+/// Scales all channels so the loudest lands on `level`, keeping the balance
+fn write_volume_native(
+    pulse: &mut pulse::Connection,
+    direction: Direction,
+    level: u8,
+) -> Result<(), String> {
+    let (current, _) = native_volume(pulse, direction)?;
+    let target = pulse::VOLUME_NORM as u64 * level as u64 / 100;
+    let loudest = current.iter().copied().max().unwrap_or(0) as u64;
+    let volume: Vec<u32> = current
+        .iter()
+        .map(|&channel| match loudest {
+            0 => target as u32,
+            _ => (channel as u64 * target / loudest) as u32,
+        })
+        .collect();
+
+    let name = direction.pulse_name();
+    match direction {
+        Direction::Output => pulse.set_sink_volume(name, &volume),
+        Direction::Input => pulse.set_source_volume(name, &volume),
+    }
+}
+
+fn write_volume_pipewire(direction: Direction, level: u8) -> Result<(), String> {
     // Convert percentage to decimal (50% = 0.5)
     let decimal = level as f64 / 100.0;
 
     let output = Command::new("wpctl")
         .args([
             "set-volume",
-            "@DEFAULT_AUDIO_SINK@", // SINK = output only, never touches SOURCE/input
+            direction.wpctl_node(),
             &format!("{:.2}", decimal),
         ])
         .output()
@@ -278,11 +461,12 @@ fn set_volume_pipewire(level: u8) -> Result<(), String> {
     Ok(())
 }
 
-fn set_volume_pulseaudio(level: u8) -> Result<(), String> {
+fn write_volume_pulseaudio(direction: Direction, level: u8) -> Result<(), String> {
+    let command = format!("set-{}-volume", direction.pactl_kind());
     let output = Command::new("pactl")
         .args([
-            "set-sink-volume", // SINK = output only
-            "@DEFAULT_SINK@",
+            command.as_str(),
+            direction.pactl_target(),
             &format!("{}%", level),
         ])
         .output()
@@ -290,7 +474,121 @@ fn set_volume_pulseaudio(level: u8) -> Result<(), String> {
 
     if !output.status.success() {
         return Err(format!(
-            "pactl set-sink-volume failed: {}",
+            "pactl {} failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+fn write_volume_alsa(direction: Direction, level: u8) -> Result<(), String> {
+    let control = direction.amixer_control();
+    let output = Command::new("amixer")
+        .args(["set", control, &format!("{}%", level)])
+        .output()
+        .map_err(|e| format!("amixer error: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "amixer set {} failed: {}",
+            control,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// MUTE
+// ============================================================================
+
+/// Mute or unmute the speaker output. Unmuting returns to the level that was
+/// active before muting, in case it was turned down meanwhile (or zeroed to
+/// mute an ALSA control without a switch)
+#[tauri::command]
+pub fn set_muted(muted: bool) -> Result<(), String> {
+    let current = get_volume_info()?;
+
+    if muted {
+        if !current.muted && current.level > 0 {
+            *PRE_MUTE_LEVEL.lock() = Some(current.level);
+        }
+        return write_mute(Direction::Output, true);
+    }
+
+    write_mute(Direction::Output, false)?;
+    let saved = PRE_MUTE_LEVEL.lock().take();
+    match saved {
+        Some(level) if level != current.level => set_volume(level),
+        _ => Ok(()),
+    }
+}
+
+/// Flip the mute state, returns the new state for the UI
+#[tauri::command]
+pub fn toggle_mute() -> Result<VolumeInfo, String> {
+    let muted = get_volume_info()?.muted;
+    set_muted(!muted)?;
+    get_volume_info()
+}
+
+fn write_mute(direction: Direction, muted: bool) -> Result<(), String> {
+    let result = with_pulse(|pulse| {
+        let name = direction.pulse_name();
+        match direction {
+            Direction::Output => pulse.set_sink_mute(name, muted),
+            Direction::Input => pulse.set_source_mute(name, muted),
+        }
+    });
+    if let Some(result) = result {
+        return result;
+    }
+
+    match *AUDIO_BACKEND {
+        AudioBackend::PipeWire => write_mute_pipewire(direction, muted),
+        AudioBackend::PulseAudio => write_mute_pulseaudio(direction, muted),
+        AudioBackend::Alsa => write_mute_alsa(direction, muted),
+    }
+}
+
+fn write_mute_pipewire(direction: Direction, muted: bool) -> Result<(), String> {
+    let output = Command::new("wpctl")
+        .args([
+            "set-mute",
+            direction.wpctl_node(),
+            if muted { "1" } else { "0" },
+        ])
+        .output()
+        .map_err(|e| format!("wpctl error: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "wpctl set-mute failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+fn write_mute_pulseaudio(direction: Direction, muted: bool) -> Result<(), String> {
+    let command = format!("set-{}-mute", direction.pactl_kind());
+    let output = Command::new("pactl")
+        .args([
+            command.as_str(),
+            direction.pactl_target(),
+            if muted { "1" } else { "0" },
+        ])
+        .output()
+        .map_err(|e| format!("pactl error: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "pactl {} failed: {}",
+            command,
             String::from_utf8_lossy(&output.stderr)
         ));
     }
@@ -298,18 +596,840 @@ fn set_volume_pulseaudio(level: u8) -> Result<(), String> {
     Ok(())
 }
 
-fn set_volume_alsa(level: u8) -> Result<(), String> {
+fn write_mute_alsa(direction: Direction, muted: bool) -> Result<(), String> {
+    let control = direction.amixer_control();
+    let switch = direction.amixer_switch(muted);
     let output = Command::new("amixer")
-        .args(["set", "Master", &format!("{}%", level)])
+        .args(["set", control, switch])
         .output()
         .map_err(|e| format!("amixer error: {}", e))?;
 
     if !output.status.success() {
         return Err(format!(
-            "amixer set Master failed: {}",
+            "amixer set {} {} failed: {}",
+            control,
+            switch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // Controls without a switch accept this and stay on
+    if muted && !read_volume_alsa(direction)?.muted {
+        match direction {
+            Direction::Output => write_volume_alsa(direction, 0)?,
+            // Zeroing isn't a mute anyone should rely on for privacy
+            Direction::Input => {
+                return Err(format!("The {} control has no mute switch", control));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// MICROPHONE INPUT
+// ============================================================================
+
+/// Get microphone gain (percentage 0-150) and whether it's muted
+#[tauri::command]
+pub fn get_input_volume_info() -> Result<VolumeInfo, String> {
+    read_volume(Direction::Input)
+}
+
+/// Set microphone gain (percentage 0-150, above 100 boosts quiet rooms)
+#[tauri::command]
+pub fn set_input_volume(level: u8) -> Result<(), String> {
+    write_volume(Direction::Input, level.min(MAX_INPUT_LEVEL))
+}
+
+/// Privacy mute, on the input device itself so nothing can record
+#[tauri::command]
+pub fn set_input_muted(muted: bool) -> Result<(), String> {
+    write_mute(Direction::Input, muted)?;
+    println!("🎙️ Microphone {}", if muted { "muted" } else { "unmuted" });
+    notify_mic_muted(muted);
+    Ok(())
+}
+
+/// Flip the microphone mute, returns the new state for the UI
+#[tauri::command]
+pub fn toggle_input_mute() -> Result<VolumeInfo, String> {
+    let muted = get_input_volume_info()?.muted;
+    set_input_muted(!muted)?;
+    get_input_volume_info()
+}
+
+/// Tell the voice agent when the mic mute changes, here or elsewhere (a
+/// hardware mute key, pactl over SSH). Retried on the next change until the
+/// agent got it.
+fn notify_mic_muted(muted: bool) {
+    let mut last = LAST_MIC_MUTED.lock();
+    if *last == Some(muted) {
+        return;
+    }
+    match send_voice_agent_command(&VoiceAgentCommand::MicMuted { muted }) {
+        Ok(()) => *last = Some(muted),
+        Err(e) => eprintln!("Audio: voice agent not told about mic mute: {}", e),
+    }
+}
+
+// ============================================================================
+// AUDIO OUTPUTS
+// ============================================================================
+
+/// Marks an ~/.asoundrc written here, any other one is left alone
+const ASOUNDRC_MARKER: &str = "# Managed by Honeybee (audio output selection)";
+
+/// A port of an output, e.g. the speakers or the headphone jack of a sound card
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioPort {
+    pub id: String,
+    pub name: String,
+    /// Whether something is plugged in, None when the hardware can't tell
+    pub available: Option<bool>,
+}
+
+/// A device sound can be played on
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioOutput {
+    /// Sink name, or "hw:CARD,DEVICE" on plain ALSA
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+    /// False when every port reports nothing plugged in
+    pub available: Option<bool>,
+    pub ports: Vec<AudioPort>,
+    pub active_port: Option<String>,
+}
+
+fn output_availability(ports: &[AudioPort]) -> Option<bool> {
+    if ports.is_empty() || ports.iter().all(|port| port.available.is_none()) {
+        return None;
+    }
+    Some(ports.iter().any(|port| port.available != Some(false)))
+}
+
+/// List the outputs, the default one marked
+#[tauri::command]
+pub fn list_audio_outputs() -> Result<Vec<AudioOutput>, String> {
+    Ok(audio_outputs()?
+        .into_iter()
+        .map(|(_, output)| output)
+        .collect())
+}
+
+/// The outputs with their node id when listed through wpctl
+fn audio_outputs() -> Result<Vec<(Option<u32>, AudioOutput)>, String> {
+    let without_node =
+        |outputs: Vec<AudioOutput>| outputs.into_iter().map(|output| (None, output)).collect();
+    if let Some(result) = with_pulse(list_audio_outputs_native) {
+        return result.map(without_node);
+    }
+
+    match *AUDIO_BACKEND {
+        AudioBackend::PipeWire => Ok(list_audio_outputs_wpctl()?
+            .into_iter()
+            .map(|(node, output)| (Some(node), output))
+            .collect()),
+        AudioBackend::PulseAudio => list_audio_outputs_pactl().map(without_node),
+        AudioBackend::Alsa => list_audio_outputs_alsa().map(without_node),
+    }
+}
+
+fn list_audio_outputs_native(pulse: &mut pulse::Connection) -> Result<Vec<AudioOutput>, String> {
+    let default = pulse.default_sink_name()?;
+    let sinks = pulse.sinks()?;
+    Ok(sinks
+        .into_iter()
+        .map(|sink| {
+            let ports: Vec<AudioPort> = sink
+                .ports
+                .into_iter()
+                .map(|port| AudioPort {
+                    id: port.name,
+                    name: port.description,
+                    available: port.available,
+                })
+                .collect();
+            AudioOutput {
+                is_default: default.as_deref() == Some(sink.name.as_str()),
+                available: output_availability(&ports),
+                id: sink.name,
+                name: sink.description,
+                ports,
+                active_port: sink.active_port,
+            }
+        })
+        .collect())
+}
+
+/// Sinks of `wpctl status` with their node ids, wpctl doesn't show ports
+fn list_audio_outputs_wpctl() -> Result<Vec<(u32, AudioOutput)>, String> {
+    let output = Command::new("wpctl")
+        .arg("status")
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("wpctl error: {}", e))?;
+
+    if !output.status.success() {
+        return Err("wpctl status failed".to_string());
+    }
+
+    let mut outputs = Vec::new();
+    for (node, is_default) in parse_wpctl_status(&String::from_utf8_lossy(&output.stdout)) {
+        let properties = wpctl_inspect(node)?;
+        let property = |key: &str| {
+            properties
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+        };
+        let Some(id) = property("node.name") else {
+            continue;
+        };
+        let name = property("node.description").unwrap_or_else(|| id.clone());
+        outputs.push((
+            node,
+            AudioOutput {
+                id,
+                name,
+                is_default,
+                available: None,
+                ports: Vec::new(),
+                active_port: None,
+            },
+        ));
+    }
+    Ok(outputs)
+}
+
+/// Node ids of the sinks in the Audio section of `wpctl status`, and whether
+/// each is the default. Lines look like
+///   Audio
+///    ├─ Sinks:
+///    │  *   47. Built-in Audio Analog Stereo        [vol: 0.40]
+///    │      52. HDMI Audio                          [vol: 1.00]
+/// where `*` marks the default.
+fn parse_wpctl_status(status: &str) -> Vec<(u32, bool)> {
+    let mut section = "";
+    let mut in_sinks = false;
+    let mut sinks = Vec::new();
+    for line in status.lines() {
+        if line.starts_with(|c: char| c.is_alphanumeric()) {
+            section = line.trim();
+            in_sinks = false;
+            continue;
+        }
+        let entry = line.trim_start_matches(|c: char| c.is_whitespace() || "│├└─".contains(c));
+        if line.contains("─ ") {
+            in_sinks = section == "Audio" && entry == "Sinks:";
+            continue;
+        }
+        if !in_sinks {
+            continue;
+        }
+
+        let (is_default, entry) = match entry.strip_prefix('*') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, entry),
+        };
+        if let Some(node) = entry.split_once('.').and_then(|(id, _)| id.parse().ok()) {
+            sinks.push((node, is_default));
+        }
+    }
+    sinks
+}
+
+fn wpctl_inspect(node: u32) -> Result<Vec<(String, String)>, String> {
+    let output = Command::new("wpctl")
+        .args(["inspect", &node.to_string()])
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("wpctl error: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("wpctl inspect {} failed", node));
+    }
+
+    Ok(parse_wpctl_inspect(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Properties of a node from `wpctl inspect`, lines like
+///   * node.name = "alsa_output.pci-0000_00_1f.3.analog-stereo"
+fn parse_wpctl_inspect(inspect: &str) -> Vec<(String, String)> {
+    inspect
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim_start_matches([' ', '*']).split_once(" = ")?;
+            Some((key.to_string(), value.trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+fn list_audio_outputs_pactl() -> Result<Vec<AudioOutput>, String> {
+    let output = Command::new("pactl")
+        .args(["get-default-sink"])
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("pactl error: {}", e))?;
+    let default = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let output = Command::new("pactl")
+        .args(["list", "sinks"])
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("pactl error: {}", e))?;
+
+    if !output.status.success() {
+        return Err("pactl list sinks failed".to_string());
+    }
+
+    Ok(parse_pactl_sinks(
+        &String::from_utf8_lossy(&output.stdout),
+        &default,
+    ))
+}
+
+fn parse_pactl_sinks(sinks: &str, default: &str) -> Vec<AudioOutput> {
+    // Blocks like:
+    //   Sink #0
+    //       Name: alsa_output.pci-0000_00_1f.3.analog-stereo
+    //       Description: Built-in Audio Analog Stereo
+    //       Ports:
+    //           analog-output-speaker: Speakers (type: Speaker, priority: 10000, availability unknown)
+    //           analog-output-headphones: Headphones (type: Headphones, priority: 9900, not available)
+    //       Active Port: analog-output-speaker
+    let mut outputs: Vec<AudioOutput> = Vec::new();
+    let mut in_ports = false;
+    for line in sinks.lines() {
+        if line.starts_with("Sink #") {
+            outputs.push(AudioOutput {
+                id: String::new(),
+                name: String::new(),
+                is_default: false,
+                available: None,
+                ports: Vec::new(),
+                active_port: None,
+            });
+            in_ports = false;
+            continue;
+        }
+        let Some(current) = outputs.last_mut() else {
+            continue;
+        };
+        let trimmed = line.trim();
+
+        if in_ports && line.starts_with("\t\t") {
+            if let Some((id, rest)) = trimmed.split_once(": ") {
+                let name = rest.split(" (").next().unwrap_or(rest).to_string();
+                let available = if rest.contains("not available") {
+                    Some(false)
+                } else if rest.contains("availability unknown") {
+                    None
+                } else {
+                    Some(true)
+                };
+                current.ports.push(AudioPort {
+                    id: id.to_string(),
+                    name,
+                    available,
+                });
+            }
+            continue;
+        }
+        in_ports = false;
+
+        if let Some(name) = trimmed.strip_prefix("Name: ") {
+            current.is_default = name == default;
+            current.id = name.to_string();
+        } else if let Some(description) = trimmed.strip_prefix("Description: ") {
+            current.name = description.to_string();
+        } else if let Some(port) = trimmed.strip_prefix("Active Port: ") {
+            current.active_port = Some(port.to_string());
+        } else if trimmed == "Ports:" {
+            in_ports = true;
+        }
+    }
+
+    for output in &mut outputs {
+        output.available = output_availability(&output.ports);
+    }
+    outputs
+}
+
+/// Text in the first pair of brackets, "HDA Intel PCH" in "PCH [HDA Intel PCH]"
+fn bracketed(text: &str) -> Option<&str> {
+    let start = text.find('[')? + 1;
+    let end = start + text[start..].find(']')?;
+    Some(&text[start..end])
+}
+
+fn asoundrc_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".asoundrc"))
+        .unwrap_or_else(|| PathBuf::from(".asoundrc"))
+}
+
+/// Card and device set in our ~/.asoundrc, ALSA's default is the first card
+fn alsa_default() -> (u32, u32) {
+    let content = fs::read_to_string(asoundrc_path()).unwrap_or_default();
+    if !content.starts_with(ASOUNDRC_MARKER) {
+        return (0, 0);
+    }
+    let value = |key: &str| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(0)
+    };
+    (value("defaults.pcm.card"), value("defaults.pcm.device"))
+}
+
+fn list_audio_outputs_alsa() -> Result<Vec<AudioOutput>, String> {
+    let output = Command::new("aplay")
+        .arg("-l")
+        .output()
+        .map_err(|e| format!("aplay error: {}", e))?;
+
+    if !output.status.success() {
+        return Err("aplay -l failed".to_string());
+    }
+
+    let (card, device) = alsa_default();
+    let default = format!("hw:{},{}", card, device);
+    let mut outputs = parse_aplay_list(&String::from_utf8_lossy(&output.stdout));
+    for output in &mut outputs {
+        output.is_default = output.id == default;
+    }
+    Ok(outputs)
+}
+
+/// Lines like "card 0: PCH [HDA Intel PCH], device 3: HDMI 0 [HDMI 0]"
+fn parse_aplay_list(list: &str) -> Vec<AudioOutput> {
+    list.lines()
+        .filter_map(|line| {
+            let rest = line.strip_prefix("card ")?;
+            let (card, rest) = rest.split_once(": ")?;
+            let (card_name, rest) = rest.split_once(", device ")?;
+            let (device, device_name) = rest.split_once(": ")?;
+            let card: u32 = card.parse().ok()?;
+            let device: u32 = device.parse().ok()?;
+            let name = format!(
+                "{} - {}",
+                bracketed(card_name).unwrap_or(card_name),
+                bracketed(device_name).unwrap_or(device_name)
+            );
+            Some(AudioOutput {
+                id: format!("hw:{},{}", card, device),
+                name,
+                is_default: false,
+                available: None,
+                ports: Vec::new(),
+                active_port: None,
+            })
+        })
+        .collect()
+}
+
+/// Play everything on `id` from now on, optionally through one of its ports.
+/// Music playback moves over right away and the voice agent is told to.
+#[tauri::command]
+pub fn set_default_audio_output(id: String, port: Option<String>) -> Result<(), String> {
+    let (node, output) = audio_outputs()?
+        .into_iter()
+        .find(|(_, output)| output.id == id)
+        .ok_or_else(|| format!("No audio output {}", id))?;
+    if let Some(port) = &port {
+        if !output.ports.iter().any(|p| &p.id == port) {
+            return Err(format!("{} has no port {}", output.name, port));
+        }
+    }
+
+    let result = with_pulse(|pulse| {
+        if let Some(port) = &port {
+            pulse.set_sink_port(&id, port)?;
+        }
+        pulse.set_default_sink(&id)
+    });
+    match result {
+        Some(result) => result?,
+        None => match *AUDIO_BACKEND {
+            AudioBackend::PipeWire => {
+                let node = node.ok_or_else(|| format!("No PipeWire node for {}", id))?;
+                set_default_audio_output_wpctl(node)?
+            }
+            AudioBackend::PulseAudio => set_default_audio_output_pactl(&id, port.as_deref())?,
+            AudioBackend::Alsa => set_default_audio_output_alsa(&id)?,
+        },
+    }
+    println!("🔊 Audio output: {}", output.name);
+
+    // Servers move streams that follow the default on their own, but plain
+    // ALSA streams stay on the device they opened
+    if let Err(e) = player::reopen_output() {
+        eprintln!("Audio: player didn't reopen its output: {}", e);
+    }
+    let command = VoiceAgentCommand::AudioOutputChanged { output: id };
+    if let Err(e) = send_voice_agent_command(&command) {
+        eprintln!("Audio: voice agent not told about the new output: {}", e);
+    }
+    Ok(())
+}
+
+/// wpctl takes the node id, which changes when the device reappears
+fn set_default_audio_output_wpctl(node: u32) -> Result<(), String> {
+    let output = Command::new("wpctl")
+        .args(["set-default", &node.to_string()])
+        .output()
+        .map_err(|e| format!("wpctl error: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "wpctl set-default failed: {}",
             String::from_utf8_lossy(&output.stderr)
         ));
     }
 
     Ok(())
 }
+
+fn set_default_audio_output_pactl(id: &str, port: Option<&str>) -> Result<(), String> {
+    let mut commands = Vec::new();
+    if let Some(port) = port {
+        commands.push(vec!["set-sink-port", id, port]);
+    }
+    commands.push(vec!["set-default-sink", id]);
+
+    for args in commands {
+        let output = Command::new("pactl")
+            .args(&args)
+            .output()
+            .map_err(|e| format!("pactl error: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "pactl {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Point ALSA's default device (and the mixer amixer uses) at the card
+fn set_default_audio_output_alsa(id: &str) -> Result<(), String> {
+    let (card, device) = id
+        .strip_prefix("hw:")
+        .and_then(|rest| rest.split_once(','))
+        .ok_or_else(|| format!("Invalid ALSA output {}", id))?;
+
+    let path = asoundrc_path();
+    if let Ok(content) = fs::read_to_string(&path) {
+        if !content.starts_with(ASOUNDRC_MARKER) {
+            return Err(format!(
+                "{} exists and isn't managed here, set the default device there",
+                path.display()
+            ));
+        }
+    }
+
+    let content = format!(
+        "{}\ndefaults.pcm.card {}\ndefaults.pcm.device {}\ndefaults.ctl.card {}\n",
+        ASOUNDRC_MARKER, card, device, card
+    );
+    write_atomic(&path, content).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+// ============================================================================
+// CHANGE NOTIFICATIONS
+// ============================================================================
+
+/// Wait for changes over a native subscription, until the connection fails
+fn watch_pulse_events(changed: &mut impl FnMut()) -> Result<(), String> {
+    let mut connection = pulse::Connection::connect()?;
+    // The default sink or source changing is a server event
+    connection
+        .subscribe(pulse::SUBSCRIBE_SINK | pulse::SUBSCRIBE_SOURCE | pulse::SUBSCRIBE_SERVER)?;
+    println!("🔊 Watching volume changes on {}", connection.server_name());
+    loop {
+        connection.next_event()?;
+        changed();
+    }
+}
+
+/// Wait for changes reported by `pactl subscribe`, until it exits
+fn watch_pactl_subscribe(changed: &mut impl FnMut()) -> Result<(), String> {
+    let mut child = Command::new("pactl")
+        .arg("subscribe")
+        .env("LC_ALL", "C")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("pactl error: {}", e))?;
+    let stdout = child.stdout.take().ok_or("pactl subscribe has no output")?;
+
+    // Lines like "Event 'change' on sink #0" or "Event 'change' on server #-1",
+    // translated outside the C locale
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if line.contains(" on sink #")
+            || line.contains(" on source #")
+            || line.contains(" on server")
+        {
+            changed();
+        }
+    }
+
+    let _ = child.wait();
+    Err("pactl subscribe exited".to_string())
+}
+
+/// Emit `volume-changed` with the new `VolumeInfo` when the output volume or
+/// mute state changes, also by a hardware key, the voice agent or over SSH,
+/// so the overlay slider stays in sync. Microphone changes are emitted as
+/// `mic-input-changed`, and the voice agent is told about the mic mute.
+pub fn start_volume_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last: Option<VolumeInfo> = None;
+        let mut last_input: Option<VolumeInfo> = None;
+        let mut check = || {
+            if let Ok(info) = get_volume_info() {
+                if last != Some(info) {
+                    last = Some(info);
+                    let _ = app.emit("volume-changed", info);
+                }
+            }
+            if let Ok(info) = get_input_volume_info() {
+                if last_input != Some(info) {
+                    last_input = Some(info);
+                    notify_mic_muted(info.muted);
+                    let _ = app.emit("mic-input-changed", info);
+                }
+            }
+        };
+        check();
+
+        loop {
+            if let Err(e) = watch_pulse_events(&mut check) {
+                eprintln!("Audio: no native change events: {}", e);
+            }
+            if !matches!(*AUDIO_BACKEND, AudioBackend::Alsa) {
+                if let Err(e) = watch_pactl_subscribe(&mut check) {
+                    eprintln!("Audio: no pactl change events: {}", e);
+                }
+            }
+
+            // Nothing reports changes: poll until a server may be back
+            let until = Instant::now() + PULSE_RETRY_INTERVAL;
+            while Instant::now() < until {
+                thread::sleep(VOLUME_POLL_INTERVAL);
+                check();
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WPCTL_STATUS: &str = "\
+PipeWire 'pipewire-0' [1.0.5, user@host, cookie:1234]
+ └─ Clients:
+        33. WirePlumber                         [1.0.5, user@host, pid:1042]
+
+Audio
+ ├─ Devices:
+ │      42. Built-in Audio                      [alsa]
+ │  
+ ├─ Sinks:
+ │      47. Built-in Audio Analog Stereo        [vol: 0.40]
+ │  *   52. HDMI Audio                          [vol: 1.00 MUTED]
+ │  
+ ├─ Sink endpoints:
+ │  
+ ├─ Sources:
+ │  *   49. Built-in Audio Analog Stereo        [vol: 1.00]
+ │  
+ └─ Streams:
+        61. Firefox
+             62. output_FL       > HDMI Audio:playback_FL	[active]
+
+Video
+ ├─ Devices:
+ │      58. Integrated Camera                   [v4l2]
+ │  
+ ├─ Sinks:
+ │      60. Camera Sink
+ │  
+ └─ Streams:
+
+Settings
+ └─ Default Configured Node Names:
+         0. Audio/Sink    alsa_output.hdmi
+";
+
+    const WPCTL_INSPECT: &str = "\
+id 47, type PipeWire:Interface:Node
+    alsa.card = \"0\"
+    media.class = \"Audio/Sink\"
+  * node.description = \"Built-in Audio Analog Stereo\"
+  * node.name = \"alsa_output.pci-0000_00_1f.3.analog-stereo\"
+    object.serial = \"48\"
+";
+
+    const PACTL_SINKS: &str = "\
+Sink #47
+	State: RUNNING
+	Name: alsa_output.pci-0000_00_1f.3.analog-stereo
+	Description: Built-in Audio Analog Stereo
+	Driver: PipeWire
+	Sample Specification: s32le 2ch 48000Hz
+	Channel Map: front-left,front-right
+	Owner Module: 4294967295
+	Mute: no
+	Volume: front-left: 26214 /  40% / -23.88 dB,   front-right: 26214 /  40% / -23.88 dB
+	        balance 0.00
+	Base Volume: 65536 / 100% / 0.00 dB
+	Monitor Source: alsa_output.pci-0000_00_1f.3.analog-stereo.monitor
+	Latency: 0 usec, configured 0 usec
+	Flags: HARDWARE HW_MUTE_CTRL HW_VOLUME_CTRL DECIBEL_VOLUME LATENCY
+	Properties:
+		alsa.card = \"0\"
+		device.description = \"Built-in Audio: Analog Stereo\"
+		node.name = \"alsa_output.pci-0000_00_1f.3.analog-stereo\"
+	Ports:
+		analog-output-speaker: Speakers (type: Speaker, priority: 10000, availability group: Legacy 3, availability unknown)
+		analog-output-headphones: Headphones (type: Headphones, priority: 9900, availability group: Legacy 2, not available)
+	Active Port: analog-output-speaker
+	Formats:
+		pcm
+
+Sink #52
+	State: SUSPENDED
+	Name: alsa_output.hdmi
+	Description: HDMI Audio
+	Driver: PipeWire
+	Properties:
+		device.description = \"HDMI Audio\"
+	Ports:
+		hdmi-output-0: HDMI / DisplayPort (type: HDMI, priority: 5900, availability group: Legacy 4, available)
+	Active Port: hdmi-output-0
+	Formats:
+		pcm
+
+Sink #60
+	State: IDLE
+	Name: null-sink
+	Description: Null Output
+	Driver: PipeWire
+	Properties:
+		node.name = \"null-sink\"
+	Formats:
+		pcm
+";
+
+    const APLAY_LIST: &str = "\
+**** List of PLAYBACK Hardware Devices ****
+card 0: PCH [HDA Intel PCH], device 0: ALC257 Analog [ALC257 Analog]
+  Subdevices: 1/1
+  Subdevice #0: subdevice #0
+card 0: PCH [HDA Intel PCH], device 3: HDMI 0 [HDMI 0]
+  Subdevices: 1/1
+  Subdevice #0: subdevice #0
+card 1: Device [USB Audio Device], device 0: USB Audio [USB Audio]
+  Subdevices: 0/1
+  Subdevice #0: subdevice #0
+";
+
+    fn port(id: &str, name: &str, available: Option<bool>) -> (String, String, Option<bool>) {
+        (id.to_string(), name.to_string(), available)
+    }
+
+    fn ports(output: &AudioOutput) -> Vec<(String, String, Option<bool>)> {
+        output
+            .ports
+            .iter()
+            .map(|p| port(&p.id, &p.name, p.available))
+            .collect()
+    }
+
+    #[test]
+    fn wpctl_status_lists_audio_sinks_only() {
+        assert_eq!(
+            parse_wpctl_status(WPCTL_STATUS),
+            vec![(47, false), (52, true)]
+        );
+        assert_eq!(parse_wpctl_status(""), vec![]);
+    }
+
+    #[test]
+    fn wpctl_inspect_reads_properties() {
+        let properties = parse_wpctl_inspect(WPCTL_INSPECT);
+        assert_eq!(properties.len(), 5);
+        assert!(properties.contains(&(
+            "node.name".to_string(),
+            "alsa_output.pci-0000_00_1f.3.analog-stereo".to_string()
+        )));
+        assert!(properties.contains(&(
+            "node.description".to_string(),
+            "Built-in Audio Analog Stereo".to_string()
+        )));
+    }
+
+    #[test]
+    fn pactl_sinks_with_properties_and_availability_groups() {
+        let outputs = parse_pactl_sinks(PACTL_SINKS, "alsa_output.hdmi");
+        assert_eq!(outputs.len(), 3);
+
+        let analog = &outputs[0];
+        assert_eq!(analog.id, "alsa_output.pci-0000_00_1f.3.analog-stereo");
+        assert_eq!(analog.name, "Built-in Audio Analog Stereo");
+        assert!(!analog.is_default);
+        assert_eq!(
+            ports(analog),
+            vec![
+                port("analog-output-speaker", "Speakers", None),
+                port("analog-output-headphones", "Headphones", Some(false)),
+            ]
+        );
+        assert_eq!(analog.active_port.as_deref(), Some("analog-output-speaker"));
+        assert_eq!(analog.available, Some(true));
+
+        let hdmi = &outputs[1];
+        assert_eq!(hdmi.id, "alsa_output.hdmi");
+        assert!(hdmi.is_default);
+        assert_eq!(
+            ports(hdmi),
+            vec![port("hdmi-output-0", "HDMI / DisplayPort", Some(true))]
+        );
+
+        let null = &outputs[2];
+        assert_eq!(null.name, "Null Output");
+        assert!(null.ports.is_empty());
+        assert_eq!(null.active_port, None);
+        assert_eq!(null.available, None);
+    }
+
+    #[test]
+    fn aplay_list_names_card_and_device() {
+        let outputs = parse_aplay_list(APLAY_LIST);
+        let outputs: Vec<_> = outputs
+            .iter()
+            .map(|o| (o.id.as_str(), o.name.as_str()))
+            .collect();
+        assert_eq!(
+            outputs,
+            vec![
+                ("hw:0,0", "HDA Intel PCH - ALC257 Analog"),
+                ("hw:0,3", "HDA Intel PCH - HDMI 0"),
+                ("hw:1,0", "USB Audio Device - USB Audio"),
+            ]
+        );
+    }
+}
@@ -6,17 +6,28 @@
 //! - Quota warnings
 //! - Errors (token, network, quota exceeded)
 //! - Usage statistics
+//!
+//! Commands (e.g. pausing wakeword detection while the app uses the mic) are
+//! sent to the agent as JSON lines over a separate connection.
 
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::{AppHandle, Emitter};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::net::UnixStream;
 
+use crate::ducking;
+use crate::mic;
+
 const VOICE_AGENT_SOCKET_PATH: &str = "/tmp/honeybee-voice-agent.sock";
 
+/// Timeout for writing a command to the voice agent
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Voice agent status types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -99,6 +110,43 @@ pub struct VoiceAgentStatus {
     pub recoverable: Option<bool>,
 }
 
+/// Command sent to the voice agent
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum VoiceAgentCommand {
+    /// Stop listening for the wakeword and release the mic. The agent resumes
+    /// on its own after `lease_secs` unless the pause is renewed, so a crashed
+    /// app can't leave it deaf.
+    PauseWakeword { owner: String, lease_secs: u64 },
+    /// Resume wakeword detection
+    ResumeWakeword { owner: String },
+    /// The default audio output changed (sink name, or "hw:CARD,DEVICE" on
+    /// plain ALSA). Reopen playback so replies come out there.
+    AudioOutputChanged { output: String },
+    /// The microphone was muted or unmuted, from the overlay or elsewhere.
+    /// While muted the agent can't hear anything, so it shouldn't listen.
+    MicMuted { muted: bool },
+}
+
+/// Send a command to the voice agent
+pub fn send_voice_agent_command(command: &VoiceAgentCommand) -> Result<(), String> {
+    if !Path::new(VOICE_AGENT_SOCKET_PATH).exists() {
+        return Err("Voice agent not running".to_string());
+    }
+
+    let mut stream = std::os::unix::net::UnixStream::connect(VOICE_AGENT_SOCKET_PATH)
+        .map_err(|e| format!("Failed to connect to voice agent: {}", e))?;
+    if let Err(e) = stream.set_write_timeout(Some(COMMAND_TIMEOUT)) {
+        eprintln!("Failed to set socket timeout: {}", e);
+    }
+
+    let mut line = serde_json::to_string(command).map_err(|e| e.to_string())?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("Failed to send voice agent command: {}", e))
+}
+
 /// Start listening for voice agent status updates
 /// This runs in a separate async task and emits events to the frontend
 pub fn start_voice_agent_ipc_listener(app_handle: AppHandle) {
@@ -139,6 +187,10 @@ async fn run_voice_agent_ipc_listener(app_handle: AppHandle, running: Arc<Atomic
             }
         }
 
+        // The agent may have crashed mid-session, don't keep the mic marked busy
+        mic::set_voice_agent_active(&app_handle, false);
+        ducking::set_session_active(false);
+
         // Wait before reconnecting
         tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
     }
@@ -164,6 +216,27 @@ async fn connect_and_listen_voice_agent(
             Ok(status) => {
                 println!("🎤 Received voice agent status: {:?}", status.event);
 
+                // Track whether the agent is in a session (holding the mic)
+                match status.event {
+                    VoiceAgentEventType::SessionStarted | VoiceAgentEventType::Listening => {
+                        mic::set_voice_agent_active(app_handle, true);
+                        ducking::set_session_active(true);
+                    }
+                    VoiceAgentEventType::SessionEnded | VoiceAgentEventType::Ready => {
+                        mic::set_voice_agent_active(app_handle, false);
+                        ducking::set_session_active(false);
+                    }
+                    // A failed session may never send SessionEnded
+                    VoiceAgentEventType::Error
+                    | VoiceAgentEventType::TokenError
+                    | VoiceAgentEventType::NetworkError
+                    | VoiceAgentEventType::QuotaExceeded => {
+                        mic::set_voice_agent_active(app_handle, false);
+                        ducking::set_session_active(false);
+                    }
+                    _ => {}
+                }
+
                 // Emit event to frontend
                 if let Err(e) = app_handle.emit("voice-agent-status", status.clone()) {
                     eprintln!("Failed to emit voice agent status: {}", e);
@@ -0,0 +1,624 @@
+//! Incremental 16-bit PCM WAV writer and reader
+//!
+//! Samples are appended as they arrive and the RIFF/data sizes in the header are
+//! patched periodically, so a file interrupted by a crash is still playable up to
+//! the last header update. `repair_header` fixes the sizes of such a file from its
+//! length on disk. `WavReader` streams the samples back out for transcoding.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Size of the canonical 44-byte header written by `WavWriter`
+pub const HEADER_LEN: u64 = 44;
+
+const BITS_PER_SAMPLE: u16 = 16;
+
+/// Offset of the RIFF chunk size field
+const RIFF_SIZE_OFFSET: u64 = 4;
+/// Offset of the data chunk size field
+const DATA_SIZE_OFFSET: u64 = 40;
+
+/// Largest data chunk that still fits the 32-bit RIFF size field
+const MAX_DATA_BYTES: u64 = u32::MAX as u64 - (HEADER_LEN - 8);
+
+pub struct WavWriter {
+    file: BufWriter<File>,
+    sample_rate: u32,
+    channels: u16,
+    data_bytes: u64,
+}
+
+impl WavWriter {
+    /// Create the file and write a header with zero sizes
+    pub fn create(path: &Path, sample_rate: u32, channels: u16) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let mut writer = Self {
+            file: BufWriter::new(file),
+            sample_rate,
+            channels,
+            data_bytes: 0,
+        };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let byte_rate = self.sample_rate * self.channels as u32 * (BITS_PER_SAMPLE as u32 / 8);
+        let block_align = self.channels * (BITS_PER_SAMPLE / 8);
+        let f = &mut self.file;
+
+        // RIFF header
+        f.write_all(b"RIFF")?;
+        f.write_all(&(HEADER_LEN as u32 - 8).to_le_bytes())?;
+        f.write_all(b"WAVE")?;
+
+        // fmt chunk
+        f.write_all(b"fmt ")?;
+        f.write_all(&16u32.to_le_bytes())?;
+        f.write_all(&1u16.to_le_bytes())?; // PCM
+        f.write_all(&self.channels.to_le_bytes())?;
+        f.write_all(&self.sample_rate.to_le_bytes())?;
+        f.write_all(&byte_rate.to_le_bytes())?;
+        f.write_all(&block_align.to_le_bytes())?;
+        f.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+        // data chunk (size patched later)
+        f.write_all(b"data")?;
+        f.write_all(&0u32.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Append interleaved f32 samples, converted to i16
+    pub fn write_samples(&mut self, samples: &[f32]) -> io::Result<()> {
+        let bytes = samples.len() as u64 * 2;
+        if self.data_bytes + bytes > MAX_DATA_BYTES {
+            return Err(io::Error::other("WAV size limit (4 GB) reached"));
+        }
+
+        for &sample in samples {
+            let clamped = sample.clamp(-1.0, 1.0);
+            let val = (clamped * 32767.0) as i16;
+            self.file.write_all(&val.to_le_bytes())?;
+        }
+        self.data_bytes += bytes;
+        Ok(())
+    }
+
+    /// Flush buffered samples and patch the header sizes to match
+    pub fn update_header(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let file = self.file.get_mut();
+        patch_sizes(file, self.data_bytes)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    /// Number of whole frames written so far
+    pub fn frames(&self) -> u64 {
+        self.data_bytes / (self.channels as u64 * 2)
+    }
+
+    /// Duration written so far in milliseconds
+    pub fn duration_ms(&self) -> u64 {
+        self.frames() * 1000 / self.sample_rate.max(1) as u64
+    }
+
+    /// Patch the header, fsync and close. Returns the duration in milliseconds.
+    pub fn finalize(mut self) -> io::Result<u64> {
+        self.update_header()?;
+        self.file.get_ref().sync_all()?;
+        Ok(self.duration_ms())
+    }
+}
+
+fn patch_sizes(file: &mut File, data_bytes: u64) -> io::Result<()> {
+    let data_size = data_bytes.min(MAX_DATA_BYTES) as u32;
+    file.seek(SeekFrom::Start(RIFF_SIZE_OFFSET))?;
+    file.write_all(&(data_size + HEADER_LEN as u32 - 8).to_le_bytes())?;
+    file.seek(SeekFrom::Start(DATA_SIZE_OFFSET))?;
+    file.write_all(&data_size.to_le_bytes())?;
+    Ok(())
+}
+
+/// Fix the header of a WAV written by `WavWriter` that was never finalized.
+/// Trailing partial frames are truncated. Returns the recovered data size in bytes.
+pub fn repair_header(path: &Path, channels: u16) -> io::Result<u64> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let len = file.metadata()?.len();
+    if len < HEADER_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "File shorter than WAV header"));
+    }
+
+    let block_align = channels.max(1) as u64 * 2;
+    let data_bytes = ((len - HEADER_LEN) / block_align * block_align).min(MAX_DATA_BYTES);
+    file.set_len(HEADER_LEN + data_bytes)?;
+    patch_sizes(&mut file, data_bytes)?;
+    file.sync_all()?;
+    Ok(data_bytes)
+}
+
+/// Keep only frames `start_frame..end_frame` of a WAV written by `WavWriter`.
+/// The audio is moved in place, so no extra disk space is needed. Returns the
+/// new data size in bytes.
+pub fn crop(path: &Path, start_frame: u64, end_frame: u64) -> io::Result<u64> {
+    let info = read_info(path)?;
+    if info.data_offset != HEADER_LEN || info.bits_per_sample != BITS_PER_SAMPLE {
+        return Err(invalid("Only WAV files written by the recorder can be cropped"));
+    }
+
+    let frame_bytes = info.channels.max(1) as u64 * 2;
+    let end = end_frame.min(info.frames());
+    let start = start_frame.min(end);
+    let data_bytes = (end - start) * frame_bytes;
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    if start > 0 {
+        let source = HEADER_LEN + start * frame_bytes;
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut moved = 0u64;
+        while moved < data_bytes {
+            let n = (data_bytes - moved).min(buf.len() as u64) as usize;
+            file.seek(SeekFrom::Start(source + moved))?;
+            file.read_exact(&mut buf[..n])?;
+            file.seek(SeekFrom::Start(HEADER_LEN + moved))?;
+            file.write_all(&buf[..n])?;
+            moved += n as u64;
+        }
+    }
+    file.set_len(HEADER_LEN + data_bytes)?;
+    patch_sizes(&mut file, data_bytes)?;
+    file.sync_all()?;
+    Ok(data_bytes)
+}
+
+/// Whether a chunk (header and body) is a LIST of type INFO
+fn is_info_list(chunk: &[u8]) -> bool {
+    chunk.len() >= 12 && &chunk[0..4] == b"LIST" && &chunk[8..12] == b"INFO"
+}
+
+/// Replace the LIST/INFO chunk of a WAV with `entries` (e.g. `INAM` title,
+/// `ICMT` comment). The new chunk goes after the data chunk, other chunks
+/// there (`cue `, `id3 `, ...) are kept. An INFO list in front of the data
+/// becomes a JUNK chunk, removing it would mean moving the audio. Empty values
+/// are skipped; no entries removes the chunk.
+pub fn write_info_chunk(path: &Path, entries: &[(&[u8; 4], &str)]) -> io::Result<()> {
+    let info = read_info(path)?;
+    let data_end = info.data_offset + info.data_len + (info.data_len & 1);
+
+    let mut body = Vec::new();
+    for (id, value) in entries.iter().filter(|(_, v)| !v.is_empty()) {
+        // Zero-terminated, padded to an even length
+        let size = value.len() as u32 + 1;
+        body.extend_from_slice(*id);
+        body.extend_from_slice(&size.to_le_bytes());
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+        if size & 1 == 1 {
+            body.push(0);
+        }
+    }
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    // Chunks between the RIFF header and the data chunk header
+    let mut pos = 12u64;
+    while pos + 8 < info.data_offset {
+        let mut header = [0u8; 12];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut header)?;
+        if is_info_list(&header) {
+            file.seek(SeekFrom::Start(pos))?;
+            file.write_all(b"JUNK")?;
+        }
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        pos += 8 + size + (size & 1);
+    }
+
+    // Complete chunks after the data, a truncated one at the end is dropped
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(data_end))?;
+    file.read_to_end(&mut tail)?;
+    let mut kept = Vec::new();
+    let mut rest = tail.as_slice();
+    while rest.len() >= 8 {
+        let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        if rest.len() - 8 < size {
+            break;
+        }
+        let chunk = &rest[..8 + size];
+        if !is_info_list(chunk) {
+            kept.extend_from_slice(chunk);
+            if size & 1 == 1 {
+                kept.push(0);
+            }
+        }
+        rest = &rest[(8 + size + (size & 1)).min(rest.len())..];
+    }
+
+    file.set_len(data_end)?;
+    file.seek(SeekFrom::End(0))?;
+    file.write_all(&kept)?;
+    if !body.is_empty() {
+        file.write_all(b"LIST")?;
+        file.write_all(&(body.len() as u32 + 4).to_le_bytes())?;
+        file.write_all(b"INFO")?;
+        file.write_all(&body)?;
+    }
+
+    let riff_size = file.metadata()?.len().saturating_sub(8).min(u32::MAX as u64) as u32;
+    file.seek(SeekFrom::Start(RIFF_SIZE_OFFSET))?;
+    file.write_all(&riff_size.to_le_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Read the channel count from a canonical header (used when repairing)
+pub fn read_channels(path: &Path) -> io::Result<u16> {
+    let mut header = [0u8; HEADER_LEN as usize];
+    File::open(path)?.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a WAV file"));
+    }
+    Ok(u16::from_le_bytes([header[22], header[23]]))
+}
+
+/// Format of a WAV file, from its fmt and data chunks
+#[derive(Debug, Clone)]
+pub struct WavInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub data_offset: u64,
+    pub data_len: u64,
+}
+
+impl WavInfo {
+    pub fn frames(&self) -> u64 {
+        let frame_bytes = self.channels.max(1) as u64 * (self.bits_per_sample as u64 / 8).max(1);
+        self.data_len / frame_bytes
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.frames() * 1000 / self.sample_rate.max(1) as u64
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Walk the RIFF chunks and return the format and data location
+pub fn read_info(path: &Path) -> io::Result<WavInfo> {
+    let mut file = BufReader::new(File::open(path)?);
+    let file_len = file.get_ref().metadata()?.len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff)?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(invalid("Not a WAV file"));
+    }
+
+    let mut fmt: Option<(u16, u32, u16)> = None;
+    let mut pos = 12u64;
+    loop {
+        let mut chunk = [0u8; 8];
+        if file.read_exact(&mut chunk).is_err() {
+            return Err(invalid("WAV file has no data chunk"));
+        }
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        pos += 8;
+
+        match &chunk[0..4] {
+            b"fmt " => {
+                let mut body = vec![0u8; size.min(64) as usize];
+                file.read_exact(&mut body)?;
+                if body.len() < 16 {
+                    return Err(invalid("WAV fmt chunk too short"));
+                }
+                let format_tag = u16::from_le_bytes([body[0], body[1]]);
+                if format_tag != 1 && format_tag != 0xFFFE {
+                    return Err(invalid("Only PCM WAV files are supported"));
+                }
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                // Everything downstream divides by these
+                if channels == 0 || sample_rate == 0 {
+                    return Err(invalid("WAV fmt chunk has no channels or sample rate"));
+                }
+                fmt = Some((
+                    channels,
+                    sample_rate,
+                    u16::from_le_bytes([body[14], body[15]]),
+                ));
+                file.seek(SeekFrom::Start(pos + size + (size & 1)))?;
+            }
+            b"data" => {
+                let (channels, sample_rate, bits_per_sample) =
+                    fmt.ok_or_else(|| invalid("WAV data chunk before fmt chunk"))?;
+                // A zero / oversized data size means the header was never patched
+                let available = file_len.saturating_sub(pos);
+                let data_len = if size == 0 || size > available { available } else { size };
+                return Ok(WavInfo {
+                    sample_rate,
+                    channels,
+                    bits_per_sample,
+                    data_offset: pos,
+                    data_len,
+                });
+            }
+            _ => {
+                file.seek(SeekFrom::Current((size + (size & 1)) as i64))?;
+            }
+        }
+        pos += size + (size & 1);
+    }
+}
+
+/// Sequential reader for 16-bit PCM WAV files
+pub struct WavReader {
+    reader: BufReader<File>,
+    info: WavInfo,
+    remaining: u64,
+}
+
+impl WavReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let info = read_info(path)?;
+        if info.bits_per_sample != BITS_PER_SAMPLE {
+            return Err(invalid("Only 16-bit WAV files are supported"));
+        }
+        let mut reader = BufReader::new(File::open(path)?);
+        reader.seek(SeekFrom::Start(info.data_offset))?;
+        Ok(Self {
+            reader,
+            remaining: info.data_len,
+            info,
+        })
+    }
+
+    pub fn info(&self) -> &WavInfo {
+        &self.info
+    }
+
+    /// Read up to `max_frames` interleaved frames into `buf` (cleared first).
+    /// Returns the number of frames read, 0 at end of data.
+    pub fn read_frames(&mut self, buf: &mut Vec<i16>, max_frames: usize) -> io::Result<usize> {
+        buf.clear();
+        let frame_bytes = self.info.channels as u64 * 2;
+        let frames = (self.remaining / frame_bytes).min(max_frames as u64) as usize;
+        if frames == 0 {
+            return Ok(0);
+        }
+
+        let mut bytes = vec![0u8; frames * frame_bytes as usize];
+        self.reader.read_exact(&mut bytes)?;
+        self.remaining -= bytes.len() as u64;
+        buf.extend(
+            bytes
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            TempFile(std::env::temp_dir().join(format!(
+                "honeybee-wav-{}-{}",
+                std::process::id(),
+                name
+            )))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(body);
+        if body.len() & 1 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn fmt(channels: u16, bits: u16) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+        let mut body = Vec::new();
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&8000u32.to_le_bytes());
+        body.extend_from_slice(&(8000 * block_align as u32).to_le_bytes());
+        body.extend_from_slice(&block_align.to_le_bytes());
+        body.extend_from_slice(&bits.to_le_bytes());
+        chunk(b"fmt ", &body)
+    }
+
+    /// A RIFF file of `chunks` with a correct RIFF size
+    fn write_riff(path: &Path, chunks: &[Vec<u8>]) {
+        let body: Vec<u8> = chunks.concat();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(&body);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    /// Top level chunk ids and the RIFF size check
+    fn chunk_ids(path: &Path) -> Vec<String> {
+        let bytes = std::fs::read(path).unwrap();
+        let riff_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        assert_eq!(riff_size, bytes.len() - 8, "RIFF size");
+
+        let mut ids = Vec::new();
+        let mut pos = 12;
+        while pos + 8 <= bytes.len() {
+            let size = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into().unwrap()) as usize;
+            ids.push(String::from_utf8_lossy(&bytes[pos..pos + 4]).to_string());
+            pos += 8 + size + (size & 1);
+        }
+        assert_eq!(pos, bytes.len(), "chunks fill the file");
+        ids
+    }
+
+    /// Mono recording of `frames` samples counting up from 0
+    fn write_recording(path: &Path, frames: u64) {
+        let mut writer = WavWriter::create(path, 8000, 1).unwrap();
+        let samples: Vec<f32> = (0..frames).map(|i| i as f32 / 32767.0).collect();
+        writer.write_samples(&samples).unwrap();
+        writer.finalize().unwrap();
+    }
+
+    fn read_samples(path: &Path) -> Vec<i16> {
+        let mut reader = WavReader::open(path).unwrap();
+        let mut samples = Vec::new();
+        let mut block = Vec::new();
+        while reader.read_frames(&mut block, 1000).unwrap() > 0 {
+            samples.extend_from_slice(&block);
+        }
+        samples
+    }
+
+    #[test]
+    fn info_chunk_is_added_replaced_and_removed() {
+        let file = TempFile::new("info.wav");
+        write_recording(&file.0, 100);
+
+        write_info_chunk(&file.0, &[(b"INAM", "Title"), (b"ICMT", "")]).unwrap();
+        assert_eq!(chunk_ids(&file.0), ["fmt ", "data", "LIST"]);
+        let bytes = std::fs::read(&file.0).unwrap();
+        // INAM with "Title\0", even already
+        assert_eq!(
+            &bytes[HEADER_LEN as usize + 200..],
+            &chunk(b"LIST", b"INFOINAM\x06\0\0\0Title\0")[..]
+        );
+
+        write_info_chunk(&file.0, &[(b"INAM", "Other"), (b"ICMT", "Odd")]).unwrap();
+        assert_eq!(chunk_ids(&file.0), ["fmt ", "data", "LIST"]);
+        assert!(std::fs::read(&file.0)
+            .unwrap()
+            .ends_with(b"ICMT\x04\0\0\0Odd\0"));
+
+        write_info_chunk(&file.0, &[]).unwrap();
+        assert_eq!(chunk_ids(&file.0), ["fmt ", "data"]);
+        assert_eq!(std::fs::metadata(&file.0).unwrap().len(), HEADER_LEN + 200);
+        assert_eq!(read_samples(&file.0), (0..100).collect::<Vec<i16>>());
+    }
+
+    #[test]
+    fn info_chunk_keeps_other_chunks() {
+        let file = TempFile::new("chunks.wav");
+        let old_info = chunk(b"LIST", b"INFOINAM\x04\0\0\0Old\0");
+        let adtl = chunk(b"LIST", b"adtllabl\x04\0\0\0\x01\0\0\0");
+        write_riff(
+            &file.0,
+            &[
+                fmt(1, 16),
+                old_info.clone(),
+                chunk(b"data", &[1, 0, 2, 0]),
+                chunk(b"cue ", &[0; 4]),
+                old_info,
+                chunk(b"id3 ", b"ID3"),
+                adtl,
+            ],
+        );
+
+        write_info_chunk(&file.0, &[(b"INAM", "New")]).unwrap();
+        // The INFO in front of the data can't move, so it's padding now
+        assert_eq!(
+            chunk_ids(&file.0),
+            ["fmt ", "JUNK", "data", "cue ", "id3 ", "LIST", "LIST"]
+        );
+        let bytes = std::fs::read(&file.0).unwrap();
+        assert!(bytes.windows(3).any(|w| w == b"ID3"));
+        assert!(bytes.windows(4).any(|w| w == b"adtl"));
+        assert!(bytes.ends_with(b"INAM\x04\0\0\0New\0"));
+        assert_eq!(read_samples(&file.0), [1, 2]);
+    }
+
+    #[test]
+    fn info_chunk_after_odd_data_is_padded() {
+        let file = TempFile::new("odd.wav");
+        // 8-bit mono, 3 bytes of data and no pad byte
+        let mut data = chunk(b"data", &[10, 20, 30]);
+        data.pop();
+        write_riff(&file.0, &[fmt(1, 8), data]);
+
+        write_info_chunk(&file.0, &[(b"INAM", "x")]).unwrap();
+        assert_eq!(chunk_ids(&file.0), ["fmt ", "data", "LIST"]);
+        let info = read_info(&file.0).unwrap();
+        assert_eq!(info.data_len, 3);
+        let bytes = std::fs::read(&file.0).unwrap();
+        let list = (info.data_offset + 4) as usize;
+        assert_eq!(bytes[list - 1], 0);
+        assert_eq!(&bytes[list..list + 4], b"LIST");
+    }
+
+    #[test]
+    fn crop_keeps_the_frames_in_bounds() {
+        let file = TempFile::new("crop.wav");
+
+        write_recording(&file.0, 100);
+        assert_eq!(crop(&file.0, 10, 30).unwrap(), 40);
+        assert_eq!(read_samples(&file.0), (10..30).collect::<Vec<i16>>());
+        assert_eq!(chunk_ids(&file.0), ["fmt ", "data"]);
+
+        // The end is clamped to the frames there are
+        write_recording(&file.0, 100);
+        assert_eq!(crop(&file.0, 90, 1000).unwrap(), 20);
+        assert_eq!(read_samples(&file.0), (90..100).collect::<Vec<i16>>());
+
+        // Nothing left when the start is past the end
+        write_recording(&file.0, 100);
+        assert_eq!(crop(&file.0, 50, 40).unwrap(), 0);
+        assert_eq!(read_info(&file.0).unwrap().data_len, 0);
+
+        // Only canonical recordings are moved around
+        write_riff(
+            &file.0,
+            &[fmt(1, 16), chunk(b"JUNK", &[0; 6]), chunk(b"data", &[0; 8])],
+        );
+        assert!(crop(&file.0, 1, 2).is_err());
+    }
+
+    #[test]
+    fn repair_patches_a_header_that_was_never_updated() {
+        let file = TempFile::new("rec.wav.part");
+        let mut writer = WavWriter::create(&file.0, 8000, 2).unwrap();
+        writer.write_samples(&[0.5; 2 * 50]).unwrap();
+        // Crashed: buffered samples reach the disk, the header never does
+        drop(writer);
+        let mut bytes = std::fs::read(&file.0).unwrap();
+        assert_eq!(&bytes[4..8], &36u32.to_le_bytes());
+        assert_eq!(&bytes[40..44], &[0; 4]);
+        // Half a frame written when it stopped
+        bytes.extend_from_slice(&[1, 2, 3]);
+        std::fs::write(&file.0, bytes).unwrap();
+
+        assert_eq!(read_channels(&file.0).unwrap(), 2);
+        assert_eq!(repair_header(&file.0, 2).unwrap(), 200);
+        assert_eq!(chunk_ids(&file.0), ["fmt ", "data"]);
+        let info = read_info(&file.0).unwrap();
+        assert_eq!(info.data_len, 200);
+        assert_eq!(info.frames(), 50);
+
+        std::fs::write(&file.0, b"RIFF").unwrap();
+        assert!(repair_header(&file.0, 2).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Waveform Peaks
+//!
+//! Min/max peaks are computed once per recording at `PEAKS_PER_SECOND` by
+//! decoding the file, and cached in a hidden sidecar next to it
+//! (`.REC_....wav.peaks`). Requests for any number of buckets are served by
+//! merging the cached peaks, so long recordings are only decoded once.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use crate::decode::AudioDecoder;
+use crate::fsutil::write_atomic;
+use crate::recorder;
+
+/// Resolution of the cache (1 hour = ~1.4 MB)
+const PEAKS_PER_SECOND: u64 = 100;
+
+/// Upper bound on buckets per request
+const MAX_BUCKETS: u32 = 10_000;
+
+const CACHE_MAGIC: &[u8; 4] = b"HBPK";
+const CACHE_VERSION: u8 = 1;
+/// magic, version, padding, source size, source mtime, sample rate, frames, peak count
+const CACHE_HEADER_LEN: usize = 4 + 1 + 3 + 8 + 8 + 4 + 8 + 4;
+
+pub(crate) const CACHE_SUFFIX: &str = "peaks";
+
+lazy_static::lazy_static! {
+    // One lock per cache file, stops two requests for the same recording from
+    // decoding it twice without making other recordings wait
+    static ref CACHE_LOCKS: Mutex<HashMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+/// Downsampled waveform of a recording
+#[derive(Debug, Clone, Serialize)]
+pub struct Waveform {
+    pub duration_ms: u64,
+    /// [min, max] per bucket, -1.0 to 1.0
+    pub peaks: Vec<[f32; 2]>,
+}
+
+struct PeakCache {
+    source_size: u64,
+    source_modified: u64,
+    sample_rate: u32,
+    frames: u64,
+    peaks: Vec<(i16, i16)>,
+}
+
+impl PeakCache {
+    fn duration_ms(&self) -> u64 {
+        self.frames * 1000 / self.sample_rate.max(1) as u64
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let mut data = Vec::with_capacity(CACHE_HEADER_LEN + self.peaks.len() * 4);
+        data.extend_from_slice(CACHE_MAGIC);
+        data.extend_from_slice(&[CACHE_VERSION, 0, 0, 0]);
+        data.extend_from_slice(&self.source_size.to_le_bytes());
+        data.extend_from_slice(&self.source_modified.to_le_bytes());
+        data.extend_from_slice(&self.sample_rate.to_le_bytes());
+        data.extend_from_slice(&self.frames.to_le_bytes());
+        data.extend_from_slice(&(self.peaks.len() as u32).to_le_bytes());
+        for (min, max) in &self.peaks {
+            data.extend_from_slice(&min.to_le_bytes());
+            data.extend_from_slice(&max.to_le_bytes());
+        }
+
+        write_atomic(path, data)
+    }
+
+    fn read(path: &Path) -> io::Result<Self> {
+        let mut data = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut data)?;
+        if data.len() < CACHE_HEADER_LEN || &data[0..4] != CACHE_MAGIC || data[4] != CACHE_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid peaks cache"));
+        }
+
+        let u64_at = |i: usize| u64::from_le_bytes(data[i..i + 8].try_into().unwrap_or_default());
+        let u32_at = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap_or_default());
+        let count = u32_at(36) as usize;
+        let body = &data[CACHE_HEADER_LEN..];
+        if body.len() != count * 4 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Truncated peaks cache"));
+        }
+
+        Ok(Self {
+            source_size: u64_at(8),
+            source_modified: u64_at(16),
+            sample_rate: u32_at(24),
+            frames: u64_at(28),
+            peaks: body
+                .chunks_exact(4)
+                .map(|p| {
+                    (
+                        i16::from_le_bytes([p[0], p[1]]),
+                        i16::from_le_bytes([p[2], p[3]]),
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Size and mtime (ns) of a recording, used to tell whether the cache is stale
+fn source_stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    Ok((metadata.len(), modified))
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * 32767.0) as i16
+}
+
+/// Decode the whole file into min/max peaks over all channels
+fn compute_peaks(path: &Path) -> Result<PeakCache, String> {
+    let (source_size, source_modified) =
+        source_stamp(path).map_err(|e| format!("Failed to read recording: {}", e))?;
+
+    let mut decoder = AudioDecoder::open(path)?;
+    let channels = decoder.channels().max(1) as usize;
+    let sample_rate = decoder.sample_rate();
+    let window = (sample_rate as u64 / PEAKS_PER_SECOND).max(1);
+
+    let mut peaks = Vec::new();
+    let mut frames = 0u64;
+    let (mut min, mut max) = (0f32, 0f32);
+    let mut block = Vec::new();
+    while decoder.next_block(&mut block)? {
+        for frame in block.chunks_exact(channels) {
+            for &s in frame {
+                min = min.min(s);
+                max = max.max(s);
+            }
+            frames += 1;
+            if frames.is_multiple_of(window) {
+                peaks.push((to_i16(min), to_i16(max)));
+                (min, max) = (0.0, 0.0);
+            }
+        }
+    }
+    if !frames.is_multiple_of(window) {
+        peaks.push((to_i16(min), to_i16(max)));
+    }
+
+    Ok(PeakCache {
+        source_size,
+        source_modified,
+        sample_rate,
+        frames,
+        peaks,
+    })
+}
+
+/// Cached peaks for a recording, (re)computed if missing or stale
+fn load_or_compute(path: &Path) -> Result<PeakCache, String> {
+    let cache_path = recorder::sidecar_path(path, CACHE_SUFFIX);
+    let lock = CACHE_LOCKS
+        .lock()
+        .entry(cache_path.clone())
+        .or_default()
+        .clone();
+    let result = {
+        let _guard = lock.lock();
+        load_or_compute_locked(path, &cache_path)
+    };
+
+    // Forget the lock once nobody else is waiting on it
+    let mut locks = CACHE_LOCKS.lock();
+    if Arc::strong_count(&lock) == 2 {
+        locks.remove(&cache_path);
+    }
+    result
+}
+
+fn load_or_compute_locked(path: &Path, cache_path: &Path) -> Result<PeakCache, String> {
+    let stamp = source_stamp(path).map_err(|e| format!("Failed to read recording: {}", e))?;
+
+    if let Ok(cache) = PeakCache::read(cache_path) {
+        if (cache.source_size, cache.source_modified) == stamp {
+            return Ok(cache);
+        }
+    }
+
+    let cache = compute_peaks(path)?;
+    if let Err(e) = cache.write(cache_path) {
+        // Still usable, just recomputed next time
+        eprintln!("Failed to write peaks cache: {}", e);
+    }
+    Ok(cache)
+}
+
+/// Build the peaks cache ahead of time (after a recording is saved)
+pub fn warm_cache(path: &Path) {
+    if let Err(e) = load_or_compute(path) {
+        eprintln!("Failed to compute waveform for {}: {}", path.display(), e);
+    }
+}
+
+/// Merge cached peaks into `buckets` buckets (fewer if the recording is very short)
+fn downsample(cache: &PeakCache, buckets: usize) -> Vec<[f32; 2]> {
+    let count = cache.peaks.len();
+    let buckets = buckets.min(count);
+    (0..buckets)
+        .map(|i| {
+            let start = i * count / buckets;
+            let end = ((i + 1) * count / buckets).max(start + 1);
+            let (min, max) = cache.peaks[start..end]
+                .iter()
+                .fold((0i16, 0i16), |(lo, hi), &(min, max)| (lo.min(min), hi.max(max)));
+            [min as f32 / 32767.0, max as f32 / 32767.0]
+        })
+        .collect()
+}
+
+/// Get min/max peaks of a recording, downsampled to `buckets` points
+#[tauri::command]
+pub async fn get_recording_waveform(path: String, buckets: u32) -> Result<Waveform, String> {
+    let target = recorder::recording_path(&path)?;
+    let buckets = buckets.clamp(1, MAX_BUCKETS) as usize;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache = load_or_compute(&target)?;
+        Ok(Waveform {
+            duration_ms: cache.duration_ms(),
+            peaks: downsample(&cache, buckets),
+        })
+    })
+    .await
+    .map_err(|e| format!("Waveform task failed: {}", e))?
+}
@@ -1,10 +1,12 @@
 import EyeTracker from './components/EyeTracker';
 import { SystemOverlay } from './components/overlay/SystemOverlay';
+import { AlarmAlert } from './components/overlay/AlarmAlert';
 
 function App() {
   return (
     <SystemOverlay>
       <EyeTracker />
+      <AlarmAlert />
     </SystemOverlay>
   );
 }
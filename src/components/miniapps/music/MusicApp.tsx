@@ -7,8 +7,22 @@ import {
   Trash2,
   AlertCircle,
   Loader2,
+  ChevronLeft,
+  ChevronUp,
+  ChevronDown,
+  Disc3,
+  User,
+  ListMusic,
+  ListPlus,
+  Plus,
+  Repeat,
+  Repeat1,
+  Shuffle,
+  SkipBack,
+  SkipForward,
 } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { cn } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
 
@@ -21,20 +35,133 @@ interface RecordingInfo {
   path: string;
   size: number;
   modified: number;
+  format: "wav" | "flac" | "opus";
+  duration_ms: number;
+  sample_rate: number;
+  channels: number;
+  title: string | null;
+  tags: string[];
+  notes: string | null;
 }
 
+interface Waveform {
+  duration_ms: number;
+  peaks: [number, number][];
+}
+
+interface PlayerState {
+  status: "stopped" | "playing" | "paused";
+  path: string | null;
+  position_ms: number;
+  duration_ms: number;
+  queue: string[];
+  index: number | null;
+  shuffle: boolean;
+  repeat: RepeatMode;
+}
+
+type RepeatMode = "off" | "all" | "one";
+
+interface PlayerPosition {
+  path: string;
+  position_ms: number;
+  duration_ms: number;
+}
+
+interface LibraryTrack {
+  path: string;
+  title: string;
+  artist: string | null;
+  album: string | null;
+  album_artist: string | null;
+  track_number: number | null;
+  disc_number: number | null;
+  duration_ms: number;
+  has_cover: boolean;
+  size: number;
+  modified: number;
+}
+
+interface ArtistInfo {
+  name: string;
+  album_count: number;
+  track_count: number;
+}
+
+interface AlbumInfo {
+  title: string;
+  artist: string;
+  track_count: number;
+  duration_ms: number;
+  cover_track: string | null;
+}
+
+interface LibraryStatus {
+  music_dir: string;
+  scanning: boolean;
+  track_count: number;
+}
+
+interface PlaylistInfo {
+  name: string;
+  track_count: number;
+  modified: number;
+}
+
+interface Playlist {
+  name: string;
+  tracks: string[];
+}
+
+type View = "recordings" | "library" | "playlists";
+
+const VIEWS: { id: View; label: string }[] = [
+  { id: "recordings", label: "Recordings" },
+  { id: "library", label: "Library" },
+  { id: "playlists", label: "Playlists" },
+];
+
+const NEXT_REPEAT: Record<RepeatMode, RepeatMode> = {
+  off: "all",
+  all: "one",
+  one: "off",
+};
+
+const WAVEFORM_BUCKETS = 80;
+
 export function MusicApp({ onClose }: MusicAppProps) {
   const [recordings, setRecordings] = useState<RecordingInfo[]>([]);
   const [loading, setLoading] = useState(true);
-  const [currentTrack, setCurrentTrack] = useState<RecordingInfo | null>(null);
+  const [currentPath, setCurrentPath] = useState<string | null>(null);
   const [isPlaying, setIsPlaying] = useState(false);
   const [progress, setProgress] = useState(0);
   const [duration, setDuration] = useState(0);
   const [confirmDelete, setConfirmDelete] = useState<string | null>(null);
   const [deleting, setDeleting] = useState(false);
+  const [waveform, setWaveform] = useState<Waveform | null>(null);
+  const [view, setView] = useState<View>("recordings");
+  const [artists, setArtists] = useState<ArtistInfo[]>([]);
+  const [albums, setAlbums] = useState<AlbumInfo[]>([]);
+  const [libraryTracks, setLibraryTracks] = useState<LibraryTrack[]>([]);
+  const [selectedArtist, setSelectedArtist] = useState<string | null>(null);
+  const [selectedAlbum, setSelectedAlbum] = useState<AlbumInfo | null>(null);
+  const [albumCover, setAlbumCover] = useState<string | null>(null);
+  const [libraryLoading, setLibraryLoading] = useState(false);
+  const [scanning, setScanning] = useState(false);
+  const [shuffle, setShuffle] = useState(false);
+  const [repeat, setRepeat] = useState<RepeatMode>("off");
+  const [playlists, setPlaylists] = useState<PlaylistInfo[]>([]);
+  const [openPlaylist, setOpenPlaylist] = useState<Playlist | null>(null);
+  const [newPlaylistName, setNewPlaylistName] = useState("");
+  const [playlistError, setPlaylistError] = useState<string | null>(null);
+  const [showAddToPlaylist, setShowAddToPlaylist] = useState(false);
+
+  const unlistenStateRef = useRef<UnlistenFn | null>(null);
+  const unlistenPositionRef = useRef<UnlistenFn | null>(null);
 
-  const audioRef = useRef<HTMLAudioElement | null>(null);
-  const progressInterval = useRef<ReturnType<typeof setInterval> | null>(null);
+  const currentTrack = recordings.find((r) => r.path === currentPath) ?? null;
+  const currentLibraryTrack =
+    libraryTracks.find((t) => t.path === currentPath) ?? null;
 
   // Load recordings
   const loadRecordings = useCallback(async () => {
@@ -49,91 +176,284 @@ export function MusicApp({ onClose }: MusicAppProps) {
     }
   }, []);
 
+  // Playback runs in the backend and keeps going after the app is closed
   useEffect(() => {
+    const applyState = (state: PlayerState) => {
+      setCurrentPath(state.path);
+      setIsPlaying(state.status === "playing");
+      setProgress(state.position_ms / 1000);
+      setDuration(state.duration_ms / 1000);
+      setShuffle(state.shuffle);
+      setRepeat(state.repeat);
+    };
+
+    const setup = async () => {
+      unlistenStateRef.current = await listen<PlayerState>(
+        "player-state",
+        (event) => applyState(event.payload)
+      );
+
+      unlistenPositionRef.current = await listen<PlayerPosition>(
+        "player-position",
+        (event) => {
+          setProgress(event.payload.position_ms / 1000);
+          setDuration(event.payload.duration_ms / 1000);
+        }
+      );
+
+      applyState(await invoke<PlayerState>("get_player_state"));
+    };
+
     loadRecordings();
+    setup().catch((err) => console.error("Failed to connect to player:", err));
+
     return () => {
-      // Cleanup audio on unmount
-      if (audioRef.current) {
-        audioRef.current.pause();
-        audioRef.current = null;
-      }
-      if (progressInterval.current) {
-        clearInterval(progressInterval.current);
-      }
+      if (unlistenStateRef.current) unlistenStateRef.current();
+      if (unlistenPositionRef.current) unlistenPositionRef.current();
     };
   }, [loadRecordings]);
 
-  // Play a track
+  // Play a track, queueing the rest of the list after it
   const playTrack = useCallback(
     async (recording: RecordingInfo) => {
-      // Stop current playback
-      if (audioRef.current) {
-        audioRef.current.pause();
-        audioRef.current = null;
+      try {
+        await invoke("player_play", {
+          paths: recordings.map((r) => r.path),
+          index: recordings.indexOf(recording),
+        });
+      } catch (err) {
+        console.error("Failed to play:", err);
+      }
+    },
+    [recordings]
+  );
+
+  // Load the current level of the library (artists > albums > tracks)
+  const loadLibrary = useCallback(async () => {
+    setLibraryLoading(true);
+    try {
+      if (!selectedArtist) {
+        setArtists(await invoke<ArtistInfo[]>("list_library_artists"));
+      } else if (!selectedAlbum) {
+        setAlbums(
+          await invoke<AlbumInfo[]>("list_library_albums", {
+            artist: selectedArtist,
+          })
+        );
+      } else {
+        setLibraryTracks(
+          await invoke<LibraryTrack[]>("list_library_tracks", {
+            artist: selectedArtist,
+            album: selectedAlbum.title,
+          })
+        );
       }
-      if (progressInterval.current) {
-        clearInterval(progressInterval.current);
+    } catch (err) {
+      console.error("Failed to load library:", err);
+    } finally {
+      setLibraryLoading(false);
+    }
+  }, [selectedArtist, selectedAlbum]);
+
+  useEffect(() => {
+    if (view !== "library") return;
+    loadLibrary();
+    invoke<LibraryStatus>("get_library_status")
+      .then((status) => setScanning(status.scanning))
+      .catch((err) => console.error("Failed to get library status:", err));
+
+    // The backend re-indexes when files change
+    const unlistenPromise = listen<LibraryStatus>(
+      "library-updated",
+      (event) => {
+        setScanning(event.payload.scanning);
+        if (!event.payload.scanning) loadLibrary();
       }
+    );
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, [view, loadLibrary]);
+
+  // Cover of the open album
+  useEffect(() => {
+    setAlbumCover(null);
+    if (!selectedAlbum?.cover_track) return;
+    let cancelled = false;
+    invoke<string | null>("get_track_cover", { path: selectedAlbum.cover_track })
+      .then((cover) => {
+        if (!cancelled) setAlbumCover(cover);
+      })
+      .catch((err) => console.error("Failed to load cover:", err));
+    return () => {
+      cancelled = true;
+    };
+  }, [selectedAlbum]);
 
+  // Play a library track, queueing the rest of the album after it
+  const playLibraryTrack = useCallback(
+    async (index: number) => {
       try {
-        const dataUrl = await invoke<string>("read_audio_file", {
-          path: recording.path,
+        await invoke("player_play", {
+          paths: libraryTracks.map((t) => t.path),
+          index,
         });
+      } catch (err) {
+        console.error("Failed to play:", err);
+      }
+    },
+    [libraryTracks]
+  );
 
-        const audio = new Audio(dataUrl);
-        audioRef.current = audio;
-        setCurrentTrack(recording);
-        setProgress(0);
+  // Playlists
+  const loadPlaylists = useCallback(async () => {
+    try {
+      setPlaylists(await invoke<PlaylistInfo[]>("list_playlists"));
+    } catch (err) {
+      console.error("Failed to list playlists:", err);
+    }
+  }, []);
 
-        audio.addEventListener("loadedmetadata", () => {
-          setDuration(audio.duration);
-        });
+  useEffect(() => {
+    if (view === "playlists" || showAddToPlaylist) loadPlaylists();
+  }, [view, showAddToPlaylist, loadPlaylists]);
 
-        audio.addEventListener("ended", () => {
-          setIsPlaying(false);
-          setProgress(0);
-          if (progressInterval.current) {
-            clearInterval(progressInterval.current);
-          }
-        });
+  const openPlaylistByName = useCallback(async (name: string) => {
+    try {
+      setOpenPlaylist(await invoke<Playlist>("get_playlist", { name }));
+    } catch (err) {
+      console.error("Failed to open playlist:", err);
+    }
+  }, []);
 
-        await audio.play();
-        setIsPlaying(true);
+  const createPlaylist = useCallback(async () => {
+    setPlaylistError(null);
+    try {
+      await invoke<Playlist>("create_playlist", { name: newPlaylistName });
+      setNewPlaylistName("");
+      loadPlaylists();
+    } catch (err) {
+      setPlaylistError(String(err));
+    }
+  }, [newPlaylistName, loadPlaylists]);
 
-        progressInterval.current = setInterval(() => {
-          if (audioRef.current) {
-            setProgress(audioRef.current.currentTime);
-          }
-        }, 100);
+  const deletePlaylist = useCallback(
+    async (name: string) => {
+      try {
+        await invoke("delete_playlist", { name });
+        setOpenPlaylist(null);
+        loadPlaylists();
       } catch (err) {
-        console.error("Failed to play:", err);
+        console.error("Failed to delete playlist:", err);
+      }
+    },
+    [loadPlaylists]
+  );
+
+  // Save a reordered / trimmed track list of the open playlist
+  const savePlaylistTracks = useCallback(
+    async (tracks: string[]) => {
+      if (!openPlaylist) return;
+      try {
+        await invoke("save_playlist", { name: openPlaylist.name, tracks });
+        setOpenPlaylist({ ...openPlaylist, tracks });
+      } catch (err) {
+        console.error("Failed to save playlist:", err);
+      }
+    },
+    [openPlaylist]
+  );
+
+  const moveTrack = (from: number, to: number) => {
+    if (!openPlaylist || to < 0 || to >= openPlaylist.tracks.length) return;
+    const tracks = [...openPlaylist.tracks];
+    const [track] = tracks.splice(from, 1);
+    tracks.splice(to, 0, track);
+    savePlaylistTracks(tracks);
+  };
+
+  const addCurrentToPlaylist = useCallback(
+    async (name: string) => {
+      if (!currentPath) return;
+      try {
+        await invoke("add_to_playlist", { name, tracks: [currentPath] });
+      } catch (err) {
+        console.error("Failed to add to playlist:", err);
+      } finally {
+        setShowAddToPlaylist(false);
       }
     },
-    []
+    [currentPath]
   );
 
+  const playPaths = useCallback(async (paths: string[], index: number) => {
+    try {
+      await invoke("player_play", { paths, index });
+    } catch (err) {
+      console.error("Failed to play:", err);
+    }
+  }, []);
+
+  const toggleShuffle = useCallback(() => {
+    invoke("player_set_shuffle", { shuffle: !shuffle }).catch((err) =>
+      console.error("Failed to set shuffle:", err)
+    );
+  }, [shuffle]);
+
+  const cycleRepeat = useCallback(() => {
+    invoke("player_set_repeat", { repeat: NEXT_REPEAT[repeat] }).catch((err) =>
+      console.error("Failed to set repeat:", err)
+    );
+  }, [repeat]);
+
   // Toggle play/pause
   const togglePlayPause = useCallback(() => {
-    if (!audioRef.current) return;
-    if (isPlaying) {
-      audioRef.current.pause();
-      setIsPlaying(false);
-    } else {
-      audioRef.current.play();
-      setIsPlaying(true);
-    }
+    invoke(isPlaying ? "player_pause" : "player_resume").catch((err) =>
+      console.error("Failed to toggle playback:", err)
+    );
   }, [isPlaying]);
 
+  // Load the waveform of the current track (recordings only)
+  const currentRecordingPath = currentTrack?.path ?? null;
+  useEffect(() => {
+    setWaveform(null);
+    if (!currentRecordingPath) return;
+    let cancelled = false;
+    invoke<Waveform>("get_recording_waveform", {
+      path: currentRecordingPath,
+      buckets: WAVEFORM_BUCKETS,
+    })
+      .then((result) => {
+        if (!cancelled) setWaveform(result);
+      })
+      .catch((err) => console.error("Failed to load waveform:", err));
+    return () => {
+      cancelled = true;
+    };
+  }, [currentRecordingPath]);
+
   // Seek
+  const seekTo = useCallback((time: number) => {
+    setProgress(time);
+    invoke("player_seek", { positionMs: Math.round(time * 1000) }).catch(
+      (err) => console.error("Failed to seek:", err)
+    );
+  }, []);
+
   const handleSeek = useCallback(
     (e: React.ChangeEvent<HTMLInputElement>) => {
-      const time = parseFloat(e.target.value);
-      if (audioRef.current) {
-        audioRef.current.currentTime = time;
-        setProgress(time);
-      }
+      seekTo(parseFloat(e.target.value));
     },
-    []
+    [seekTo]
+  );
+
+  const handleWaveformSeek = useCallback(
+    (e: React.MouseEvent<HTMLDivElement>) => {
+      const rect = e.currentTarget.getBoundingClientRect();
+      const fraction = (e.clientX - rect.left) / rect.width;
+      seekTo(Math.max(0, Math.min(1, fraction)) * duration);
+    },
+    [seekTo, duration]
   );
 
   // Delete recording
@@ -141,16 +461,11 @@ export function MusicApp({ onClose }: MusicAppProps) {
     async (path: string) => {
       setDeleting(true);
       try {
-        await invoke("delete_recording", { path });
         // Stop if currently playing this track
-        if (currentTrack?.path === path) {
-          if (audioRef.current) {
-            audioRef.current.pause();
-            audioRef.current = null;
-          }
-          setCurrentTrack(null);
-          setIsPlaying(false);
+        if (currentPath === path) {
+          await invoke("player_stop");
         }
+        await invoke("delete_recording", { path });
         setRecordings((prev) => prev.filter((r) => r.path !== path));
       } catch (err) {
         console.error("Failed to delete:", err);
@@ -159,7 +474,7 @@ export function MusicApp({ onClose }: MusicAppProps) {
         setConfirmDelete(null);
       }
     },
-    [currentTrack]
+    [currentPath]
   );
 
   const formatTime = (sec: number) => {
@@ -168,6 +483,9 @@ export function MusicApp({ onClose }: MusicAppProps) {
     return `${m}:${s.toString().padStart(2, "0")}`;
   };
 
+  const displayName = (rec: RecordingInfo) =>
+    rec.title || rec.filename.replace(/\.(wav|flac|opus|ogg)$/, "");
+
   const formatDate = (timestamp: number) => {
     if (!timestamp) return "";
     const d = new Date(timestamp * 1000);
@@ -185,6 +503,21 @@ export function MusicApp({ onClose }: MusicAppProps) {
     return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
   };
 
+  // Display name for any playable path
+  const trackName = (path: string) => {
+    const rec = recordings.find((r) => r.path === path);
+    if (rec) return displayName(rec);
+    const track = libraryTracks.find((t) => t.path === path);
+    if (track) return track.title;
+    return path.split("/").pop() ?? path;
+  };
+
+  const nowPlayingName = currentTrack
+    ? displayName(currentTrack)
+    : currentLibraryTrack
+      ? currentLibraryTrack.title
+      : (currentPath?.split("/").pop() ?? "");
+
   return (
     <div className="fixed inset-0 z-50 flex flex-col bg-gradient-to-b from-zinc-900 via-zinc-950 to-black">
       {/* Header */}
@@ -192,12 +525,28 @@ export function MusicApp({ onClose }: MusicAppProps) {
         <div className="flex items-center gap-2">
           <Music className="h-6 w-6 text-emerald-400" />
           <h1 className="text-lg font-semibold text-white">Music</h1>
-          {recordings.length > 0 && (
+          {view === "recordings" && recordings.length > 0 && (
             <span className="text-sm text-zinc-500">
               ({recordings.length})
             </span>
           )}
         </div>
+        <div className="flex rounded-full bg-zinc-800/80 p-1">
+          {VIEWS.map((v) => (
+            <button
+              key={v.id}
+              onClick={() => setView(v.id)}
+              className={cn(
+                "rounded-full px-4 py-1.5 text-sm transition-colors",
+                view === v.id
+                  ? "bg-emerald-500 text-white"
+                  : "text-zinc-400 hover:text-white"
+              )}
+            >
+              {v.label}
+            </button>
+          ))}
+        </div>
         <button
           onClick={onClose}
           className="flex h-10 w-10 items-center justify-center rounded-full bg-zinc-800/80 text-zinc-400 transition-all hover:bg-zinc-700 hover:text-white active:scale-95"
@@ -207,106 +556,441 @@ export function MusicApp({ onClose }: MusicAppProps) {
       </div>
 
       {/* Track list */}
-      <div className="flex-1 overflow-y-auto px-4 pb-4">
-        {loading ? (
-          <div className="flex items-center justify-center pt-20">
-            <Loader2 className="h-10 w-10 text-emerald-400 animate-spin" />
-          </div>
-        ) : recordings.length === 0 ? (
-          <div className="flex flex-col items-center justify-center pt-20 text-center">
-            <AlertCircle className="h-16 w-16 text-zinc-600 mb-4" />
-            <h2 className="text-lg font-medium text-zinc-400 mb-2">
-              No recordings yet
-            </h2>
-            <p className="text-sm text-zinc-600">
-              Use the Recorder app to create recordings
-            </p>
-          </div>
-        ) : (
-          <div className="space-y-2">
-            {recordings.map((rec) => (
-              <div
-                key={rec.path}
-                className={cn(
-                  "flex items-center gap-3 rounded-xl px-4 py-3 transition-colors",
-                  currentTrack?.path === rec.path
-                    ? "bg-emerald-500/10 border border-emerald-500/20"
-                    : "bg-zinc-800/50 hover:bg-zinc-800"
-                )}
-              >
-                {/* Play button */}
-                <button
-                  onClick={() =>
-                    currentTrack?.path === rec.path
-                      ? togglePlayPause()
-                      : playTrack(rec)
-                  }
+      {view === "recordings" ? (
+        <div className="flex-1 overflow-y-auto px-4 pb-4">
+          {loading ? (
+            <div className="flex items-center justify-center pt-20">
+              <Loader2 className="h-10 w-10 text-emerald-400 animate-spin" />
+            </div>
+          ) : recordings.length === 0 ? (
+            <div className="flex flex-col items-center justify-center pt-20 text-center">
+              <AlertCircle className="h-16 w-16 text-zinc-600 mb-4" />
+              <h2 className="text-lg font-medium text-zinc-400 mb-2">
+                No recordings yet
+              </h2>
+              <p className="text-sm text-zinc-600">
+                Use the Recorder app to create recordings
+              </p>
+            </div>
+          ) : (
+            <div className="space-y-2">
+              {recordings.map((rec) => (
+                <div
+                  key={rec.path}
                   className={cn(
-                    "flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-full transition-colors",
+                    "flex items-center gap-3 rounded-xl px-4 py-3 transition-colors",
                     currentTrack?.path === rec.path
-                      ? "bg-emerald-500 text-white"
-                      : "bg-zinc-700 text-zinc-400 hover:bg-zinc-600"
+                      ? "bg-emerald-500/10 border border-emerald-500/20"
+                      : "bg-zinc-800/50 hover:bg-zinc-800"
                   )}
                 >
-                  {currentTrack?.path === rec.path && isPlaying ? (
-                    <Pause className="h-4 w-4" />
+                  {/* Play button */}
+                  <button
+                    onClick={() =>
+                      currentTrack?.path === rec.path
+                        ? togglePlayPause()
+                        : playTrack(rec)
+                    }
+                    className={cn(
+                      "flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-full transition-colors",
+                      currentTrack?.path === rec.path
+                        ? "bg-emerald-500 text-white"
+                        : "bg-zinc-700 text-zinc-400 hover:bg-zinc-600"
+                    )}
+                  >
+                    {currentTrack?.path === rec.path && isPlaying ? (
+                      <Pause className="h-4 w-4" />
+                    ) : (
+                      <Play className="h-4 w-4 ml-0.5" />
+                    )}
+                  </button>
+
+                  {/* Track info */}
+                  <div className="flex-1 min-w-0">
+                    <p className="text-sm font-medium text-white truncate">
+                      {displayName(rec)}
+                    </p>
+                    <p className="text-xs text-zinc-500">
+                      {formatDate(rec.modified)} · {formatTime(rec.duration_ms / 1000)} ·{" "}
+                      {formatSize(rec.size)}
+                    </p>
+                  </div>
+
+                  {/* Delete */}
+                  {confirmDelete === rec.path ? (
+                    <div className="flex items-center gap-1">
+                      <Button
+                        size="sm"
+                        variant="ghost"
+                        onClick={() => setConfirmDelete(null)}
+                        className="h-8 px-2 text-xs text-zinc-400"
+                      >
+                        No
+                      </Button>
+                      <Button
+                        size="sm"
+                        onClick={() => deleteRecording(rec.path)}
+                        disabled={deleting}
+                        className="h-8 px-2 text-xs bg-red-600 hover:bg-red-700 text-white"
+                      >
+                        {deleting ? (
+                          <Loader2 className="h-3 w-3 animate-spin" />
+                        ) : (
+                          "Yes"
+                        )}
+                      </Button>
+                    </div>
                   ) : (
-                    <Play className="h-4 w-4 ml-0.5" />
+                    <button
+                      onClick={() => setConfirmDelete(rec.path)}
+                      className="flex h-8 w-8 items-center justify-center rounded-full text-zinc-600 hover:text-red-400 hover:bg-red-500/10 transition-colors"
+                    >
+                      <Trash2 className="h-4 w-4" />
+                    </button>
                   )}
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+      ) : view === "playlists" ? (
+        <div className="flex-1 overflow-y-auto px-4 pb-4">
+          {openPlaylist ? (
+            <>
+              <div className="mb-3 flex items-center justify-between">
+                <button
+                  onClick={() => setOpenPlaylist(null)}
+                  className="flex items-center gap-1 text-sm text-zinc-400 hover:text-white"
+                >
+                  <ChevronLeft className="h-4 w-4" />
+                  Playlists
                 </button>
-
-                {/* Track info */}
-                <div className="flex-1 min-w-0">
-                  <p className="text-sm font-medium text-white truncate">
-                    {rec.filename.replace(/\.wav$/, "")}
-                  </p>
-                  <p className="text-xs text-zinc-500">
-                    {formatDate(rec.modified)} · {formatSize(rec.size)}
-                  </p>
+                <div className="flex items-center gap-2">
+                  <Button
+                    size="sm"
+                    onClick={() => playPaths(openPlaylist.tracks, 0)}
+                    disabled={openPlaylist.tracks.length === 0}
+                    className="h-8 bg-emerald-500 hover:bg-emerald-600 text-white"
+                  >
+                    <Play className="h-3 w-3 mr-1" />
+                    Play
+                  </Button>
+                  <button
+                    onClick={() => deletePlaylist(openPlaylist.name)}
+                    className="flex h-8 w-8 items-center justify-center rounded-full text-zinc-600 hover:text-red-400 hover:bg-red-500/10 transition-colors"
+                  >
+                    <Trash2 className="h-4 w-4" />
+                  </button>
                 </div>
-
-                {/* Delete */}
-                {confirmDelete === rec.path ? (
-                  <div className="flex items-center gap-1">
-                    <Button
-                      size="sm"
-                      variant="ghost"
-                      onClick={() => setConfirmDelete(null)}
-                      className="h-8 px-2 text-xs text-zinc-400"
+              </div>
+              <h2 className="mb-3 text-lg font-semibold text-white truncate">
+                {openPlaylist.name}
+              </h2>
+              {openPlaylist.tracks.length === 0 ? (
+                <p className="pt-10 text-center text-sm text-zinc-600">
+                  Add tracks from the player bar while they play
+                </p>
+              ) : (
+                <div className="space-y-2">
+                  {openPlaylist.tracks.map((path, index) => (
+                    <div
+                      key={`${index}-${path}`}
+                      className={cn(
+                        "flex items-center gap-3 rounded-xl px-4 py-3 transition-colors",
+                        currentPath === path
+                          ? "bg-emerald-500/10 border border-emerald-500/20"
+                          : "bg-zinc-800/50 hover:bg-zinc-800"
+                      )}
                     >
-                      No
-                    </Button>
-                    <Button
-                      size="sm"
-                      onClick={() => deleteRecording(rec.path)}
-                      disabled={deleting}
-                      className="h-8 px-2 text-xs bg-red-600 hover:bg-red-700 text-white"
+                      <button
+                        onClick={() =>
+                          currentPath === path
+                            ? togglePlayPause()
+                            : playPaths(openPlaylist.tracks, index)
+                        }
+                        className={cn(
+                          "flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-full transition-colors",
+                          currentPath === path
+                            ? "bg-emerald-500 text-white"
+                            : "bg-zinc-700 text-zinc-400 hover:bg-zinc-600"
+                        )}
+                      >
+                        {currentPath === path && isPlaying ? (
+                          <Pause className="h-4 w-4" />
+                        ) : (
+                          <Play className="h-4 w-4 ml-0.5" />
+                        )}
+                      </button>
+                      <p className="flex-1 min-w-0 text-sm font-medium text-white truncate">
+                        {trackName(path)}
+                      </p>
+                      <button
+                        onClick={() => moveTrack(index, index - 1)}
+                        className="flex h-8 w-8 items-center justify-center rounded-full text-zinc-500 hover:text-white"
+                      >
+                        <ChevronUp className="h-4 w-4" />
+                      </button>
+                      <button
+                        onClick={() => moveTrack(index, index + 1)}
+                        className="flex h-8 w-8 items-center justify-center rounded-full text-zinc-500 hover:text-white"
+                      >
+                        <ChevronDown className="h-4 w-4" />
+                      </button>
+                      <button
+                        onClick={() =>
+                          savePlaylistTracks(
+                            openPlaylist.tracks.filter((_, i) => i !== index)
+                          )
+                        }
+                        className="flex h-8 w-8 items-center justify-center rounded-full text-zinc-600 hover:text-red-400 hover:bg-red-500/10 transition-colors"
+                      >
+                        <X className="h-4 w-4" />
+                      </button>
+                    </div>
+                  ))}
+                </div>
+              )}
+            </>
+          ) : (
+            <>
+              <div className="mb-3 flex items-center gap-2">
+                <input
+                  value={newPlaylistName}
+                  onChange={(e) => setNewPlaylistName(e.target.value)}
+                  onKeyDown={(e) => e.key === "Enter" && createPlaylist()}
+                  placeholder="New playlist"
+                  className="flex-1 rounded-xl bg-zinc-800/80 px-4 py-2 text-sm text-white placeholder:text-zinc-500 outline-none focus:ring-1 focus:ring-emerald-500"
+                />
+                <Button
+                  size="sm"
+                  onClick={createPlaylist}
+                  disabled={!newPlaylistName.trim()}
+                  className="h-9 bg-emerald-500 hover:bg-emerald-600 text-white"
+                >
+                  <Plus className="h-4 w-4" />
+                </Button>
+              </div>
+              {playlistError && (
+                <p className="mb-3 text-xs text-red-400">{playlistError}</p>
+              )}
+              {playlists.length === 0 ? (
+                <div className="flex flex-col items-center justify-center pt-20 text-center">
+                  <ListMusic className="h-16 w-16 text-zinc-600 mb-4" />
+                  <h2 className="text-lg font-medium text-zinc-400 mb-2">
+                    No playlists yet
+                  </h2>
+                </div>
+              ) : (
+                <div className="space-y-2">
+                  {playlists.map((playlist) => (
+                    <button
+                      key={playlist.name}
+                      onClick={() => openPlaylistByName(playlist.name)}
+                      className="flex w-full items-center gap-3 rounded-xl bg-zinc-800/50 px-4 py-3 text-left transition-colors hover:bg-zinc-800"
                     >
-                      {deleting ? (
-                        <Loader2 className="h-3 w-3 animate-spin" />
-                      ) : (
-                        "Yes"
-                      )}
-                    </Button>
-                  </div>
+                      <div className="flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-lg bg-zinc-700 text-zinc-400">
+                        <ListMusic className="h-4 w-4" />
+                      </div>
+                      <div className="flex-1 min-w-0">
+                        <p className="text-sm font-medium text-white truncate">
+                          {playlist.name}
+                        </p>
+                        <p className="text-xs text-zinc-500">
+                          {playlist.track_count} track
+                          {playlist.track_count === 1 ? "" : "s"}
+                        </p>
+                      </div>
+                    </button>
+                  ))}
+                </div>
+              )}
+            </>
+          )}
+        </div>
+      ) : (
+        <div className="flex-1 overflow-y-auto px-4 pb-4">
+          {selectedArtist && (
+            <button
+              onClick={() =>
+                selectedAlbum ? setSelectedAlbum(null) : setSelectedArtist(null)
+              }
+              className="mb-3 flex items-center gap-1 text-sm text-zinc-400 hover:text-white"
+            >
+              <ChevronLeft className="h-4 w-4" />
+              {selectedAlbum ? selectedArtist : "Artists"}
+            </button>
+          )}
+
+          {libraryLoading && artists.length + albums.length + libraryTracks.length === 0 ? (
+            <div className="flex items-center justify-center pt-20">
+              <Loader2 className="h-10 w-10 text-emerald-400 animate-spin" />
+            </div>
+          ) : !selectedArtist ? (
+            artists.length === 0 ? (
+              <div className="flex flex-col items-center justify-center pt-20 text-center">
+                {scanning ? (
+                  <Loader2 className="h-16 w-16 text-zinc-600 mb-4 animate-spin" />
                 ) : (
+                  <AlertCircle className="h-16 w-16 text-zinc-600 mb-4" />
+                )}
+                <h2 className="text-lg font-medium text-zinc-400 mb-2">
+                  {scanning ? "Scanning your music..." : "No music found"}
+                </h2>
+                <p className="text-sm text-zinc-600">
+                  Add MP3, FLAC, OGG or M4A files to your Music folder
+                </p>
+              </div>
+            ) : (
+              <div className="space-y-2">
+                {artists.map((artist) => (
                   <button
-                    onClick={() => setConfirmDelete(rec.path)}
-                    className="flex h-8 w-8 items-center justify-center rounded-full text-zinc-600 hover:text-red-400 hover:bg-red-500/10 transition-colors"
+                    key={artist.name}
+                    onClick={() => setSelectedArtist(artist.name)}
+                    className="flex w-full items-center gap-3 rounded-xl bg-zinc-800/50 px-4 py-3 text-left transition-colors hover:bg-zinc-800"
                   >
-                    <Trash2 className="h-4 w-4" />
+                    <div className="flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-full bg-zinc-700 text-zinc-400">
+                      <User className="h-4 w-4" />
+                    </div>
+                    <div className="flex-1 min-w-0">
+                      <p className="text-sm font-medium text-white truncate">
+                        {artist.name}
+                      </p>
+                      <p className="text-xs text-zinc-500">
+                        {artist.album_count} album{artist.album_count === 1 ? "" : "s"} ·{" "}
+                        {artist.track_count} track{artist.track_count === 1 ? "" : "s"}
+                      </p>
+                    </div>
                   </button>
+                ))}
+              </div>
+            )
+          ) : !selectedAlbum ? (
+            <div className="space-y-2">
+              {albums.map((album) => (
+                <button
+                  key={album.title}
+                  onClick={() => setSelectedAlbum(album)}
+                  className="flex w-full items-center gap-3 rounded-xl bg-zinc-800/50 px-4 py-3 text-left transition-colors hover:bg-zinc-800"
+                >
+                  <div className="flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-lg bg-zinc-700 text-zinc-400">
+                    <Disc3 className="h-4 w-4" />
+                  </div>
+                  <div className="flex-1 min-w-0">
+                    <p className="text-sm font-medium text-white truncate">
+                      {album.title}
+                    </p>
+                    <p className="text-xs text-zinc-500">
+                      {album.track_count} track{album.track_count === 1 ? "" : "s"} ·{" "}
+                      {formatTime(album.duration_ms / 1000)}
+                    </p>
+                  </div>
+                </button>
+              ))}
+            </div>
+          ) : (
+            <>
+              <div className="mb-4 flex items-center gap-4">
+                {albumCover ? (
+                  <img
+                    src={albumCover}
+                    alt=""
+                    className="h-24 w-24 rounded-lg object-cover"
+                  />
+                ) : (
+                  <div className="flex h-24 w-24 items-center justify-center rounded-lg bg-zinc-800 text-zinc-500">
+                    <Disc3 className="h-10 w-10" />
+                  </div>
                 )}
+                <div className="min-w-0">
+                  <h2 className="text-lg font-semibold text-white truncate">
+                    {selectedAlbum.title}
+                  </h2>
+                  <p className="text-sm text-zinc-400 truncate">
+                    {selectedAlbum.artist}
+                  </p>
+                </div>
               </div>
-            ))}
-          </div>
-        )}
-      </div>
+              <div className="space-y-2">
+                {libraryTracks.map((track, index) => (
+                  <div
+                    key={track.path}
+                    className={cn(
+                      "flex items-center gap-3 rounded-xl px-4 py-3 transition-colors",
+                      currentPath === track.path
+                        ? "bg-emerald-500/10 border border-emerald-500/20"
+                        : "bg-zinc-800/50 hover:bg-zinc-800"
+                    )}
+                  >
+                    <button
+                      onClick={() =>
+                        currentPath === track.path
+                          ? togglePlayPause()
+                          : playLibraryTrack(index)
+                      }
+                      className={cn(
+                        "flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-full transition-colors",
+                        currentPath === track.path
+                          ? "bg-emerald-500 text-white"
+                          : "bg-zinc-700 text-zinc-400 hover:bg-zinc-600"
+                      )}
+                    >
+                      {currentPath === track.path && isPlaying ? (
+                        <Pause className="h-4 w-4" />
+                      ) : (
+                        <Play className="h-4 w-4 ml-0.5" />
+                      )}
+                    </button>
+                    <div className="flex-1 min-w-0">
+                      <p className="text-sm font-medium text-white truncate">
+                        {track.track_number ? `${track.track_number}. ` : ""}
+                        {track.title}
+                      </p>
+                      <p className="text-xs text-zinc-500 truncate">
+                        {track.artist || selectedAlbum.artist} ·{" "}
+                        {formatTime(track.duration_ms / 1000)}
+                      </p>
+                    </div>
+                  </div>
+                ))}
+              </div>
+            </>
+          )}
+        </div>
+      )}
 
       {/* Now Playing bar */}
-      {currentTrack && (
-        <div className="border-t border-zinc-800 bg-zinc-900/80 px-4 py-3">
+      {currentPath && (
+        <div className="relative border-t border-zinc-800 bg-zinc-900/80 px-4 py-3">
+          {showAddToPlaylist && (
+            <div className="absolute bottom-full right-4 mb-2 w-56 rounded-xl border border-zinc-700 bg-zinc-900 p-2 shadow-xl">
+              {playlists.length === 0 ? (
+                <p className="px-2 py-1 text-xs text-zinc-500">
+                  Create a playlist first
+                </p>
+              ) : (
+                playlists.map((playlist) => (
+                  <button
+                    key={playlist.name}
+                    onClick={() => addCurrentToPlaylist(playlist.name)}
+                    className="block w-full truncate rounded-lg px-2 py-1.5 text-left text-sm text-zinc-300 hover:bg-zinc-800"
+                  >
+                    {playlist.name}
+                  </button>
+                ))
+              )}
+            </div>
+          )}
           <div className="flex items-center gap-3">
+            <button
+              onClick={() =>
+                invoke("player_previous").catch((err) =>
+                  console.error("Failed to skip:", err)
+                )
+              }
+              className="flex h-8 w-8 flex-shrink-0 items-center justify-center rounded-full text-zinc-400 hover:text-white"
+            >
+              <SkipBack className="h-4 w-4" />
+            </button>
             <button
               onClick={togglePlayPause}
               className="flex h-10 w-10 flex-shrink-0 items-center justify-center rounded-full bg-emerald-500 text-white"
@@ -319,26 +1003,87 @@ export function MusicApp({ onClose }: MusicAppProps) {
             </button>
             <div className="flex-1 min-w-0">
               <p className="text-sm text-white truncate">
-                {currentTrack.filename.replace(/\.wav$/, "")}
+                {nowPlayingName}
               </p>
               <div className="flex items-center gap-2 mt-1">
                 <span className="text-[10px] text-zinc-500 w-8 text-right">
                   {formatTime(progress)}
                 </span>
-                <input
-                  type="range"
-                  min={0}
-                  max={duration || 0}
-                  step={0.1}
-                  value={progress}
-                  onChange={handleSeek}
-                  className="flex-1 h-1 accent-emerald-500 cursor-pointer"
-                />
+                {waveform && waveform.peaks.length > 0 ? (
+                  <div
+                    onClick={handleWaveformSeek}
+                    className="flex flex-1 h-8 items-center gap-px cursor-pointer"
+                  >
+                    {waveform.peaks.map(([min, max], i) => (
+                      <div
+                        key={i}
+                        className={cn(
+                          "flex-1 rounded-sm",
+                          duration &&
+                            i / waveform.peaks.length < progress / duration
+                            ? "bg-emerald-500"
+                            : "bg-zinc-600"
+                        )}
+                        style={{
+                          height: `${Math.max(8, ((max - min) / 2) * 100)}%`,
+                        }}
+                      />
+                    ))}
+                  </div>
+                ) : (
+                  <input
+                    type="range"
+                    min={0}
+                    max={duration || 0}
+                    step={0.1}
+                    value={progress}
+                    onChange={handleSeek}
+                    className="flex-1 h-1 accent-emerald-500 cursor-pointer"
+                  />
+                )}
                 <span className="text-[10px] text-zinc-500 w-8">
                   {formatTime(duration)}
                 </span>
               </div>
             </div>
+            <button
+              onClick={() =>
+                invoke("player_next").catch((err) =>
+                  console.error("Failed to skip:", err)
+                )
+              }
+              className="flex h-8 w-8 flex-shrink-0 items-center justify-center rounded-full text-zinc-400 hover:text-white"
+            >
+              <SkipForward className="h-4 w-4" />
+            </button>
+            <button
+              onClick={toggleShuffle}
+              className={cn(
+                "flex h-8 w-8 flex-shrink-0 items-center justify-center rounded-full",
+                shuffle ? "text-emerald-400" : "text-zinc-500 hover:text-white"
+              )}
+            >
+              <Shuffle className="h-4 w-4" />
+            </button>
+            <button
+              onClick={cycleRepeat}
+              className={cn(
+                "flex h-8 w-8 flex-shrink-0 items-center justify-center rounded-full",
+                repeat !== "off" ? "text-emerald-400" : "text-zinc-500 hover:text-white"
+              )}
+            >
+              {repeat === "one" ? (
+                <Repeat1 className="h-4 w-4" />
+              ) : (
+                <Repeat className="h-4 w-4" />
+              )}
+            </button>
+            <button
+              onClick={() => setShowAddToPlaylist((v) => !v)}
+              className="flex h-8 w-8 flex-shrink-0 items-center justify-center rounded-full text-zinc-500 hover:text-white"
+            >
+              <ListPlus className="h-4 w-4" />
+            </button>
           </div>
         </div>
       )}
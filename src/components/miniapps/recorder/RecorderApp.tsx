@@ -3,6 +3,8 @@ import {
   Mic,
   X,
   Square,
+  Pause,
+  Play,
   AlertCircle,
   Loader2,
 } from "lucide-react";
@@ -17,6 +19,7 @@ interface RecorderAppProps {
 
 interface RecordingStatus {
   recording: boolean;
+  paused: boolean;
   duration_ms: number;
 }
 
@@ -26,14 +29,18 @@ interface RecordingSaved {
   duration_ms: number;
   success: boolean;
   error: string | null;
+  stop_reason: "user" | "silence" | "error" | "storage_full";
+  trimmed_ms: number;
 }
 
 export function RecorderApp({ onClose }: RecorderAppProps) {
   const [isRecording, setIsRecording] = useState(false);
+  const [isPaused, setIsPaused] = useState(false);
   const [durationMs, setDurationMs] = useState(0);
   const [error, setError] = useState<string | null>(null);
   const [saving, setSaving] = useState(false);
   const [lastSaved, setLastSaved] = useState<string | null>(null);
+  const [autoStopped, setAutoStopped] = useState(false);
   const [showSaved, setShowSaved] = useState(false);
 
   const unlistenStatusRef = useRef<UnlistenFn | null>(null);
@@ -46,6 +53,7 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
         "recording-status",
         (event) => {
           setDurationMs(event.payload.duration_ms);
+          setIsPaused(event.payload.paused);
         }
       );
 
@@ -53,6 +61,10 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
         "recording-saved",
         (event) => {
           setSaving(false);
+          // Recordings can end on their own (silence auto-stop, errors)
+          setIsRecording(false);
+          setIsPaused(false);
+          setAutoStopped(event.payload.stop_reason === "silence");
           if (event.payload.success) {
             setLastSaved(event.payload.filename);
             setShowSaved(true);
@@ -68,6 +80,7 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
         (event) => {
           setError(event.payload);
           setIsRecording(false);
+          setIsPaused(false);
         }
       );
 
@@ -87,6 +100,7 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
 
   const startRecording = useCallback(async () => {
     setError(null);
+    setAutoStopped(false);
     setDurationMs(0);
     try {
       await invoke("start_recording");
@@ -101,12 +115,24 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
     try {
       await invoke("stop_recording");
       setIsRecording(false);
+      setIsPaused(false);
     } catch (err) {
       setError(String(err));
       setSaving(false);
     }
   }, []);
 
+  const togglePause = useCallback(async () => {
+    try {
+      const status = await invoke<RecordingStatus>(
+        isPaused ? "resume_recording" : "pause_recording"
+      );
+      setIsPaused(status.paused);
+    } catch (err) {
+      setError(String(err));
+    }
+  }, [isPaused]);
+
   const handleClose = useCallback(async () => {
     if (isRecording) {
       await invoke("stop_recording").catch(() => {});
@@ -151,7 +177,7 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
               : "border-zinc-700"
           )}
         >
-          {isRecording && (
+          {isRecording && !isPaused && (
             <div className="absolute inset-0 rounded-full border-4 border-red-500/30 animate-ping" />
           )}
           <Mic
@@ -174,9 +200,13 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
           </p>
           <p className="mt-2 text-sm text-zinc-500">
             {isRecording
-              ? "Recording..."
+              ? isPaused
+                ? "Paused"
+                : "Recording..."
               : saving
               ? "Saving..."
+              : autoStopped
+              ? "Stopped after silence"
               : "Tap to record"}
           </p>
         </div>
@@ -198,7 +228,25 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
       </div>
 
       {/* Controls */}
-      <div className="px-6 py-8 flex items-center justify-center">
+      <div className="px-6 py-8 flex items-center justify-center gap-6">
+        {isRecording && (
+          <button
+            onClick={togglePause}
+            disabled={saving}
+            className={cn(
+              "flex h-14 w-14 items-center justify-center rounded-full",
+              "bg-zinc-800 transition-all",
+              "hover:bg-zinc-700 active:scale-95",
+              "disabled:opacity-50"
+            )}
+          >
+            {isPaused ? (
+              <Play className="h-6 w-6 text-white fill-white" />
+            ) : (
+              <Pause className="h-6 w-6 text-white fill-white" />
+            )}
+          </button>
+        )}
         {isRecording ? (
           <button
             onClick={stopRecording}
@@ -0,0 +1,112 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { AlarmClock, BellOff, Timer } from 'lucide-react';
+import { Button } from '@/components/ui/button';
+import { cn } from '@/lib/utils';
+
+interface FiredAlarm {
+  id: string;
+  kind: 'alarm' | 'timer';
+  label: string;
+  fired_at: number; // unix seconds
+}
+
+interface AlarmStatus {
+  ringing: FiredAlarm[];
+}
+
+interface AlarmStopped {
+  id: string;
+  reason: 'dismissed' | 'snoozed' | 'timed_out';
+}
+
+function formatTime(unixSecs: number) {
+  return new Date(unixSecs * 1000).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
+}
+
+/**
+ * Full-screen alert for ringing alarms and timers, on top of everything.
+ * The scheduler rings in Rust; this only offers snooze and dismiss.
+ */
+export function AlarmAlert() {
+  const [ringing, setRinging] = useState<FiredAlarm[]>([]);
+
+  useEffect(() => {
+    // Something may already be ringing when the UI (re)loads
+    invoke<AlarmStatus>('get_alarms')
+      .then((status) => setRinging(status.ringing))
+      .catch((error) => console.error('Failed to load alarms:', error));
+
+    const unlistenFiredPromise = listen<FiredAlarm>('alarm-fired', (event) => {
+      setRinging((current) => [...current.filter((a) => a.id !== event.payload.id), event.payload]);
+    });
+    const unlistenStoppedPromise = listen<AlarmStopped>('alarm-stopped', (event) => {
+      setRinging((current) => current.filter((a) => a.id !== event.payload.id));
+    });
+
+    return () => {
+      unlistenFiredPromise.then((unlisten) => unlisten());
+      unlistenStoppedPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  const alarm = ringing[ringing.length - 1];
+  if (!alarm) {
+    return null;
+  }
+
+  const snooze = () => {
+    invoke('snooze_alarm', { id: alarm.id }).catch((error) => console.error('Failed to snooze:', error));
+  };
+  const dismiss = () => {
+    invoke('dismiss_alarm', { id: alarm.id }).catch((error) => console.error('Failed to dismiss:', error));
+  };
+
+  const Icon = alarm.kind === 'timer' ? Timer : AlarmClock;
+  const title = alarm.label || (alarm.kind === 'timer' ? "Time's up" : 'Alarm');
+
+  return (
+    <div className="fixed inset-0 z-[1100] flex items-center justify-center bg-black/80 backdrop-blur-md">
+      <div className="flex flex-col items-center gap-8 px-8 text-center">
+        <div className="flex items-center justify-center w-24 h-24 rounded-full bg-amber-500/15 text-amber-400 animate-pulse">
+          <Icon className="h-12 w-12" />
+        </div>
+
+        <div className="space-y-2">
+          <div className="text-5xl font-semibold text-white tabular-nums">{formatTime(alarm.fired_at)}</div>
+          <div className="text-xl text-white/70">{title}</div>
+          {ringing.length > 1 && (
+            <div className="text-sm text-white/40">{ringing.length - 1} more ringing</div>
+          )}
+        </div>
+
+        <div className="flex items-center gap-4">
+          <Button
+            variant="ghost"
+            onClick={snooze}
+            className={cn(
+              "px-8 py-4 h-auto rounded-2xl text-lg",
+              "bg-white/10 hover:bg-white/15 border border-white/10",
+              "text-white/90 hover:text-white"
+            )}
+          >
+            Snooze
+          </Button>
+          <Button
+            variant="ghost"
+            onClick={dismiss}
+            className={cn(
+              "flex items-center gap-2 px-8 py-4 h-auto rounded-2xl text-lg",
+              "bg-amber-500/20 hover:bg-amber-500/30 border border-amber-500/30",
+              "text-amber-300 hover:text-amber-200"
+            )}
+          >
+            <BellOff className="h-5 w-5" />
+            Dismiss
+          </Button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -1,11 +1,13 @@
 import { useEffect, useState, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Sun, Volume2, VolumeX, Volume1, Settings, X, GripHorizontal, Home } from 'lucide-react';
+import { listen } from '@tauri-apps/api/event';
+import { Sun, Volume2, VolumeX, Volume1, Mic, MicOff, Settings, X, GripHorizontal, Home } from 'lucide-react';
 import { useSwipeGesture } from '@/hooks/useSwipeGesture';
 import { Slider } from '@/components/ui/slider';
 import { Button } from '@/components/ui/button';
 import { cn } from '@/lib/utils';
 import { MiniAppsPage } from '@/components/miniapps/MiniAppsPage';
+import type { AudioOutput, VolumeInfo } from '@/types/system';
 
 interface SystemOverlayProps {
   children?: React.ReactNode;
@@ -14,10 +16,18 @@ interface SystemOverlayProps {
 export function SystemOverlay({ children }: SystemOverlayProps) {
   const [brightness, setBrightness] = useState(50);
   const [volume, setVolume] = useState(50);
+  const [muted, setMuted] = useState(false);
+  const [outputs, setOutputs] = useState<AudioOutput[]>([]);
+  const [micGain, setMicGain] = useState(100);
+  const [micMuted, setMicMuted] = useState(false);
   const [_isInitialized, setIsInitialized] = useState(false);
   const [showMiniApps, setShowMiniApps] = useState(false);
   const autoDismissRef = useRef<number | null>(null);
   const lastInteractionRef = useRef<number>(Date.now());
+  // When the sliders were last moved here, to ignore the echo of our own changes
+  const lastBrightnessChangeRef = useRef(0);
+  const lastVolumeChangeRef = useRef(0);
+  const lastMicChangeRef = useRef(0);
 
   const { isActive, progress, isDragging, dismiss, show } = useSwipeGesture({
     edgeThreshold: 40,
@@ -66,15 +76,56 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
     return () => clearAutoDismiss();
   }, [isActive, clearAutoDismiss, resetAutoDismiss]);
 
+  // Follow changes made elsewhere (hardware keys, voice agent, SSH)
+  useEffect(() => {
+    const unlistenVolumePromise = listen<VolumeInfo>('volume-changed', (event) => {
+      if (Date.now() - lastVolumeChangeRef.current < 1000) {
+        return;
+      }
+      setVolume(event.payload.level);
+      setMuted(event.payload.muted);
+    });
+    // The mute state is always taken over, it must never show the wrong one
+    const unlistenMicPromise = listen<VolumeInfo>('mic-input-changed', (event) => {
+      setMicMuted(event.payload.muted);
+      if (Date.now() - lastMicChangeRef.current < 1000) {
+        return;
+      }
+      setMicGain(event.payload.level);
+    });
+    const unlistenBrightnessPromise = listen<number>('brightness-changed', (event) => {
+      if (Date.now() - lastBrightnessChangeRef.current < 1000) {
+        return;
+      }
+      setBrightness(event.payload);
+    });
+
+    return () => {
+      unlistenVolumePromise.then((unlisten) => unlisten());
+      unlistenMicPromise.then((unlisten) => unlisten());
+      unlistenBrightnessPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
   const loadCurrentValues = async () => {
     try {
       const [currentBrightness, currentVolume] = await Promise.all([
         invoke<number>('get_brightness').catch(() => 50),
-        invoke<number>('get_volume').catch(() => 50),
+        invoke<VolumeInfo>('get_volume_info').catch(() => ({ level: 50, muted: false })),
       ]);
       setBrightness(currentBrightness);
-      setVolume(currentVolume);
+      setVolume(currentVolume.level);
+      setMuted(currentVolume.muted);
       setIsInitialized(true);
+      invoke<VolumeInfo>('get_input_volume_info')
+        .then((info) => {
+          setMicGain(info.level);
+          setMicMuted(info.muted);
+        })
+        .catch((error) => console.error('Failed to load microphone state:', error));
+      invoke<AudioOutput[]>('list_audio_outputs')
+        .then(setOutputs)
+        .catch(() => setOutputs([]));
     } catch (error) {
       console.error('Failed to load system values:', error);
       setIsInitialized(true);
@@ -84,6 +135,7 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
   const handleBrightnessChange = useCallback(async (value: number[]) => {
     const newValue = value[0];
     setBrightness(newValue);
+    lastBrightnessChangeRef.current = Date.now();
     resetAutoDismiss();
     try {
       await invoke('set_brightness', { level: newValue });
@@ -95,6 +147,7 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
   const handleVolumeChange = useCallback(async (value: number[]) => {
     const newValue = value[0];
     setVolume(newValue);
+    lastVolumeChangeRef.current = Date.now();
     resetAutoDismiss();
     try {
       await invoke('set_volume', { level: newValue });
@@ -103,6 +156,50 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
     }
   }, [resetAutoDismiss]);
 
+  const handleToggleMute = useCallback(async () => {
+    resetAutoDismiss();
+    try {
+      const info = await invoke<VolumeInfo>('toggle_mute');
+      setVolume(info.level);
+      setMuted(info.muted);
+    } catch (error) {
+      console.error('Failed to toggle mute:', error);
+    }
+  }, [resetAutoDismiss]);
+
+  const handleMicGainChange = useCallback(async (value: number[]) => {
+    const newValue = value[0];
+    setMicGain(newValue);
+    lastMicChangeRef.current = Date.now();
+    resetAutoDismiss();
+    try {
+      await invoke('set_input_volume', { level: newValue });
+    } catch (error) {
+      console.error('Failed to set microphone gain:', error);
+    }
+  }, [resetAutoDismiss]);
+
+  const handleToggleMicMute = useCallback(async () => {
+    resetAutoDismiss();
+    try {
+      const info = await invoke<VolumeInfo>('toggle_input_mute');
+      setMicGain(info.level);
+      setMicMuted(info.muted);
+    } catch (error) {
+      console.error('Failed to toggle microphone mute:', error);
+    }
+  }, [resetAutoDismiss]);
+
+  const handleOutputSelect = useCallback(async (id: string, port: string | null = null) => {
+    resetAutoDismiss();
+    try {
+      await invoke('set_default_audio_output', { id, port });
+      setOutputs(await invoke<AudioOutput[]>('list_audio_outputs'));
+    } catch (error) {
+      console.error('Failed to switch audio output:', error);
+    }
+  }, [resetAutoDismiss]);
+
   const handleBackdropClick = (e: React.MouseEvent) => {
     if (e.target === e.currentTarget) {
       dismiss();
@@ -114,7 +211,7 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
   };
 
   // Get volume icon based on level
-  const VolumeIcon = volume === 0 ? VolumeX : volume < 50 ? Volume1 : Volume2;
+  const VolumeIcon = muted || volume === 0 ? VolumeX : volume < 50 ? Volume1 : Volume2;
 
   // Calculate transform based on drag state
   const getTransform = () => {
@@ -145,6 +242,8 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
     );
   }
 
+  const defaultOutput = outputs.find((output) => output.is_default);
+
   return (
     <>
       {children}
@@ -231,9 +330,17 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
 
           {/* Volume Control */}
           <div className="flex items-center gap-4">
-            <div className="flex items-center justify-center w-11 h-11 rounded-xl bg-blue-500/15 text-blue-400">
+            <button
+              type="button"
+              onClick={handleToggleMute}
+              title={muted ? 'Unmute' : 'Mute'}
+              className={cn(
+                "flex items-center justify-center w-11 h-11 rounded-xl transition-colors",
+                muted ? "bg-white/10 text-white/40" : "bg-blue-500/15 text-blue-400"
+              )}
+            >
               <VolumeIcon className="h-5 w-5" />
-            </div>
+            </button>
             
             <div className="flex-1 relative">
               <Slider
@@ -255,6 +362,91 @@ export function SystemOverlay({ children }: SystemOverlayProps) {
             </span>
           </div>
 
+          {/* Microphone Control */}
+          <div className="flex items-center gap-4">
+            <button
+              type="button"
+              onClick={handleToggleMicMute}
+              title={micMuted ? 'Unmute microphone' : 'Mute microphone'}
+              className={cn(
+                "flex items-center justify-center w-11 h-11 rounded-xl transition-colors",
+                micMuted
+                  ? "bg-red-500/20 text-red-400 ring-1 ring-red-500/50"
+                  : "bg-emerald-500/15 text-emerald-400"
+              )}
+            >
+              {micMuted ? <MicOff className="h-5 w-5" /> : <Mic className="h-5 w-5" />}
+            </button>
+
+            <div className={cn("flex-1 relative", micMuted && "opacity-40")}>
+              <Slider
+                value={[micGain]}
+                onValueChange={handleMicGainChange}
+                onPointerDown={handleSliderInteraction}
+                min={0}
+                max={150}
+                step={1}
+                className="py-2"
+                trackClassName="h-3 bg-white/10"
+                rangeClassName="bg-gradient-to-r from-emerald-500 to-emerald-400"
+                thumbClassName="border-emerald-400 shadow-emerald-500/30"
+              />
+            </div>
+
+            <span
+              className={cn(
+                "w-12 text-right text-sm font-medium tabular-nums",
+                micMuted ? "text-red-400" : "text-white/70"
+              )}
+            >
+              {micMuted ? 'Off' : `${micGain}%`}
+            </span>
+          </div>
+
+          {/* Audio Output Selection */}
+          {outputs.length > 1 && (
+            <div className="flex flex-wrap items-center justify-center gap-2">
+              {outputs.map((output) => (
+                <button
+                  key={output.id}
+                  type="button"
+                  onClick={() => handleOutputSelect(output.id)}
+                  className={cn(
+                    "px-3 py-1.5 rounded-lg text-xs font-medium border transition-colors",
+                    output.is_default
+                      ? "bg-blue-500/20 border-blue-500/40 text-blue-300"
+                      : "bg-white/5 border-white/10 text-white/60 hover:bg-white/10",
+                    output.available === false && "opacity-40"
+                  )}
+                >
+                  {output.name}
+                </button>
+              ))}
+            </div>
+          )}
+
+          {/* Port Selection (speakers / headphones) of the current output */}
+          {defaultOutput && defaultOutput.ports.length > 1 && (
+            <div className="flex flex-wrap items-center justify-center gap-2">
+              {defaultOutput.ports.map((port) => (
+                <button
+                  key={port.id}
+                  type="button"
+                  onClick={() => handleOutputSelect(defaultOutput.id, port.id)}
+                  className={cn(
+                    "px-2.5 py-1 rounded-md text-[11px] font-medium border transition-colors",
+                    port.id === defaultOutput.active_port
+                      ? "bg-blue-500/20 border-blue-500/40 text-blue-300"
+                      : "bg-white/5 border-white/10 text-white/50 hover:bg-white/10",
+                    port.available === false && "opacity-40"
+                  )}
+                >
+                  {port.name}
+                </button>
+              ))}
+            </div>
+          )}
+
           {/* Action Buttons Row */}
           <div className="flex items-center justify-center gap-4 pt-2">
             {/* Home Button - Opens Mini Apps */}
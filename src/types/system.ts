@@ -6,10 +6,25 @@ export interface BrightnessInfo {
 }
 
 export interface VolumeInfo {
-  level: number;    // Volume level percentage (0-100)
+  level: number;    // Volume level percentage (0-100, mic gain up to 150)
   muted: boolean;   // Whether audio is muted
 }
 
+export interface AudioPort {
+  id: string;
+  name: string;
+  available: boolean | null;  // Something plugged in, null when unknown
+}
+
+export interface AudioOutput {
+  id: string;                 // Sink name, or "hw:CARD,DEVICE" on plain ALSA
+  name: string;
+  is_default: boolean;
+  available: boolean | null;
+  ports: AudioPort[];
+  active_port: string | null;
+}
+
 export interface SystemControlsState {
   brightness: number;
   volume: number;